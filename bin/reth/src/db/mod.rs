@@ -70,6 +70,8 @@ pub struct Command {
 pub enum Subcommands {
     /// Lists all the tables, their entry count and their size
     Stats,
+    /// Prints the configuration and state of the database environment
+    Info,
     /// Lists the contents of a table
     List(list::Command),
     /// Create a diff between two database tables or two entire databases.
@@ -170,6 +172,23 @@ impl Command {
 
                 println!("{stats_table}");
             }
+            Subcommands::Info => {
+                let db = open_db_read_only(&db_path, self.db.log_level)?;
+                let info = db.env_info()?;
+
+                println!("Map size: {}", human_bytes(info.map_size as f64));
+                println!("Last transaction id: {}", info.last_txn_id);
+                println!("Last page number: {}", info.last_page_number);
+                println!("Readers: {}/{}", info.num_readers, info.max_readers);
+                println!(
+                    "Geometry: min {}, max {}, current {}, growth step {}, shrink threshold {}",
+                    human_bytes(info.geometry.min_size as f64),
+                    human_bytes(info.geometry.max_size as f64),
+                    human_bytes(info.geometry.current_size as f64),
+                    human_bytes(info.geometry.growth_step as f64),
+                    human_bytes(info.geometry.shrink_threshold as f64),
+                );
+            }
             Subcommands::List(command) => {
                 let db = open_db_read_only(&db_path, self.db.log_level)?;
                 let tool = DbTool::new(&db, self.chain.clone())?;
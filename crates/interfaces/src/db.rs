@@ -1,3 +1,5 @@
+use reth_primitives::H256;
+
 /// Database error type. It uses i32 to represent an error code.
 #[derive(Debug, thiserror::Error, PartialEq, Eq, Clone)]
 pub enum DatabaseError {
@@ -43,6 +45,64 @@ pub enum DatabaseError {
     /// Failed to use the specified log level, as it's not available.
     #[error("Log level is not available: {0:?}")]
     LogLevelUnavailable(LogLevel),
+    /// The page size is not supported by the database.
+    #[error("Invalid page size {0}, must be a power of two between 256 and 65536")]
+    InvalidPageSize(usize),
+    /// The genesis header of the database does not match the genesis of the chain.
+    #[error("Genesis hash mismatch: expected {expected:?}, found {found:?} in the database")]
+    GenesisMismatch {
+        /// The expected genesis hash
+        expected: H256,
+        /// The genesis hash found in the database
+        found: H256,
+    },
+    /// Entries that were expected in ascending key order are out of order.
+    #[error("Entries for table \"{table_name}\" are not sorted at key \"{key:?}\"")]
+    UnsortedEntries {
+        /// Table name
+        table_name: &'static str,
+        /// The first key that is not greater than its predecessor
+        key: Box<[u8]>,
+    },
+    /// The table is expected to be empty but contains entries.
+    #[error("Table \"{table_name}\" is not empty")]
+    TableNotEmpty {
+        /// Table name
+        table_name: &'static str,
+    },
+    /// An entry that is written must not exist yet.
+    #[error("Entry for key \"{key:?}\" already exists in table \"{table_name}\"")]
+    EntryExists {
+        /// Table name
+        table_name: &'static str,
+        /// Key of the existing entry
+        key: Box<[u8]>,
+    },
+    /// Adding to a numeric value overflowed.
+    #[error("Value overflow for key \"{key:?}\" in table \"{table_name}\"")]
+    ValueOverflow {
+        /// Table name
+        table_name: &'static str,
+        /// Key of the value
+        key: Box<[u8]>,
+    },
+    /// Subtracting from a numeric value underflowed.
+    #[error("Value underflow for key \"{key:?}\" in table \"{table_name}\"")]
+    ValueUnderflow {
+        /// Table name
+        table_name: &'static str,
+        /// Key of the value
+        key: Box<[u8]>,
+    },
+    /// Failed to compact the database.
+    #[error("Database compaction failed: {0}")]
+    Compaction(String),
+    /// The operation doesn't support `DUPSORT` tables.
+    #[error("Operation is not supported on DUPSORT table \"{table_name}\"")]
+    DupSortUnsupported {
+        /// Table name
+        table_name: &'static str,
+    },
 }
 
 /// Database write operation type
@@ -13,6 +13,10 @@ use std::collections::BTreeSet;
 pub struct AccessListInspector {
     /// All addresses that should be excluded from the final accesslist
     excluded: HashSet<Address>,
+    /// Addresses that are removed from the final accesslist, including their storage slots
+    removed: HashSet<Address>,
+    /// If set, the only addresses that are kept in the final accesslist
+    scope: Option<HashSet<Address>>,
     /// All addresses and touched slots
     access_list: HashMap<Address, BTreeSet<H256>>,
 }
@@ -29,6 +33,8 @@ impl AccessListInspector {
     ) -> Self {
         AccessListInspector {
             excluded: [from, to].iter().chain(precompiles.iter()).copied().collect(),
+            removed: HashSet::new(),
+            scope: None,
             access_list: access_list
                 .0
                 .iter()
@@ -37,23 +43,61 @@ impl AccessListInspector {
         }
     }
 
+    /// Removes the given addresses from the access list, including the storage slots accessed on
+    /// them.
+    ///
+    /// The accounts and slots are still accessed during execution, they are only left out of the
+    /// returned access list.
+    pub fn with_removed_addresses(mut self, addresses: impl IntoIterator<Item = Address>) -> Self {
+        self.removed.extend(addresses);
+        self
+    }
+
+    /// Includes the address in the access list if it is touched during execution, even if it is
+    /// excluded by default because it is the sender, the recipient or a precompile.
+    pub fn with_included_address(mut self, address: Address) -> Self {
+        self.excluded.remove(&address);
+        self
+    }
+
+    /// Restricts the access list to the given addresses and their storage slots.
+    ///
+    /// Like with [Self::with_removed_addresses], all other accounts and slots are still accessed
+    /// during execution, they are only left out of the returned access list. Addresses that are
+    /// excluded by default are not included by being in scope.
+    pub fn with_scope(mut self, addresses: impl IntoIterator<Item = Address>) -> Self {
+        self.scope.get_or_insert_with(HashSet::new).extend(addresses);
+        self
+    }
+
+    /// Returns `true` if the address is kept in the final access list.
+    fn is_listed(&self, address: &Address) -> bool {
+        !self.removed.contains(address) &&
+            self.scope.as_ref().map_or(true, |scope| scope.contains(address))
+    }
+
     /// Returns list of addresses and storage keys used by the transaction. It gives you the list of
     /// addresses and storage keys that were touched during execution.
-    pub fn into_access_list(self) -> AccessList {
-        let items = self.access_list.into_iter().map(|(address, slots)| AccessListItem {
-            address,
-            storage_keys: slots.into_iter().collect(),
-        });
+    pub fn into_access_list(mut self) -> AccessList {
+        let access_list = std::mem::take(&mut self.access_list);
+        let items = access_list.into_iter().filter(|(address, _)| self.is_listed(address)).map(
+            |(address, slots)| AccessListItem {
+                address,
+                storage_keys: slots.into_iter().collect(),
+            },
+        );
         AccessList(items.collect())
     }
 
     /// Returns list of addresses and storage keys used by the transaction. It gives you the list of
     /// addresses and storage keys that were touched during execution.
     pub fn access_list(&self) -> AccessList {
-        let items = self.access_list.iter().map(|(address, slots)| AccessListItem {
-            address: *address,
-            storage_keys: slots.iter().copied().collect(),
-        });
+        let items = self.access_list.iter().filter(|(address, _)| self.is_listed(address)).map(
+            |(address, slots)| AccessListItem {
+                address: *address,
+                storage_keys: slots.iter().copied().collect(),
+            },
+        );
         AccessList(items.collect())
     }
 }
@@ -1,4 +1,5 @@
 use crate::tracing::types::{CallTrace, CallTraceNode, LogCallOrder};
+use reth_primitives::Log;
 
 /// An arena of recorded traces.
 ///
@@ -56,6 +57,34 @@ impl CallTraceArena {
             }
         }
     }
+
+    /// Returns all recorded logs in the order they were emitted.
+    ///
+    /// Unlike the logs of a committed execution, this also includes logs emitted by calls that
+    /// reverted afterwards.
+    pub fn logs(&self) -> Vec<Log> {
+        let mut logs = Vec::new();
+        self.collect_logs(0, &mut logs);
+        logs
+    }
+
+    /// Appends the logs of the node and its children, in the order they were emitted.
+    fn collect_logs(&self, idx: usize, logs: &mut Vec<Log>) {
+        let node = &self.arena[idx];
+        for order in node.ordering.iter() {
+            match *order {
+                LogCallOrder::Log(log) => {
+                    let log = &node.logs[log];
+                    logs.push(Log {
+                        address: node.execution_address(),
+                        topics: log.topics.clone(),
+                        data: log.data.clone().into(),
+                    });
+                }
+                LogCallOrder::Call(child) => self.collect_logs(node.children[child], logs),
+            }
+        }
+    }
 }
 
 /// How to push a trace into the arena
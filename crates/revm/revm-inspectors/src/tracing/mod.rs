@@ -82,6 +82,11 @@ impl TracingInspector {
         }
     }
 
+    /// Returns the recorded traces.
+    pub fn traces(&self) -> &CallTraceArena {
+        &self.traces
+    }
+
     /// Consumes the Inspector and returns a [ParityTraceBuilder].
     pub fn into_parity_builder(self) -> ParityTraceBuilder {
         ParityTraceBuilder::new(self.traces.arena, self.spec_id, self.config)
@@ -258,13 +258,18 @@ pub struct BlockOverrides {
     pub number: Option<U256>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub difficulty: Option<U256>,
+    /// Overrides the block timestamp.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub time: Option<U64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub gas_limit: Option<U64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub coinbase: Option<Address>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Overrides the `prevrandao` value of the block, returned by the `PREVRANDAO` (formerly
+    /// `DIFFICULTY`) opcode. Also accepted as `prevRandao`.
+    ///
+    /// If not set, the value of the target block is used.
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "prevRandao")]
     pub random: Option<H256>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub base_fee: Option<U256>,
@@ -274,6 +279,20 @@ pub struct BlockOverrides {
 mod tests {
     use super::*;
 
+    #[test]
+    fn serde_block_overrides_prev_randao() {
+        let random = H256::from_low_u64_be(1);
+        let expected = BlockOverrides { random: Some(random), ..Default::default() };
+
+        let overrides: BlockOverrides =
+            serde_json::from_value(serde_json::json!({ "random": random })).unwrap();
+        assert_eq!(overrides, expected);
+
+        let overrides: BlockOverrides =
+            serde_json::from_value(serde_json::json!({ "prevRandao": random })).unwrap();
+        assert_eq!(overrides, expected);
+    }
+
     #[test]
     fn test_full_conversion() {
         let full = true;
@@ -21,6 +21,10 @@ pub struct StateContext {
     pub block_number: Option<BlockId>,
     /// Inclusive number of tx to replay in block. -1 means replay all
     pub transaction_index: Option<TransactionIndex>,
+    /// Whether to stop executing the bundle at the first transaction that fails.
+    ///
+    /// By default all transactions are executed and failures are reported per transaction.
+    pub stop_on_error: bool,
 }
 
 /// CallResponse for eth_callMany
@@ -33,6 +37,12 @@ pub struct EthCallResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// eth_call output (if error)
     pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Gas refunded by the EVM after the EIP-3529 cap (if no error)
+    pub gas_refunded: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Address of the created contract (if the transaction is a contract creation)
+    pub created_address: Option<Address>,
 }
 
 /// Represents a transaction index where -1 means all transactions
@@ -1,5 +1,5 @@
 pub use common::TransactionInfo;
-pub use receipt::TransactionReceipt;
+pub use receipt::{validate_cumulative_gas, ReceiptError, TransactionReceipt};
 pub use request::TransactionRequest;
 use reth_primitives::{AccessListItem, Address, Bytes, H256, U128, U256, U64};
 use serde::{Deserialize, Serialize};
@@ -1,5 +1,8 @@
 use crate::Log;
-use reth_primitives::{Address, Bloom, H256, U128, U256, U64, U8};
+use reth_primitives::{
+    contract::create_address, Address, Bloom, Receipt, TransactionKind, TransactionMeta,
+    TransactionSignedEcRecovered, H256, U128, U256, U64, U8,
+};
 use serde::{Deserialize, Serialize};
 
 /// Transaction receipt
@@ -30,6 +33,13 @@ pub struct TransactionReceipt {
     pub contract_address: Option<Address>,
     /// Logs emitted by this transaction.
     pub logs: Vec<Log>,
+    /// Index of the transaction's first log within the block, which is the number of logs
+    /// emitted by the preceding transactions of the block.
+    ///
+    /// Not part of the standard receipt, only set if requested when assembling the receipts of a
+    /// block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_log_index: Option<U256>,
     /// Logs bloom
     pub logs_bloom: Bloom,
     /// The post-transaction stateroot (pre Byzantium)
@@ -43,4 +53,357 @@ pub struct TransactionReceipt {
     /// EIP-2718 Transaction type, Some(1) for AccessList transaction, None for Legacy
     #[serde(rename = "type")]
     pub transaction_type: U8,
+    /// The fee paid for posting the transaction data to L1, in wei.
+    #[cfg(feature = "optimism")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub l1_fee: Option<U256>,
+    /// The L1 gas used for posting the transaction data, including the fixed overhead.
+    #[cfg(feature = "optimism")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub l1_gas_used: Option<U256>,
+    /// The L1 base fee at the time the transaction was included.
+    #[cfg(feature = "optimism")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub l1_gas_price: Option<U256>,
+}
+
+impl TransactionReceipt {
+    /// Creates the receipt of a transaction from the [Receipt] of its execution.
+    ///
+    /// `prev_cumulative_gas_used` is the cumulative gas used by the preceding transactions of the
+    /// block and `first_log_index` the index of the transaction's first log within the block,
+    /// which the log indices are derived from. [TransactionReceipt::first_log_index] is left unset,
+    /// see [TransactionReceipt::with_first_log_index].
+    pub fn from_execution(
+        transaction: &TransactionSignedEcRecovered,
+        receipt: Receipt,
+        prev_cumulative_gas_used: u64,
+        meta: TransactionMeta,
+        first_log_index: usize,
+    ) -> Self {
+        let from = transaction.signer();
+        let (to, contract_address) = match transaction.kind() {
+            TransactionKind::Create => (None, Some(create_address(from, transaction.nonce()))),
+            TransactionKind::Call(to) => (Some(*to), None),
+        };
+
+        let logs_bloom = receipt.bloom_slow();
+        let logs = receipt
+            .logs
+            .into_iter()
+            .enumerate()
+            .map(|(tx_log_idx, log)| Log {
+                address: log.address,
+                topics: log.topics,
+                data: log.data,
+                block_hash: Some(meta.block_hash),
+                block_number: Some(U256::from(meta.block_number)),
+                transaction_hash: Some(meta.tx_hash),
+                transaction_index: Some(U256::from(meta.index)),
+                log_index: Some(U256::from(first_log_index + tx_log_idx)),
+                removed: false,
+            })
+            .collect();
+
+        Self {
+            transaction_hash: Some(meta.tx_hash),
+            transaction_index: meta.index.into(),
+            block_hash: Some(meta.block_hash),
+            block_number: Some(U256::from(meta.block_number)),
+            cumulative_gas_used: U256::from(receipt.cumulative_gas_used),
+            gas_used: Some(U256::from(
+                receipt.cumulative_gas_used.saturating_sub(prev_cumulative_gas_used),
+            )),
+            effective_gas_price: U128::from(transaction.effective_gas_price(meta.base_fee)),
+            from,
+            to,
+            contract_address,
+            logs,
+            first_log_index: None,
+            logs_bloom,
+            // TODO pre-byzantium receipts have a post-transaction state root
+            state_root: None,
+            status_code: Some(U64::from(receipt.success as u8)),
+            transaction_type: transaction.tx_type().into(),
+            #[cfg(feature = "optimism")]
+            l1_fee: None,
+            #[cfg(feature = "optimism")]
+            l1_gas_used: None,
+            #[cfg(feature = "optimism")]
+            l1_gas_price: None,
+        }
+    }
+
+    /// Sets the non-standard [TransactionReceipt::first_log_index] field.
+    pub fn with_first_log_index(mut self, first_log_index: u64) -> Self {
+        self.first_log_index = Some(U256::from(first_log_index));
+        self
+    }
+
+    /// Sets the L1 data fee of the transaction from the inputs of the L1 fee oracle.
+    ///
+    /// `l1_gas_used` is the L1 gas used for the transaction data, including the fixed overhead,
+    /// `l1_gas_price` the L1 base fee and `l1_fee_scalar` the dynamic overhead with 6 decimals.
+    /// The fee is `l1_gas_used * l1_gas_price * l1_fee_scalar / 1e6`.
+    #[cfg(feature = "optimism")]
+    pub fn with_l1_fee(mut self, l1_gas_used: u64, l1_gas_price: U256, l1_fee_scalar: u64) -> Self {
+        let l1_gas_used = U256::from(l1_gas_used);
+        let scalar = U256::from(l1_fee_scalar);
+        let l1_fee = l1_gas_used.saturating_mul(l1_gas_price).saturating_mul(scalar);
+        self.l1_fee = Some(l1_fee / U256::from(1_000_000));
+        self.l1_gas_used = Some(l1_gas_used);
+        self.l1_gas_price = Some(l1_gas_price);
+        self
+    }
+
+    /// Returns the priority fee per gas that was actually paid, which is the effective gas price
+    /// minus the `base_fee` of the block, or zero if the effective gas price is below the base fee.
+    ///
+    /// For legacy transactions this is the gas price minus the base fee.
+    pub fn effective_tip(&self, base_fee: U256) -> U256 {
+        U256::from(self.effective_gas_price.to::<u128>()).saturating_sub(base_fee)
+    }
+}
+
+/// Error returned when a list of receipts is inconsistent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ReceiptError {
+    /// The cumulative gas used of the receipt is lower than the one of the previous receipt.
+    #[error("cumulative gas used decreased at receipt {index}")]
+    CumulativeGasDecreased {
+        /// Index of the offending receipt.
+        index: usize,
+    },
+    /// The difference to the previous cumulative gas used does not match the receipt's gas used.
+    #[error("cumulative gas used does not match gas used at receipt {index}")]
+    GasUsedMismatch {
+        /// Index of the offending receipt.
+        index: usize,
+    },
+}
+
+impl ReceiptError {
+    /// Returns the index of the receipt that broke the invariant.
+    pub fn index(&self) -> usize {
+        match self {
+            ReceiptError::CumulativeGasDecreased { index } |
+            ReceiptError::GasUsedMismatch { index } => *index,
+        }
+    }
+}
+
+/// Validates the `cumulative_gas_used` of a block's receipts.
+///
+/// The cumulative gas used must be non-decreasing and the difference between consecutive receipts
+/// must equal the receipt's `gas_used`. For the first receipt the cumulative gas used must equal
+/// its `gas_used`. Receipts without `gas_used` are only checked for monotonicity.
+///
+/// Returns the first receipt that breaks the invariant.
+pub fn validate_cumulative_gas(receipts: &[TransactionReceipt]) -> Result<(), ReceiptError> {
+    let mut previous = U256::ZERO;
+    for (index, receipt) in receipts.iter().enumerate() {
+        let cumulative = receipt.cumulative_gas_used;
+        if cumulative < previous {
+            return Err(ReceiptError::CumulativeGasDecreased { index })
+        }
+        if let Some(gas_used) = receipt.gas_used {
+            if cumulative - previous != gas_used {
+                return Err(ReceiptError::GasUsedMismatch { index })
+            }
+        }
+        previous = cumulative;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::{Bytes, Signature, Transaction, TransactionSigned, TxEip1559, TxType};
+
+    fn receipt(cumulative_gas_used: u64, gas_used: Option<u64>) -> TransactionReceipt {
+        TransactionReceipt {
+            transaction_hash: None,
+            transaction_index: U64::zero(),
+            block_hash: None,
+            block_number: None,
+            cumulative_gas_used: U256::from(cumulative_gas_used),
+            gas_used: gas_used.map(U256::from),
+            effective_gas_price: U128::ZERO,
+            from: Address::zero(),
+            to: None,
+            contract_address: None,
+            logs: vec![],
+            first_log_index: None,
+            logs_bloom: Bloom::default(),
+            state_root: None,
+            status_code: None,
+            transaction_type: U8::ZERO,
+            #[cfg(feature = "optimism")]
+            l1_fee: None,
+            #[cfg(feature = "optimism")]
+            l1_gas_used: None,
+            #[cfg(feature = "optimism")]
+            l1_gas_price: None,
+        }
+    }
+
+    fn executed(
+        kind: TransactionKind,
+        success: bool,
+        logs: Vec<reth_primitives::Log>,
+    ) -> (TransactionSignedEcRecovered, Receipt) {
+        let transaction = Transaction::Eip1559(TxEip1559 {
+            chain_id: 1,
+            nonce: 3,
+            gas_limit: 100_000,
+            max_fee_per_gas: 30,
+            max_priority_fee_per_gas: 2,
+            to: kind,
+            ..Default::default()
+        });
+        let signed =
+            TransactionSigned::from_transaction_and_signature(transaction, Signature::default());
+        let receipt =
+            Receipt { tx_type: TxType::EIP1559, success, cumulative_gas_used: 71_000, logs };
+        (TransactionSignedEcRecovered::from_signed_transaction(signed, Address::random()), receipt)
+    }
+
+    #[test]
+    fn effective_tip_of_eip1559_receipt() {
+        let (transaction, receipt) =
+            executed(TransactionKind::Call(Address::random()), true, vec![]);
+        let meta = TransactionMeta { base_fee: Some(10), ..Default::default() };
+        let receipt = TransactionReceipt::from_execution(&transaction, receipt, 0, meta, 0);
+
+        // the max priority fee fits below the max fee
+        assert_eq!(receipt.effective_gas_price, U128::from(12));
+        assert_eq!(receipt.effective_tip(U256::from(10)), U256::from(2));
+    }
+
+    #[test]
+    fn effective_tip_of_legacy_receipt() {
+        let receipt = TransactionReceipt {
+            effective_gas_price: U128::from(20),
+            ..receipt(21_000, Some(21_000))
+        };
+        assert_eq!(receipt.effective_tip(U256::from(15)), U256::from(5));
+        assert_eq!(receipt.effective_tip(U256::ZERO), U256::from(20));
+        // clamped at zero if the gas price is below the base fee
+        assert_eq!(receipt.effective_tip(U256::from(25)), U256::ZERO);
+    }
+
+    #[test]
+    fn receipt_from_contract_creation() {
+        let log = reth_primitives::Log {
+            address: Address::random(),
+            topics: vec![H256::random()],
+            data: Bytes::from(vec![1]),
+        };
+        let (transaction, receipt) = executed(TransactionKind::Create, true, vec![log.clone()]);
+        let meta = TransactionMeta {
+            tx_hash: transaction.hash,
+            index: 2,
+            block_hash: H256::random(),
+            block_number: 100,
+            base_fee: Some(10),
+        };
+
+        let rpc_receipt =
+            TransactionReceipt::from_execution(&transaction, receipt.clone(), 21_000, meta, 5);
+
+        assert_eq!(rpc_receipt.from, transaction.signer());
+        assert_eq!(rpc_receipt.to, None);
+        assert_eq!(rpc_receipt.contract_address, Some(create_address(transaction.signer(), 3)));
+        assert_eq!(rpc_receipt.status_code, Some(U64::from(1)));
+        assert_eq!(rpc_receipt.cumulative_gas_used, U256::from(71_000));
+        assert_eq!(rpc_receipt.gas_used, Some(U256::from(50_000)));
+        // base fee + priority fee
+        assert_eq!(rpc_receipt.effective_gas_price, U128::from(12));
+        assert_eq!(rpc_receipt.transaction_type, U8::from(2));
+        assert_eq!(rpc_receipt.logs_bloom, receipt.bloom_slow());
+        assert_ne!(rpc_receipt.logs_bloom, Bloom::default());
+        assert_eq!(rpc_receipt.logs.len(), 1);
+        assert_eq!(rpc_receipt.logs[0].address, log.address);
+        assert_eq!(rpc_receipt.logs[0].log_index, Some(U256::from(5)));
+        assert_eq!(rpc_receipt.logs[0].transaction_index, Some(U256::from(2)));
+        assert_eq!(rpc_receipt.logs[0].block_hash, Some(meta.block_hash));
+    }
+
+    #[test]
+    fn receipt_from_failed_transaction() {
+        let to = Address::random();
+        let (transaction, receipt) = executed(TransactionKind::Call(to), false, vec![]);
+        let meta = TransactionMeta { tx_hash: transaction.hash, ..Default::default() };
+
+        let rpc_receipt = TransactionReceipt::from_execution(&transaction, receipt, 0, meta, 0);
+
+        assert_eq!(rpc_receipt.to, Some(to));
+        assert_eq!(rpc_receipt.contract_address, None);
+        assert_eq!(rpc_receipt.status_code, Some(U64::from(0)));
+        assert_eq!(rpc_receipt.gas_used, Some(U256::from(71_000)));
+        // no base fee, the max fee is paid
+        assert_eq!(rpc_receipt.effective_gas_price, U128::from(30));
+        assert!(rpc_receipt.logs.is_empty());
+        assert_eq!(rpc_receipt.logs_bloom, Bloom::default());
+    }
+
+    #[test]
+    fn valid_cumulative_gas() {
+        assert_eq!(validate_cumulative_gas(&[]), Ok(()));
+
+        let receipts = [receipt(21_000, Some(21_000)), receipt(50_000, Some(29_000))];
+        assert_eq!(validate_cumulative_gas(&receipts), Ok(()));
+
+        // missing gas used is only checked for monotonicity
+        let receipts =
+            [receipt(21_000, Some(21_000)), receipt(40_000, None), receipt(40_000, None)];
+        assert_eq!(validate_cumulative_gas(&receipts), Ok(()));
+    }
+
+    #[test]
+    fn invalid_cumulative_gas() {
+        let receipts = [receipt(21_000, Some(20_000))];
+        assert_eq!(
+            validate_cumulative_gas(&receipts),
+            Err(ReceiptError::GasUsedMismatch { index: 0 })
+        );
+
+        let receipts = [receipt(21_000, Some(21_000)), receipt(42_000, Some(20_000))];
+        assert_eq!(
+            validate_cumulative_gas(&receipts),
+            Err(ReceiptError::GasUsedMismatch { index: 1 })
+        );
+
+        let receipts =
+            [receipt(21_000, Some(21_000)), receipt(42_000, None), receipt(30_000, None)];
+        let err = validate_cumulative_gas(&receipts).unwrap_err();
+        assert_eq!(err, ReceiptError::CumulativeGasDecreased { index: 2 });
+        assert_eq!(err.index(), 2);
+    }
+
+    #[test]
+    fn receipt_without_l1_fee_omits_fields() {
+        let json = serde_json::to_value(receipt(21_000, Some(21_000))).unwrap();
+        assert!(json.get("l1Fee").is_none());
+        assert!(json.get("l1GasUsed").is_none());
+        assert!(json.get("l1GasPrice").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "optimism")]
+    fn serde_optimism_receipt_l1_fee() {
+        // 1_000 gas at 10 gwei with a scalar of 0.684
+        let gas_price = U256::from(10_000_000_000u64);
+        let receipt = receipt(21_000, Some(21_000)).with_l1_fee(1_000, gas_price, 684_000);
+        assert_eq!(receipt.l1_fee, Some(U256::from(6_840_000_000_000u64)));
+
+        let json = serde_json::to_value(&receipt).unwrap();
+        assert_eq!(json["l1Fee"], "0x6388fc53000");
+        assert_eq!(json["l1GasUsed"], "0x3e8");
+        assert_eq!(json["l1GasPrice"], "0x2540be400");
+
+        let decoded: TransactionReceipt = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, receipt);
+    }
 }
@@ -348,7 +348,8 @@ where
             return Err(EthApiError::InvalidParams(String::from("bundles are empty.")))
         }
 
-        let StateContext { transaction_index, block_number } = state_context.unwrap_or_default();
+        let StateContext { transaction_index, block_number, .. } =
+            state_context.unwrap_or_default();
         let transaction_index = transaction_index.unwrap_or_default();
 
         let target_block = block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
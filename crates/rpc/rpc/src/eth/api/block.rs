@@ -1,14 +1,16 @@
 //! Contains RPC handler implementations specific to blocks.
 
+#[cfg(feature = "optimism")]
+use crate::eth::optimism::L1BlockInfo;
 use crate::{
     eth::{
-        api::transactions::build_transaction_receipt_with_block_receipts,
+        api::transactions::build_block_receipts,
         error::{EthApiError, EthResult},
     },
     EthApi,
 };
 use reth_network_api::NetworkInfo;
-use reth_primitives::{BlockId, BlockNumberOrTag, TransactionMeta};
+use reth_primitives::{BlockId, BlockNumberOrTag, H256};
 
 use reth_provider::{BlockReaderIdExt, ChainSpecProvider, EvmEnvProvider, StateProviderFactory};
 use reth_rpc_types::{Index, RichBlock, TransactionReceipt};
@@ -60,6 +62,24 @@ where
     pub(crate) async fn block_receipts(
         &self,
         number: BlockNumberOrTag,
+    ) -> EthResult<Option<Vec<TransactionReceipt>>> {
+        self.block_receipts_with(number, false).await
+    }
+
+    /// Same as [Self::block_receipts] but every receipt carries the non-standard
+    /// [TransactionReceipt::first_log_index], the index of its first log within the block.
+    pub async fn block_receipts_with_first_log_index(
+        &self,
+        number: BlockNumberOrTag,
+    ) -> EthResult<Option<Vec<TransactionReceipt>>> {
+        self.block_receipts_with(number, true).await
+    }
+
+    /// Returns all transaction receipts in the block, optionally with their first log index.
+    async fn block_receipts_with(
+        &self,
+        number: BlockNumberOrTag,
+        include_first_log_index: bool,
     ) -> EthResult<Option<Vec<TransactionReceipt>>> {
         let mut block_and_receipts = None;
 
@@ -70,26 +90,20 @@ where
         }
 
         if let Some((block, receipts)) = block_and_receipts {
-            let block_number = block.number;
-            let base_fee = block.base_fee_per_gas;
-            let block_hash = block.hash;
-            let receipts = block
-                .body
-                .into_iter()
-                .zip(receipts.clone())
-                .enumerate()
-                .map(|(idx, (tx, receipt))| {
-                    let meta = TransactionMeta {
-                        tx_hash: tx.hash,
-                        index: idx as u64,
-                        block_hash,
-                        block_number,
-                        base_fee,
-                    };
-                    build_transaction_receipt_with_block_receipts(tx, meta, receipt, &receipts)
-                })
-                .collect::<EthResult<Vec<_>>>();
-            return receipts.map(Some)
+            // the pending block is not stored yet, its L1 fee inputs are read from its parent
+            #[cfg(feature = "optimism")]
+            let l1_block_info = {
+                let at = if number.is_pending() { block.parent_hash } else { block.hash };
+                L1BlockInfo::from_state(&*self.state_at_hash(at)?)?
+            };
+            let (receipts, _) = build_block_receipts(
+                block,
+                receipts,
+                include_first_log_index,
+                #[cfg(feature = "optimism")]
+                &l1_block_info,
+            )?;
+            return Ok(Some(receipts))
         }
 
         Ok(None)
@@ -117,6 +131,16 @@ where
         Ok(self.cache().get_block_transactions(block_hash).await?.map(|txs| txs.len()))
     }
 
+    /// Returns the state root of the block with the given id.
+    ///
+    /// Returns [EthApiError::UnknownBlockNumber] if the block doesn't exist.
+    pub fn state_root_at(&self, at: BlockId) -> EthResult<H256> {
+        self.provider()
+            .header_by_id(at)?
+            .map(|header| header.state_root)
+            .ok_or(EthApiError::UnknownBlockNumber)
+    }
+
     /// Returns the block object for the given block id.
     pub(crate) async fn block(
         &self,
@@ -164,3 +188,47 @@ where
         Ok(Some(block.into()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eth::{cache::EthStateCache, gas_oracle::GasPriceOracle},
+        TracingCallPool,
+    };
+    use assert_matches::assert_matches;
+    use reth_network_api::noop::NoopNetwork;
+    use reth_primitives::{constants::ETHEREUM_BLOCK_GAS_LIMIT, Block, Header};
+    use reth_provider::test_utils::MockEthProvider;
+    use reth_transaction_pool::test_utils::testing_pool;
+
+    #[tokio::test]
+    async fn state_root_at_block() {
+        let provider = MockEthProvider::default();
+        let state_roots = [H256::random(), H256::random()];
+        for (number, state_root) in [1u64, 2].into_iter().zip(state_roots) {
+            let header = Header { number, state_root, ..Default::default() };
+            provider.add_block(H256::random(), Block { header, ..Default::default() });
+        }
+
+        let cache = EthStateCache::spawn(provider.clone(), Default::default());
+        let eth_api = EthApi::new(
+            provider.clone(),
+            testing_pool(),
+            NoopNetwork::default(),
+            cache.clone(),
+            GasPriceOracle::new(provider, Default::default(), cache),
+            ETHEREUM_BLOCK_GAS_LIMIT,
+            TracingCallPool::build().expect("failed to build tracing pool"),
+        );
+
+        let latest = eth_api.state_root_at(BlockNumberOrTag::Latest.into()).unwrap();
+        assert_eq!(latest, state_roots[1]);
+        let historical = eth_api.state_root_at(BlockNumberOrTag::Number(1).into()).unwrap();
+        assert_eq!(historical, state_roots[0]);
+        assert_matches!(
+            eth_api.state_root_at(BlockNumberOrTag::Number(3).into()),
+            Err(EthApiError::UnknownBlockNumber)
+        );
+    }
+}
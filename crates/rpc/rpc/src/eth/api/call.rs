@@ -2,10 +2,17 @@
 
 use crate::{
     eth::{
-        error::{ensure_success, EthApiError, EthResult, RevertError, RpcInvalidTransactionError},
+        api::transactions::StateCacheDB,
+        error::{
+            ensure_success, EstimateGasError, EthApiError, EthResult, RevertError,
+            RpcInvalidTransactionError,
+        },
         revm_utils::{
-            build_call_evm_env, caller_gas_allowance, cap_tx_gas_limit_with_caller_allowance,
-            get_precompiles, inspect, prepare_call_env, transact, EvmOverrides,
+            access_list_intrinsic_gas, apply_state_overrides, build_call_evm_env,
+            caller_gas_allowance, cap_tx_gas_limit_with_caller_allowance, configure_eip3607,
+            ensure_fee_cap_covers_base_fee, get_precompiles, inspect, inspect_with_limits,
+            intrinsic_gas, prepare_call_env, transact, transact_with_limits, validate_call_request,
+            CallLimits, EvmOverrides,
         },
         EthTransactions,
     },
@@ -13,7 +20,11 @@ use crate::{
 };
 use ethers_core::utils::get_contract_address;
 use reth_network_api::NetworkInfo;
-use reth_primitives::{AccessList, BlockId, BlockNumberOrTag, Bytes, U256};
+use reth_primitives::{
+    basefee::calculate_next_block_base_fee, bloom::logs_bloom, bytes, constants::SLOT_DURATION,
+    AccessList, AccessListWithGasUsed, Address, BaseFeeParams, BlockId, BlockNumberOrTag, Bloom,
+    Bytes, IntoRecoveredTransaction, Log, H256, U256,
+};
 use reth_provider::{
     BlockReaderIdExt, ChainSpecProvider, EvmEnvProvider, StateProvider, StateProviderFactory,
 };
@@ -21,21 +32,246 @@ use reth_revm::{
     access_list::AccessListInspector,
     database::{State, SubState},
     env::tx_env_with_recovered,
+    tracing::{TracingInspector, TracingInspectorConfig},
 };
 use reth_rpc_types::{
-    state::StateOverride, BlockError, Bundle, CallRequest, EthCallResponse, StateContext,
+    state::StateOverride,
+    trace::geth::{CallConfig, CallFrame},
+    BlockError, BlockOverrides, Bundle, CallRequest, EthCallResponse, StateContext,
+};
+use reth_transaction_pool::{
+    BestTransactions, PoolTransaction, TransactionPool, ValidPoolTransaction,
 };
-use reth_transaction_pool::TransactionPool;
 use revm::{
     db::{CacheDB, DatabaseRef},
-    primitives::{BlockEnv, CfgEnv, Env, ExecutionResult, Halt, TransactTo},
-    DatabaseCommit,
+    interpreter::{opcode, CallInputs, CreateInputs, Gas, InstructionResult, Interpreter},
+    primitives::{BlockEnv, CfgEnv, Env, ExecutionResult, Halt, SpecId, TransactTo},
+    Database, DatabaseCommit, EVMData, Inspector,
 };
+use std::{collections::HashSet, sync::Arc};
 use tracing::trace;
 
 // Gas per transaction not creating a contract.
 const MIN_TRANSACTION_GAS: u64 = 21_000u64;
-const MIN_CREATE_GAS: u64 = 53_000u64;
+
+/// The maximum number of times a transaction is executed to find a stable access list.
+const MAX_ACCESS_LIST_ITERATIONS: usize = 10;
+
+/// The result of a gas estimation that includes a recommended gas limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasEstimate {
+    /// The lowest gas limit the call succeeds with.
+    pub exact: U256,
+    /// The exact estimate plus a safety margin, capped by the block gas limit.
+    pub recommended: U256,
+}
+
+impl GasEstimate {
+    /// Creates a new [GasEstimate] by adding `margin_percent` percent on top of the `exact`
+    /// estimate.
+    ///
+    /// The recommended gas limit never exceeds the `block_gas_limit`, but is also never lower than
+    /// the `exact` estimate. A margin of zero yields `recommended == exact`.
+    pub fn new(exact: U256, margin_percent: u64, block_gas_limit: U256) -> Self {
+        let margin = exact.saturating_mul(U256::from(margin_percent)) / U256::from(100);
+        let recommended = exact.saturating_add(margin).min(block_gas_limit).max(exact);
+        Self { exact, recommended }
+    }
+}
+
+/// A block of calls executed by [EthApi::simulate_blocks].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimulatedBlock {
+    /// The number of the simulated block.
+    pub number: u64,
+    /// The timestamp of the simulated block.
+    pub timestamp: u64,
+    /// The base fee the calls of the block were executed with.
+    pub base_fee: U256,
+    /// The total gas used by the calls of the block.
+    pub gas_used: u64,
+    /// The responses of the calls, in order.
+    pub calls: Vec<EthCallResponse>,
+}
+
+/// A gas estimate together with the intrinsic gas of the request's access list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerboseGasEstimate {
+    /// The estimated gas, including the intrinsic gas of the access list.
+    pub gas: U256,
+    /// The intrinsic gas charged for the access list of the request.
+    pub access_list_gas: u64,
+}
+
+/// A gas estimate of a binary search that may have been stopped early, see
+/// [EthApi::estimate_gas_bounded_at].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundedGasEstimate {
+    /// The estimated gas.
+    ///
+    /// If the search was truncated this is the lowest gas limit the call was found to succeed
+    /// with so far, which over-estimates the gas the call needs.
+    pub gas: U256,
+    /// Whether the search was stopped after the maximum number of iterations.
+    pub truncated: bool,
+}
+
+/// The output of a call together with the gas it ran with, see [EthApi::call_with_gas].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallWithGas {
+    /// The output of the call.
+    pub output: Bytes,
+    /// The gas limit the call was executed with.
+    pub gas_limit: u64,
+    /// The gas used by the call, including the intrinsic gas and refunds.
+    pub gas_used: u64,
+    /// The gas the EVM had left once the call completed, before refunds were applied.
+    ///
+    /// `gas_limit - gas_left` is the gas the call spent, which exceeds `gas_used` by the refund.
+    pub gas_left: u64,
+}
+
+/// The gas used by a call, bucketed by the class of the opcodes that consumed it.
+///
+/// Gas consumed by the code executed in a call or create is attributed to the opcodes of that
+/// code, the call and create buckets only contain the costs of the call and create opcodes
+/// themselves.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasProfile {
+    /// The total gas used by the call, including the intrinsic gas and refunds.
+    pub gas_used: u64,
+    /// Gas consumed by `SLOAD` and `SSTORE`.
+    pub storage: u64,
+    /// Gas consumed by memory reads, writes and copies into memory.
+    pub memory: u64,
+    /// Gas consumed by calls, creates and self destructs.
+    pub calls: u64,
+    /// Gas consumed by `LOG` opcodes.
+    pub logs: u64,
+    /// Gas consumed by all other opcodes.
+    pub compute: u64,
+}
+
+/// The number of unique accounts and storage slots accessed by a call.
+///
+/// An account or slot is cold if it wasn't warm at the start of the call, see EIP-2929. The sender,
+/// the callee, the precompiles, the coinbase since Shanghai, and the entries of the request's
+/// access list start out warm.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessCounts {
+    /// The number of accessed accounts.
+    pub accounts: usize,
+    /// The number of accessed storage slots.
+    pub slots: usize,
+    /// The number of accessed accounts that were cold.
+    pub cold_accounts: usize,
+    /// The number of accessed storage slots that were cold.
+    pub cold_slots: usize,
+}
+
+/// Options for creating an access list with [EthApi::create_access_list_with_options_at].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessListOptions {
+    /// Addresses that are left out of the access list, including the storage slots accessed on
+    /// them.
+    pub exclude: Vec<Address>,
+    /// If set, the access list only includes these addresses and the storage slots accessed on
+    /// them, which allows creating an access list for specific contracts of a call.
+    ///
+    /// Addresses that are left out by default, like the sender and the precompiles, are not added
+    /// by being in scope.
+    pub scope: Option<Vec<Address>>,
+    /// Whether the sender is included in the access list if it is accessed during execution.
+    ///
+    /// Like the recipient and the precompiles, the sender is left out by default.
+    pub include_sender: bool,
+    /// Gas limit the transaction is executed with while collecting the access list.
+    ///
+    /// If execution runs out of gas, the accesses that occurred up to that point are returned
+    /// instead of an error. By default the gas limit of the request is used.
+    pub gas_limit_override: Option<u64>,
+}
+
+/// Options for estimating gas with [EthApi::estimate_gas_with_options_at].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EstimateGasOptions {
+    /// Whether the fee cap of the request must cover the base fee of the block, as it would for a
+    /// mined transaction.
+    ///
+    /// By default the base fee is ignored, so an underpriced legacy or EIP-1559 request is
+    /// estimated like any other request.
+    pub strict_fees: bool,
+    /// Whether the EIP-3607 check is enforced, so requests from senders that have code are
+    /// rejected.
+    ///
+    /// This is the same setting as [EvmOverrides::enforce_eip3607], disabled by default.
+    pub enforce_eip3607: bool,
+}
+
+/// Options for the call tree returned by [EthApi::call_with_calls_options].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallTreeOptions {
+    /// Whether every [CallFrame] includes the data its call returned to the caller as `output`.
+    ///
+    /// The output of a reverted frame is its revert data, such frames can be told apart by their
+    /// `error`. Outputs are left out by default since they can be large.
+    pub include_outputs: bool,
+}
+
+/// The responses of a simulated bundle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallManyResult {
+    /// The responses of the executed transactions, in bundle order.
+    pub responses: Vec<EthCallResponse>,
+    /// The index of the transaction that failed, if execution stopped early because of
+    /// [StateContext::stop_on_error].
+    pub failed_index: Option<usize>,
+}
+
+/// A stateful `eth_call` session at a fixed block, see [EthApi::call_session].
+///
+/// Every call of the session is executed on top of the state changes of all previous calls of the
+/// session. The state overrides the session was created with are applied once, before the first
+/// call.
+pub struct CallSession<'a> {
+    db: StateCacheDB<'a>,
+    cfg: CfgEnv,
+    block_env: BlockEnv,
+    gas_limit: u64,
+    overrides: EvmOverrides,
+}
+
+impl CallSession<'_> {
+    /// Executes the call request on top of the session's state and returns its output.
+    ///
+    /// The state changes of the call are committed into the session, even if the call failed.
+    /// Requests with conflicting or unsupported fields are rejected before they are executed.
+    pub fn call(&mut self, request: CallRequest) -> EthResult<Bytes> {
+        validate_call_request(&request, self.cfg.spec_id)?;
+        let env = prepare_call_env(
+            self.cfg.clone(),
+            self.block_env.clone(),
+            request,
+            self.gas_limit,
+            &mut self.db,
+            self.overrides.clone(),
+        )?;
+        let (res, _) = transact_with_limits(&mut self.db, env, self.overrides.limits)?;
+        self.db.commit(res.state);
+
+        ensure_success(res.result)
+    }
+}
+
+impl std::fmt::Debug for CallSession<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallSession")
+            .field("cfg", &self.cfg)
+            .field("block_env", &self.block_env)
+            .field("gas_limit", &self.gas_limit)
+            .finish_non_exhaustive()
+    }
+}
 
 impl<Provider, Pool, Network> EthApi<Provider, Pool, Network>
 where
@@ -44,14 +280,140 @@ where
         BlockReaderIdExt + ChainSpecProvider + StateProviderFactory + EvmEnvProvider + 'static,
     Network: NetworkInfo + Send + Sync + 'static,
 {
+    /// Returns the [SpecId] the EVM is configured with for the block at the given [BlockId].
+    ///
+    /// This resolves the block's environment the same way calls at that block do.
+    pub async fn spec_id_at(&self, at: BlockId) -> EthResult<SpecId> {
+        let (cfg, _, _) = self.evm_env_at(at).await?;
+        Ok(cfg.spec_id)
+    }
+
     /// Estimate gas needed for execution of the `request` at the [BlockId].
+    ///
+    /// The binary search is executed on the blocking pool, so expensive estimates don't block the
+    /// async runtime.
     pub async fn estimate_gas_at(&self, request: CallRequest, at: BlockId) -> EthResult<U256> {
-        let (cfg, block_env, at) = self.evm_env_at(at).await?;
-        let state = self.state_at(at)?;
-        self.estimate_gas_with(cfg, block_env, request, state)
+        self.estimate_gas_with_options_at(request, at, Default::default()).await
+    }
+
+    /// Same as [Self::estimate_gas_at] but also returns the intrinsic gas charged for the
+    /// access list of the `request`, which is included in the estimate.
+    pub async fn estimate_gas_verbose_at(
+        &self,
+        request: CallRequest,
+        at: BlockId,
+    ) -> EthResult<VerboseGasEstimate> {
+        let access_list_gas =
+            request.access_list.as_ref().map(access_list_intrinsic_gas).unwrap_or_default();
+        let gas = self.estimate_gas_at(request, at).await?;
+        Ok(VerboseGasEstimate { gas, access_list_gas })
+    }
+
+    /// Same as [Self::estimate_gas_at] but stops the binary search for the lowest gas limit after
+    /// `max_iterations` executions of the call.
+    ///
+    /// If the search is stopped early, the lowest gas limit the call succeeded with so far is
+    /// returned, which is a safe over-estimate. `None` searches until the exact estimate is found.
+    pub async fn estimate_gas_bounded_at(
+        &self,
+        request: CallRequest,
+        at: BlockId,
+        max_iterations: Option<usize>,
+    ) -> EthResult<BoundedGasEstimate> {
+        let (mut cfg, block_env, at) = self.evm_env_at(at).await?;
+        validate_call_request(&request, cfg.spec_id)?;
+        configure_eip3607(&mut cfg, false);
+        let this = self.clone();
+        self.spawn_with_state_at_block(at, move |state| {
+            let db = SubState::new(State::new(state));
+            this.estimate_gas_bounded_with_db(cfg, block_env, request, db, max_iterations)
+        })
+        .await
+    }
+
+    /// Same as [Self::estimate_gas_at] but configured by the given [EstimateGasOptions].
+    ///
+    /// With [EstimateGasOptions::strict_fees] the request is rejected with
+    /// [RpcInvalidTransactionError::FeeCapTooLow] if its fee cap is below the base fee of the
+    /// block.
+    pub async fn estimate_gas_with_options_at(
+        &self,
+        request: CallRequest,
+        at: BlockId,
+        options: EstimateGasOptions,
+    ) -> EthResult<U256> {
+        let (mut cfg, block_env, at) = self.evm_env_at(at).await?;
+        validate_call_request(&request, cfg.spec_id)?;
+        configure_eip3607(&mut cfg, options.enforce_eip3607);
+        if options.strict_fees {
+            ensure_fee_cap_covers_base_fee(&request, block_env.basefee)?;
+        }
+
+        let this = self.clone();
+        self.spawn_with_state_at_block(at, move |state| {
+            this.estimate_gas_with(cfg, block_env, request, state)
+        })
+        .await
+    }
+
+    /// Estimate gas needed for execution of the `request` at the [BlockId], on top of the pending
+    /// transactions of the pool if `at` is the pending block.
+    ///
+    /// [Self::estimate_gas_at] estimates against the latest committed state for the pending block.
+    /// This first executes the best pending transactions of the pool, so the estimate reflects
+    /// nonce and balance changes of transactions that are about to be mined. For any other block
+    /// this is the same as [Self::estimate_gas_at].
+    pub async fn estimate_gas_with_pending_txs_at(
+        &self,
+        request: CallRequest,
+        at: BlockId,
+    ) -> EthResult<U256> {
+        if !at.is_pending() {
+            return self.estimate_gas_at(request, at).await
+        }
+
+        let (mut cfg, block_env, at) = self.evm_env_at(at).await?;
+        validate_call_request(&request, cfg.spec_id)?;
+        configure_eip3607(&mut cfg, false);
+        let this = self.clone();
+        self.spawn_with_state_at_block(at, move |state| {
+            let mut db = SubState::new(State::new(state));
+
+            let mut pending =
+                this.pool().best_transactions_with_base_fee(block_env.basefee.to::<u64>());
+            apply_transactions(&cfg, &block_env, &mut db, &mut *pending)?;
+
+            this.estimate_gas_with_db(cfg, block_env, request, db)
+        })
+        .await
+    }
+
+    /// Estimate gas needed for execution of the `request` at the [BlockId] and additionally
+    /// returns a recommended gas limit that includes a safety margin of `margin_percent` percent.
+    ///
+    /// See also [GasEstimate::new]
+    pub async fn estimate_gas_with_margin_at(
+        &self,
+        request: CallRequest,
+        at: BlockId,
+        margin_percent: u64,
+    ) -> EthResult<GasEstimate> {
+        let (mut cfg, block_env, at) = self.evm_env_at(at).await?;
+        validate_call_request(&request, cfg.spec_id)?;
+        configure_eip3607(&mut cfg, false);
+        let block_gas_limit = block_env.gas_limit;
+        let this = self.clone();
+        let exact = self
+            .spawn_with_state_at_block(at, move |state| {
+                this.estimate_gas_with(cfg, block_env, request, state)
+            })
+            .await?;
+        Ok(GasEstimate::new(exact, margin_percent, block_gas_limit))
     }
 
     /// Executes the call request (`eth_call`) and returns the output
+    ///
+    /// Requests with conflicting or unsupported fields are rejected before they are executed.
     pub async fn call(
         &self,
         request: CallRequest,
@@ -69,20 +431,388 @@ where
         ensure_success(res.result)
     }
 
+    /// Executes the call request (`eth_call`) and returns its output together with the gas limit
+    /// it was executed with and the gas left at completion.
+    ///
+    /// This is cheaper than a [GasProfile] if only the utilization of the gas limit is of
+    /// interest.
+    pub async fn call_with_gas(
+        &self,
+        request: CallRequest,
+        block_number: Option<BlockId>,
+        overrides: EvmOverrides,
+    ) -> EthResult<CallWithGas> {
+        let (res, env) = self
+            .transact_call_at(
+                request,
+                block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest)),
+                overrides,
+            )
+            .await?;
+
+        let gas_limit = env.tx.gas_limit;
+        let gas_used = res.result.gas_used();
+        let gas_refunded = match res.result {
+            ExecutionResult::Success { gas_refunded, .. } => gas_refunded,
+            _ => 0,
+        };
+        let output = ensure_success(res.result)?;
+        let gas_left = gas_limit - gas_used - gas_refunded;
+        Ok(CallWithGas { output, gas_limit, gas_used, gas_left })
+    }
+
+    /// Executes a batch of call requests (`eth_call`) at the same block and returns the output of
+    /// every call, in order.
+    ///
+    /// The env and the state of the block are resolved only once and shared by all calls of the
+    /// batch, each call is executed against its own copy of the state. The state overrides are
+    /// applied once for the whole batch. A failing call doesn't abort the batch, its error is
+    /// returned in place of its output. This includes requests that are rejected before execution
+    /// because of conflicting or unsupported fields.
+    pub async fn call_batch(
+        &self,
+        requests: Vec<CallRequest>,
+        block_number: Option<BlockId>,
+        mut overrides: EvmOverrides,
+    ) -> EthResult<Vec<EthResult<Bytes>>> {
+        let at = block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
+        let (cfg, block_env, at) = self.evm_env_at(at).await?;
+        let gas_limit = self.call_gas_limit();
+        let validations = requests
+            .iter()
+            .map(|request| validate_call_request(request, cfg.spec_id))
+            .collect::<Vec<_>>();
+
+        self.spawn_with_state_at_block(at, move |state| {
+            let mut base = SubState::new(State::new(&state));
+            if let Some(state_overrides) = overrides.state.take() {
+                apply_state_overrides(state_overrides, &mut base)?;
+            }
+
+            let limits = overrides.limits;
+            Ok(requests
+                .into_iter()
+                .zip(validations)
+                .map(|(request, validation)| {
+                    validation?;
+                    let mut db = base.clone();
+                    let env = prepare_call_env(
+                        cfg.clone(),
+                        block_env.clone(),
+                        request,
+                        gas_limit,
+                        &mut db,
+                        overrides.clone(),
+                    )?;
+                    let (res, _) = transact_with_limits(&mut db, env, limits)?;
+                    ensure_success(res.result)
+                })
+                .collect())
+        })
+        .await
+    }
+
+    /// Opens a [CallSession] at the given block for executing consecutive calls that each observe
+    /// the state changes of the previous ones.
+    ///
+    /// The state of the block is resolved once and the state overrides are applied before the
+    /// first call of the session, the remaining overrides apply to every call.
+    pub async fn call_session(
+        &self,
+        block_number: Option<BlockId>,
+        mut overrides: EvmOverrides,
+    ) -> EthResult<CallSession<'_>> {
+        let at = block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
+        let (cfg, block_env, at) = self.evm_env_at(at).await?;
+
+        let mut db = SubState::new(State::new(self.state_at(at)?));
+        if let Some(state_overrides) = overrides.state.take() {
+            apply_state_overrides(state_overrides, &mut db)?;
+        }
+
+        Ok(CallSession { db, cfg, block_env, gas_limit: self.call_gas_limit(), overrides })
+    }
+
+    /// Executes the call request (`eth_call`) and returns the [ExecutionResult] together with all
+    /// logs that were emitted during execution.
+    ///
+    /// The logs are observational: they are collected by a call tracer while executing and are
+    /// never committed. Hence, unlike the logs of the [ExecutionResult], they also include logs
+    /// that were emitted before a revert.
+    pub async fn call_with_logs(
+        &self,
+        request: CallRequest,
+        block_number: Option<BlockId>,
+        overrides: EvmOverrides,
+    ) -> EthResult<(ExecutionResult, Vec<Log>)> {
+        let limits = overrides.limits;
+        self.spawn_with_call_at(
+            request,
+            block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest)),
+            overrides,
+            move |db, env| inspect_logs(db, env, limits),
+        )
+        .await
+    }
+
+    /// Executes the call request (`eth_call`) and returns the output together with the [Bloom]
+    /// over the logs that were emitted during execution.
+    ///
+    /// This allows checking whether the logs of a call would match a bloom filter. The logs are
+    /// collected the same way as in [EthApi::call_with_logs], so the bloom also includes the logs
+    /// of reverted subcalls.
+    pub async fn call_with_bloom(
+        &self,
+        request: CallRequest,
+        block_number: Option<BlockId>,
+        overrides: EvmOverrides,
+    ) -> EthResult<(Bytes, Bloom)> {
+        let limits = overrides.limits;
+        self.spawn_with_call_at(
+            request,
+            block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest)),
+            overrides,
+            move |db, env| {
+                let (res, logs) = inspect_logs(db, env, limits)?;
+                Ok((ensure_success(res)?, logs_bloom(&logs)))
+            },
+        )
+        .await
+    }
+
+    /// Executes the call request (`eth_call`) and returns the output together with the number of
+    /// accounts and storage slots the call accessed.
+    ///
+    /// This is cheaper than creating an access list if only its size is of interest, for example
+    /// to warm caches, see [AccessCounts].
+    pub async fn call_with_access_counts(
+        &self,
+        request: CallRequest,
+        block_number: Option<BlockId>,
+        overrides: EvmOverrides,
+    ) -> EthResult<(Bytes, AccessCounts)> {
+        let limits = overrides.limits;
+        self.spawn_with_call_at(
+            request,
+            block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest)),
+            overrides,
+            move |db, env| inspect_access_counts(db, env, limits),
+        )
+        .await
+    }
+
+    /// Executes the call request and returns the gas it used, bucketed by opcode class.
+    ///
+    /// This is considerably cheaper than collecting struct logs, see [GasProfile].
+    pub async fn call_gas_profile(
+        &self,
+        request: CallRequest,
+        block_number: Option<BlockId>,
+        overrides: EvmOverrides,
+    ) -> EthResult<GasProfile> {
+        let limits = overrides.limits;
+        self.spawn_with_call_at(
+            request,
+            block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest)),
+            overrides,
+            move |db, env| inspect_gas_profile(db, env, limits),
+        )
+        .await
+    }
+
+    /// Executes the call request (`eth_call`) and returns its call tree.
+    ///
+    /// Every [CallFrame] includes the gas limit and the gas used by the frame, including the gas
+    /// used by its subcalls. The gas used by the root frame is the gas used by the whole call,
+    /// including the intrinsic gas and refunds. The tree is also returned if the call reverted, the
+    /// reverted frames carry the error.
+    ///
+    /// The outputs of the frames are left out, see [EthApi::call_with_calls_options].
+    pub async fn call_with_calls(
+        &self,
+        request: CallRequest,
+        block_number: Option<BlockId>,
+        overrides: EvmOverrides,
+    ) -> EthResult<CallFrame> {
+        self.call_with_calls_options(request, block_number, overrides, Default::default()).await
+    }
+
+    /// Same as [EthApi::call_with_calls] but with the given [CallTreeOptions].
+    ///
+    /// With [CallTreeOptions::include_outputs], every frame carries the data it returned to its
+    /// caller, e.g. what an implementation returned to a proxy that delegatecalled it.
+    pub async fn call_with_calls_options(
+        &self,
+        request: CallRequest,
+        block_number: Option<BlockId>,
+        overrides: EvmOverrides,
+        options: CallTreeOptions,
+    ) -> EthResult<CallFrame> {
+        let limits = overrides.limits;
+        self.spawn_with_call_at(
+            request,
+            block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest)),
+            overrides,
+            move |db, env| {
+                // only the calls are recorded, the call tracer doesn't need opcode level traces
+                let mut inspector = TracingInspector::new(
+                    TracingInspectorConfig::default_parity().set_exclude_precompile_calls(false),
+                );
+                let (res, _) = inspect_with_limits(db, env, &mut inspector, limits)?;
+                let mut root = inspector
+                    .into_geth_builder()
+                    .geth_call_traces(CallConfig::default(), res.result.gas_used());
+                if !options.include_outputs {
+                    clear_outputs(&mut root);
+                }
+                Ok(root)
+            },
+        )
+        .await
+    }
+
+    /// Executes the call request (`eth_call`) without tracing and, only if it reverted, executes
+    /// it again with a call tracer.
+    ///
+    /// Returns the output, which is the revert data if the call reverted, together with the
+    /// frames of the call tree if the call reverted. The frames are flattened in the order the
+    /// calls were entered, the root frame first, and carry their outputs and errors. Successful
+    /// calls cost a single untraced execution. A call that halted is an error, like in
+    /// [EthApi::call].
+    pub async fn call_trace_on_revert(
+        &self,
+        request: CallRequest,
+        block_number: Option<BlockId>,
+        overrides: EvmOverrides,
+    ) -> EthResult<(Bytes, Option<Vec<CallFrame>>)> {
+        let limits = overrides.limits;
+        self.spawn_with_call_at(
+            request,
+            block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest)),
+            overrides,
+            move |mut db, env| {
+                // state changes are never committed, so the second run starts from the same state
+                let (res, _) = transact_with_limits(&mut db, env.clone(), limits)?;
+                if !matches!(res.result, ExecutionResult::Revert { .. }) {
+                    return Ok((ensure_success(res.result)?, None))
+                }
+
+                let mut inspector = TracingInspector::new(
+                    TracingInspectorConfig::default_parity().set_exclude_precompile_calls(false),
+                );
+                let (res, _) = inspect_with_limits(db, env, &mut inspector, limits)?;
+                let gas_used = res.result.gas_used();
+                let output = match res.result {
+                    ExecutionResult::Revert { output, .. } => output,
+                    result => ensure_success(result)?,
+                };
+                let root =
+                    inspector.into_geth_builder().geth_call_traces(CallConfig::default(), gas_used);
+                let mut frames = Vec::new();
+                flatten_frames(root, &mut frames);
+                Ok((output, Some(frames)))
+            },
+        )
+        .await
+    }
+
+    /// Executes the call request (`eth_call`) with the given [Inspector] and returns the output
+    /// together with the inspector.
+    ///
+    /// This allows running a call through custom tracers, the inspector observes the execution
+    /// and is handed back for inspection afterwards.
+    pub async fn call_with_inspector<I>(
+        &self,
+        request: CallRequest,
+        block_number: Option<BlockId>,
+        overrides: EvmOverrides,
+        mut inspector: I,
+    ) -> EthResult<(Bytes, I)>
+    where
+        I: for<'r> Inspector<StateCacheDB<'r>> + Send + 'static,
+    {
+        let limits = overrides.limits;
+        self.spawn_with_call_at(
+            request,
+            block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest)),
+            overrides,
+            move |db, env| {
+                let (res, _) = inspect_with_limits(db, env, &mut inspector, limits)?;
+                Ok((ensure_success(res.result)?, inspector))
+            },
+        )
+        .await
+    }
+
     /// Simulate arbitrary number of transactions at an arbitrary blockchain index, with the
     /// optionality of state overrides
     pub async fn call_many(
         &self,
         bundle: Bundle,
         state_context: Option<StateContext>,
-        mut state_override: Option<StateOverride>,
+        state_override: Option<StateOverride>,
     ) -> EthResult<Vec<EthCallResponse>> {
+        // apply state overrides only once, before the first transaction
+        let mut state_overrides = vec![None; bundle.transactions.len()];
+        if let Some(first) = state_overrides.first_mut() {
+            *first = state_override;
+        }
+        let limits = CallLimits::default();
+        Ok(self.call_many_staged(bundle, state_context, state_overrides, limits).await?.responses)
+    }
+
+    /// Simulates consecutive blocks of calls on top of the block at the [BlockId].
+    ///
+    /// The calls of each block are executed on top of each other and on top of all previously
+    /// simulated blocks. Every simulated block is one slot of [SLOT_DURATION] after its
+    /// predecessor. The first simulated block inherits the base fee of the parent block, the
+    /// base fee of every following block is derived from the gas used by its predecessor
+    /// according to EIP-1559.
+    pub async fn simulate_blocks(
+        &self,
+        blocks: Vec<Vec<CallRequest>>,
+        at: BlockId,
+    ) -> EthResult<Vec<SimulatedBlock>> {
+        let (cfg, block_env, at) = self.evm_env_at(at).await?;
+        let base_fee_params = self.provider().chain_spec().base_fee_params;
+        let gas_limit = self.inner.gas_cap;
+
+        self.spawn_with_state_at_block(at, move |state| {
+            let mut db = SubState::new(State::new(state));
+            simulate_blocks_with(cfg, block_env, &mut db, blocks, base_fee_params, gas_limit)
+        })
+        .await
+    }
+
+    /// Same as [Self::call_many] but with state overrides that are applied at specific points of
+    /// the bundle.
+    ///
+    /// The `state_overrides` are aligned with the bundle's transactions: the override at index `i`
+    /// is applied right before the `i`th transaction is executed. A `None` entry applies no
+    /// additional override.
+    ///
+    /// If [StateContext::stop_on_error] is set, execution stops at the first failing transaction
+    /// and only the responses up to and including the failed one are returned. The `limits` apply
+    /// to every transaction of the bundle.
+    pub async fn call_many_staged(
+        &self,
+        bundle: Bundle,
+        state_context: Option<StateContext>,
+        state_overrides: Vec<Option<StateOverride>>,
+        limits: CallLimits,
+    ) -> EthResult<CallManyResult> {
         let Bundle { transactions, block_override } = bundle;
         if transactions.is_empty() {
             return Err(EthApiError::InvalidParams(String::from("transactions are empty.")))
         }
+        if state_overrides.len() != transactions.len() {
+            return Err(EthApiError::InvalidParams(String::from(
+                "state overrides do not match the number of transactions.",
+            )))
+        }
 
-        let StateContext { transaction_index, block_number } = state_context.unwrap_or_default();
+        let StateContext { transaction_index, block_number, stop_on_error } =
+            state_context.unwrap_or_default();
         let transaction_index = transaction_index.unwrap_or_default();
 
         let target_block = block_number.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
@@ -105,7 +835,6 @@ where
         }
 
         self.spawn_with_state_at_block(at.into(), move |state| {
-            let mut results = Vec::with_capacity(transactions.len());
             let mut db = SubState::new(State::new(state));
 
             if replay_block_txs {
@@ -123,41 +852,17 @@ where
                 }
             }
 
-            let block_overrides = block_override.map(Box::new);
-
-            let mut transactions = transactions.into_iter().peekable();
-            while let Some(tx) = transactions.next() {
-                // apply state overrides only once, before the first transaction
-                let state_overrides = state_override.take();
-                let overrides = EvmOverrides::new(state_overrides, block_overrides.clone());
-
-                let env = prepare_call_env(
-                    cfg.clone(),
-                    block_env.clone(),
-                    tx,
-                    gas_limit,
-                    &mut db,
-                    overrides,
-                )?;
-                let (res, _) = transact(&mut db, env)?;
-
-                match ensure_success(res.result) {
-                    Ok(output) => {
-                        results.push(EthCallResponse { output: Some(output), error: None });
-                    }
-                    Err(err) => {
-                        results
-                            .push(EthCallResponse { output: None, error: Some(err.to_string()) });
-                    }
-                }
-
-                if transactions.peek().is_some() {
-                    // need to apply the state changes of this call before executing the next call
-                    db.commit(res.state);
-                }
-            }
-
-            Ok(results)
+            call_many_with(
+                cfg,
+                block_env,
+                &mut db,
+                transactions,
+                state_overrides,
+                block_override.map(Box::new),
+                gas_limit,
+                limits,
+                stop_on_error,
+            )
         })
         .await
     }
@@ -167,7 +872,7 @@ where
     /// This will execute the [CallRequest] and find the best gas limit via binary search
     fn estimate_gas_with<S>(
         &self,
-        mut cfg: CfgEnv,
+        cfg: CfgEnv,
         block: BlockEnv,
         request: CallRequest,
         state: S,
@@ -175,10 +880,36 @@ where
     where
         S: StateProvider,
     {
-        // Disabled because eth_estimateGas is sometimes used with eoa senders
-        // See <htps://github.com/paradigmxyz/reth/issues/1959>
-        cfg.disable_eip3607 = true;
+        self.estimate_gas_with_db(cfg, block, request, SubState::new(State::new(state)))
+    }
+
+    /// Same as [Self::estimate_gas_with] but estimates on top of the given database.
+    fn estimate_gas_with_db<S>(
+        &self,
+        cfg: CfgEnv,
+        block: BlockEnv,
+        request: CallRequest,
+        db: CacheDB<State<S>>,
+    ) -> EthResult<U256>
+    where
+        S: StateProvider,
+    {
+        Ok(self.estimate_gas_bounded_with_db(cfg, block, request, db, None)?.gas)
+    }
 
+    /// Same as [Self::estimate_gas_with_db] but stops the binary search after `max_iterations`
+    /// executions of the call.
+    fn estimate_gas_bounded_with_db<S>(
+        &self,
+        mut cfg: CfgEnv,
+        block: BlockEnv,
+        request: CallRequest,
+        mut db: CacheDB<State<S>>,
+        max_iterations: Option<usize>,
+    ) -> EthResult<BoundedGasEstimate>
+    where
+        S: StateProvider,
+    {
         // The basefee should be ignored for eth_createAccessList
         // See:
         // <https://github.com/ethereum/go-ethereum/blob/ee8e83fa5f6cb261dad2ed0a7bbcde4930c41e6c/internal/ethapi/api.go#L985>
@@ -188,6 +919,11 @@ where
         let request_gas = request.gas;
         let request_gas_price = request.gas_price;
         let env_gas_limit = block.gas_limit;
+        // the access list is charged upfront, so no call can use less gas than the base cost plus
+        // the access list cost
+        let access_list_gas =
+            request.access_list.as_ref().map(access_list_intrinsic_gas).unwrap_or_default();
+        let intrinsic_gas = intrinsic_gas(&request, cfg.spec_id);
 
         // get the highest possible gas limit, either the request's set value or the currently
         // configured gas limit
@@ -195,7 +931,6 @@ where
 
         // Configure the evm env
         let mut env = build_call_evm_env(cfg, block, request)?;
-        let mut db = SubState::new(State::new(state));
 
         // if the request is a simple transfer we can optimize
         if env.tx.data.is_empty() {
@@ -207,11 +942,10 @@ where
                         let available_funds =
                             db.basic(env.tx.caller)?.map(|acc| acc.balance).unwrap_or_default();
                         if env.tx.value > available_funds {
-                            return Err(
-                                RpcInvalidTransactionError::InsufficientFundsForTransfer.into()
-                            )
+                            return Err(EstimateGasError::InsufficientFunds.into())
                         }
-                        return Ok(U256::from(MIN_TRANSACTION_GAS))
+                        let gas = U256::from(MIN_TRANSACTION_GAS + access_list_gas);
+                        return Ok(BoundedGasEstimate { gas, truncated: false })
                     }
                 }
             }
@@ -253,7 +987,7 @@ where
                 // succeeded
             }
             ExecutionResult::Halt { reason, gas_used } => {
-                return Err(RpcInvalidTransactionError::halt(reason, gas_used).into())
+                return Err(EstimateGasError::Halt { reason, gas_used }.into())
             }
             ExecutionResult::Revert { output, .. } => {
                 // if price or limit was included in the request then we can execute the request
@@ -262,7 +996,7 @@ where
                     Err(map_out_of_gas_err(env_gas_limit, env, &mut db))
                 } else {
                     // the transaction did revert
-                    Err(RpcInvalidTransactionError::Revert(RevertError::new(output)).into())
+                    Err(EstimateGasError::revert(output).into())
                 }
             }
         }
@@ -272,9 +1006,8 @@ where
         // possible range NOTE: this is the gas the transaction used, which is less than the
         // transaction requires to succeed
         let gas_used = res.result.gas_used();
-        // the lowest value is capped by the gas it takes for a transfer
-        let mut lowest_gas_limit =
-            if env.tx.transact_to.is_create() { MIN_CREATE_GAS } else { MIN_TRANSACTION_GAS };
+        // the lowest value is capped by the intrinsic gas of the transaction
+        let mut lowest_gas_limit = intrinsic_gas;
         let mut highest_gas_limit: u64 = highest_gas_limit.try_into().unwrap_or(u64::MAX);
         // pick a point that's close to the estimated gas
         let mut mid_gas_limit = std::cmp::min(
@@ -285,7 +1018,16 @@ where
         trace!(target: "rpc::eth::estimate", ?env, ?highest_gas_limit, ?lowest_gas_limit, ?mid_gas_limit, "Starting binary search for gas");
 
         // binary search
+        let mut iterations = 0;
+        let mut truncated = false;
         while (highest_gas_limit - lowest_gas_limit) > 1 {
+            if max_iterations.map_or(false, |max| iterations >= max) {
+                // the highest gas limit is always one the call succeeded with
+                truncated = true;
+                break
+            }
+            iterations += 1;
+
             let mut env = env.clone();
             env.tx.gas_limit = mid_gas_limit;
             let ethres = transact(&mut db, env);
@@ -313,16 +1055,16 @@ where
                     // increase the lowest gas limit
                     lowest_gas_limit = mid_gas_limit;
                 }
-                ExecutionResult::Halt { reason, .. } => {
+                ExecutionResult::Halt { reason, gas_used } => {
                     match reason {
                         Halt::OutOfGas(_) => {
                             // increase the lowest gas limit
                             lowest_gas_limit = mid_gas_limit;
                         }
-                        err => {
+                        reason => {
                             // these should be unreachable because we know the transaction succeeds,
                             // but we consider these cases an error
-                            return Err(RpcInvalidTransactionError::EvmHalt(err).into())
+                            return Err(EstimateGasError::Halt { reason, gas_used }.into())
                         }
                     }
                 }
@@ -331,14 +1073,25 @@ where
             mid_gas_limit = ((highest_gas_limit as u128 + lowest_gas_limit as u128) / 2) as u64;
         }
 
-        Ok(U256::from(highest_gas_limit))
+        Ok(BoundedGasEstimate { gas: U256::from(highest_gas_limit), truncated })
     }
 
     pub(crate) async fn create_access_list_at(
         &self,
         request: CallRequest,
         at: Option<BlockId>,
-    ) -> EthResult<AccessList> {
+    ) -> EthResult<AccessListWithGasUsed> {
+        self.create_access_list_with_options_at(request, at, AccessListOptions::default()).await
+    }
+
+    /// Creates an access list for the `request` like `eth_createAccessList`, filtered according to
+    /// the given [AccessListOptions].
+    pub async fn create_access_list_with_options_at(
+        &self,
+        request: CallRequest,
+        at: Option<BlockId>,
+        options: AccessListOptions,
+    ) -> EthResult<AccessListWithGasUsed> {
         let block_id = at.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
         let (cfg, block, at) = self.evm_env_at(block_id).await?;
         let state = self.state_at(at)?;
@@ -356,7 +1109,10 @@ where
 
         let mut db = SubState::new(State::new(state));
 
-        if request.gas.is_none() && env.tx.gas_price > U256::ZERO {
+        if request.gas.is_none() &&
+            options.gas_limit_override.is_none() &&
+            env.tx.gas_price > U256::ZERO
+        {
             // no gas limit was provided in the request, so we need to cap the request's gas limit
             cap_tx_gas_limit_with_caller_allowance(&mut db, &mut env.tx)?;
         }
@@ -369,13 +1125,465 @@ where
             get_contract_address(from, nonce).into()
         };
 
-        let initial = request.access_list.clone().unwrap_or_default();
-
-        let precompiles = get_precompiles(&env.cfg.spec_id);
-        let mut inspector = AccessListInspector::new(initial, from, to, precompiles);
-        let (result, _env) = inspect(&mut db, env, &mut inspector)?;
+        let initial = request.access_list.unwrap_or_default();
 
-        match result.result {
+        create_access_list_with(env, &mut db, from, to, initial, &options)
+    }
+}
+
+/// Removes the outputs of the [CallFrame] and all of its subcalls.
+fn clear_outputs(frame: &mut CallFrame) {
+    frame.output = None;
+    frame.calls.iter_mut().for_each(clear_outputs);
+}
+
+/// Appends the [CallFrame] and all of its subcalls to `frames` in the order they were entered.
+///
+/// The subcalls are moved out of their parent frames, so every frame occurs once.
+fn flatten_frames(mut frame: CallFrame, frames: &mut Vec<CallFrame>) {
+    let calls = std::mem::take(&mut frame.calls);
+    frames.push(frame);
+    for call in calls {
+        flatten_frames(call, frames);
+    }
+}
+
+/// Executes the [Env] with a call tracer and returns the result and all logs that were emitted,
+/// including the ones of reverted calls.
+fn inspect_logs<DB>(db: DB, env: Env, limits: CallLimits) -> EthResult<(ExecutionResult, Vec<Log>)>
+where
+    DB: Database,
+    <DB as Database>::Error: Into<EthApiError>,
+{
+    let mut inspector =
+        TracingInspector::new(TracingInspectorConfig::default_parity().set_record_logs(true));
+    let (res, _) = inspect_with_limits(db, env, &mut inspector, limits)?;
+    Ok((res.result, inspector.traces().logs()))
+}
+
+/// Executes the [Env] with a [GasProfileInspector] and returns the [GasProfile] of the execution.
+fn inspect_gas_profile<DB>(db: DB, env: Env, limits: CallLimits) -> EthResult<GasProfile>
+where
+    DB: Database,
+    <DB as Database>::Error: Into<EthApiError>,
+{
+    let mut inspector = GasProfileInspector::default();
+    let (res, _) = inspect_with_limits(db, env, &mut inspector, limits)?;
+    inspector.profile.gas_used = res.result.gas_used();
+    Ok(inspector.profile)
+}
+
+/// An [Inspector] that buckets the gas consumed by every executed opcode by its class.
+#[derive(Debug, Default)]
+struct GasProfileInspector {
+    /// The profile collected so far.
+    profile: GasProfile,
+    /// The opcodes currently executed and the remaining gas before their execution.
+    ///
+    /// This is a stack, because call and create opcodes only finish after the code of the call.
+    steps: Vec<(u8, u64)>,
+    /// The gas used by the last finished call or create frame.
+    frame_gas_used: u64,
+}
+
+impl GasProfileInspector {
+    /// Returns the bucket of the given opcode.
+    fn bucket(&mut self, op: u8) -> &mut u64 {
+        match op {
+            opcode::SLOAD | opcode::SSTORE => &mut self.profile.storage,
+            opcode::MLOAD |
+            opcode::MSTORE |
+            opcode::MSTORE8 |
+            opcode::CALLDATACOPY |
+            opcode::CODECOPY |
+            opcode::EXTCODECOPY |
+            opcode::RETURNDATACOPY => &mut self.profile.memory,
+            opcode::CALL |
+            opcode::CALLCODE |
+            opcode::DELEGATECALL |
+            opcode::STATICCALL |
+            opcode::CREATE |
+            opcode::CREATE2 |
+            opcode::SELFDESTRUCT => &mut self.profile.calls,
+            opcode::LOG0 | opcode::LOG1 | opcode::LOG2 | opcode::LOG3 | opcode::LOG4 => {
+                &mut self.profile.logs
+            }
+            _ => &mut self.profile.compute,
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for GasProfileInspector {
+    fn step(
+        &mut self,
+        interp: &mut Interpreter,
+        _data: &mut EVMData<'_, DB>,
+        _is_static: bool,
+    ) -> InstructionResult {
+        let op = interp.contract.bytecode.bytecode()[interp.program_counter()];
+        self.steps.push((op, interp.gas.remaining()));
+        InstructionResult::Continue
+    }
+
+    fn step_end(
+        &mut self,
+        interp: &mut Interpreter,
+        _data: &mut EVMData<'_, DB>,
+        _is_static: bool,
+        _eval: InstructionResult,
+    ) -> InstructionResult {
+        if let Some((op, gas_before)) = self.steps.pop() {
+            // the gas used by a call or create frame is attributed to the opcodes of the frame
+            let frame_gas_used = std::mem::take(&mut self.frame_gas_used);
+            let cost = gas_before.saturating_sub(interp.gas.remaining());
+            *self.bucket(op) += cost.saturating_sub(frame_gas_used);
+        }
+        InstructionResult::Continue
+    }
+
+    fn call_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CallInputs,
+        remaining_gas: Gas,
+        ret: InstructionResult,
+        out: bytes::Bytes,
+        _is_static: bool,
+    ) -> (InstructionResult, Gas, bytes::Bytes) {
+        self.frame_gas_used = remaining_gas.spend();
+        (ret, remaining_gas, out)
+    }
+
+    fn create_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<Address>,
+        remaining_gas: Gas,
+        out: bytes::Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, bytes::Bytes) {
+        self.frame_gas_used = remaining_gas.spend();
+        (ret, address, remaining_gas, out)
+    }
+}
+
+/// Executes the call and returns its output and the [AccessCounts] of the call.
+fn inspect_access_counts<DB>(
+    db: DB,
+    env: Env,
+    limits: CallLimits,
+) -> EthResult<(Bytes, AccessCounts)>
+where
+    DB: Database,
+    <DB as Database>::Error: Into<EthApiError>,
+{
+    let mut inspector = AccessCountsInspector::new(&env);
+    let (res, _) = inspect_with_limits(db, env, &mut inspector, limits)?;
+    let output = ensure_success(res.result)?;
+    Ok((output, inspector.counts()))
+}
+
+/// An [Inspector] that records the unique accounts and storage slots accessed by a call.
+#[derive(Debug, Default)]
+struct AccessCountsInspector {
+    /// Accounts that are warm at the start of the call.
+    warm_accounts: HashSet<Address>,
+    /// Storage slots that are warm at the start of the call.
+    warm_slots: HashSet<(Address, U256)>,
+    /// All accessed accounts.
+    accounts: HashSet<Address>,
+    /// All accessed storage slots.
+    slots: HashSet<(Address, U256)>,
+}
+
+impl AccessCountsInspector {
+    /// Creates a new inspector for a call with the given [Env].
+    fn new(env: &Env) -> Self {
+        let mut warm_accounts: HashSet<_> = get_precompiles(&env.cfg.spec_id).into_iter().collect();
+        let mut accounts = HashSet::from([env.tx.caller]);
+        if let TransactTo::Call(to) = env.tx.transact_to {
+            accounts.insert(to);
+        }
+        warm_accounts.extend(accounts.iter().copied());
+        if SpecId::enabled(env.cfg.spec_id, SpecId::SHANGHAI) {
+            warm_accounts.insert(env.block.coinbase);
+        }
+
+        let mut warm_slots = HashSet::new();
+        for (address, slots) in &env.tx.access_list {
+            warm_accounts.insert(*address);
+            warm_slots.extend(slots.iter().map(|slot| (*address, *slot)));
+        }
+
+        Self { warm_accounts, warm_slots, accounts, slots: HashSet::new() }
+    }
+
+    /// Returns the [AccessCounts] of all accesses so far.
+    fn counts(&self) -> AccessCounts {
+        AccessCounts {
+            accounts: self.accounts.len(),
+            slots: self.slots.len(),
+            cold_accounts: self.accounts.difference(&self.warm_accounts).count(),
+            cold_slots: self.slots.difference(&self.warm_slots).count(),
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for AccessCountsInspector {
+    fn step(
+        &mut self,
+        interp: &mut Interpreter,
+        _data: &mut EVMData<'_, DB>,
+        _is_static: bool,
+    ) -> InstructionResult {
+        let address_at = |index| {
+            let value = interp.stack().peek(index).ok()?;
+            Some(Address::from(H256::from(value.to_be_bytes())))
+        };
+        match interp.contract.bytecode.bytecode()[interp.program_counter()] {
+            opcode::SLOAD | opcode::SSTORE => {
+                if let Ok(slot) = interp.stack().peek(0) {
+                    self.slots.insert((interp.contract.address, slot));
+                }
+            }
+            opcode::EXTCODECOPY |
+            opcode::EXTCODEHASH |
+            opcode::EXTCODESIZE |
+            opcode::BALANCE |
+            opcode::SELFDESTRUCT => self.accounts.extend(address_at(0)),
+            opcode::DELEGATECALL | opcode::CALL | opcode::STATICCALL | opcode::CALLCODE => {
+                self.accounts.extend(address_at(1))
+            }
+            _ => {}
+        }
+        InstructionResult::Continue
+    }
+}
+
+/// Executes the best `transactions` of the pool on top of each other and commits their state
+/// changes to the `db`, until the gas limit of the block is reached.
+///
+/// Transactions that are invalid on top of the current state are marked as invalid, which also
+/// skips the transactions that depend on them.
+fn apply_transactions<S, T>(
+    cfg: &CfgEnv,
+    block_env: &BlockEnv,
+    db: &mut CacheDB<State<S>>,
+    transactions: &mut dyn BestTransactions<Item = Arc<ValidPoolTransaction<T>>>,
+) -> EthResult<()>
+where
+    S: StateProvider,
+    T: PoolTransaction,
+{
+    let block_gas_limit: u64 = block_env.gas_limit.try_into().unwrap_or(u64::MAX);
+    let mut cumulative_gas_used = 0;
+
+    while let Some(pool_tx) = transactions.next() {
+        // the pending block is full
+        if cumulative_gas_used + pool_tx.gas_limit() > block_gas_limit {
+            break
+        }
+
+        let tx = pool_tx.to_recovered_transaction();
+        let env =
+            Env { cfg: cfg.clone(), block: block_env.clone(), tx: tx_env_with_recovered(&tx) };
+        match transact(&mut *db, env) {
+            Ok((res, _)) => {
+                cumulative_gas_used += res.result.gas_used();
+                db.commit(res.state)
+            }
+            Err(EthApiError::InvalidTransaction(_)) => transactions.mark_invalid(&pool_tx),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Converts the result of a call into its [EthCallResponse].
+fn call_response(result: ExecutionResult, created_address: Option<Address>) -> EthCallResponse {
+    let gas_refunded = match result {
+        ExecutionResult::Success { gas_refunded, .. } => Some(U256::from(gas_refunded)),
+        _ => None,
+    };
+    let (output, error) = match ensure_success(result) {
+        Ok(output) => (Some(output), None),
+        Err(err) => (None, Some(err.to_string())),
+    };
+    EthCallResponse { output, error, gas_refunded, created_address }
+}
+
+/// Returns the address of the contract that is created by the transaction of the [Env], or `None`
+/// if the transaction is a call.
+///
+/// The address is derived from the caller and its nonce, so it must be called before the
+/// transaction is executed.
+fn created_address<S>(db: &mut CacheDB<State<S>>, env: &Env) -> EthResult<Option<Address>>
+where
+    S: StateProvider,
+{
+    if !matches!(env.tx.transact_to, TransactTo::Create(_)) {
+        return Ok(None)
+    }
+    let nonce = db.basic(env.tx.caller)?.unwrap_or_default().nonce;
+    Ok(Some(get_contract_address(env.tx.caller, nonce).into()))
+}
+
+/// Executes consecutive `blocks` of calls on top of each other, starting on top of the `parent`
+/// block.
+///
+/// See [EthApi::simulate_blocks].
+fn simulate_blocks_with<S>(
+    cfg: CfgEnv,
+    parent: BlockEnv,
+    db: &mut CacheDB<State<S>>,
+    blocks: Vec<Vec<CallRequest>>,
+    base_fee_params: BaseFeeParams,
+    gas_limit: u64,
+) -> EthResult<Vec<SimulatedBlock>>
+where
+    S: StateProvider,
+{
+    let mut block_env = parent;
+    let mut simulated_blocks: Vec<SimulatedBlock> = Vec::with_capacity(blocks.len());
+
+    for calls in blocks {
+        block_env.number += U256::from(1);
+        block_env.timestamp += U256::from(SLOT_DURATION.as_secs());
+        if let Some(prev) = simulated_blocks.last() {
+            block_env.basefee = U256::from(calculate_next_block_base_fee(
+                prev.gas_used,
+                block_env.gas_limit.saturating_to(),
+                prev.base_fee.saturating_to(),
+                base_fee_params,
+            ));
+        }
+
+        let mut block = SimulatedBlock {
+            number: block_env.number.saturating_to(),
+            timestamp: block_env.timestamp.saturating_to(),
+            base_fee: block_env.basefee,
+            gas_used: 0,
+            calls: Vec::with_capacity(calls.len()),
+        };
+        for call in calls {
+            let env = prepare_call_env(
+                cfg.clone(),
+                block_env.clone(),
+                call,
+                gas_limit,
+                db,
+                EvmOverrides::default(),
+            )?;
+            let created_address = created_address(db, &env)?;
+            let (res, _) = transact(&mut *db, env)?;
+            block.gas_used += res.result.gas_used();
+            db.commit(res.state);
+            block.calls.push(call_response(res.result, created_address));
+        }
+        simulated_blocks.push(block);
+    }
+
+    Ok(simulated_blocks)
+}
+
+/// Executes the `transactions` of a bundle on top of each other.
+///
+/// The state override at index `i` of `state_overrides` is applied right before the `i`th
+/// transaction is executed. If `stop_on_error` is set, no further transactions are executed after
+/// the first one that fails.
+#[allow(clippy::too_many_arguments)]
+fn call_many_with<S>(
+    cfg: CfgEnv,
+    block_env: BlockEnv,
+    db: &mut CacheDB<State<S>>,
+    transactions: Vec<CallRequest>,
+    state_overrides: Vec<Option<StateOverride>>,
+    block_overrides: Option<Box<BlockOverrides>>,
+    gas_limit: u64,
+    limits: CallLimits,
+    stop_on_error: bool,
+) -> EthResult<CallManyResult>
+where
+    S: StateProvider,
+{
+    let mut responses = Vec::with_capacity(transactions.len());
+
+    let mut transactions = transactions.into_iter().zip(state_overrides).enumerate().peekable();
+    while let Some((idx, (tx, state_overrides))) = transactions.next() {
+        let overrides =
+            EvmOverrides::new(state_overrides, block_overrides.clone()).with_limits(limits);
+
+        let env = prepare_call_env(cfg.clone(), block_env.clone(), tx, gas_limit, db, overrides)?;
+        let created_address = created_address(db, &env)?;
+        let (res, _) = transact_with_limits(&mut *db, env, limits)?;
+
+        let response = call_response(res.result, created_address);
+        let failed = response.error.is_some();
+        responses.push(response);
+        if failed && stop_on_error {
+            return Ok(CallManyResult { responses, failed_index: Some(idx) })
+        }
+
+        if transactions.peek().is_some() {
+            // need to apply the state changes of this call before executing the next call
+            db.commit(res.state);
+        }
+    }
+
+    Ok(CallManyResult { responses, failed_index: None })
+}
+
+/// Creates the access list for the given [Env] by repeatedly executing the transaction with the
+/// access list of the previous run applied, until the access list no longer changes.
+///
+/// Slots that are only discovered with a warmed access list can cause additional accesses, so this
+/// runs until a fixpoint is reached, but at most [MAX_ACCESS_LIST_ITERATIONS] times. If the limit
+/// is reached, the access list of the last run is returned.
+///
+/// Returns the final access list and the gas used by the transaction with that list applied.
+fn create_access_list_with<S>(
+    mut env: Env,
+    db: &mut CacheDB<State<S>>,
+    from: Address,
+    to: Address,
+    initial: AccessList,
+    options: &AccessListOptions,
+) -> EthResult<AccessListWithGasUsed>
+where
+    S: StateProvider,
+{
+    if let Some(gas_limit) = options.gas_limit_override {
+        env.tx.gas_limit = gas_limit;
+    }
+
+    let precompiles = get_precompiles(&env.cfg.spec_id);
+    let mut access_list = initial;
+    access_list.0.sort_by_key(|item| item.address);
+    let mut iteration = 0;
+
+    loop {
+        iteration += 1;
+        env.tx.access_list = access_list.clone().flattened();
+
+        let mut inspector =
+            AccessListInspector::new(access_list.clone(), from, to, precompiles.clone())
+                .with_removed_addresses(options.exclude.iter().copied());
+        if options.include_sender {
+            inspector = inspector.with_included_address(from);
+        }
+        if let Some(scope) = &options.scope {
+            inspector = inspector.with_scope(scope.iter().copied());
+        }
+        let (result, _env) = inspect(&mut *db, env.clone(), &mut inspector)?;
+
+        let gas_used = match result.result {
+            // running out of the overridden gas limit ends the access list where execution stopped
+            ExecutionResult::Halt { reason: Halt::OutOfGas(_), gas_used }
+                if options.gas_limit_override.is_some() =>
+            {
+                Ok(gas_used)
+            }
             ExecutionResult::Halt { reason, .. } => Err(match reason {
                 Halt::NonceOverflow => RpcInvalidTransactionError::NonceMaxValue,
                 halt => RpcInvalidTransactionError::EvmHalt(halt),
@@ -383,9 +1591,19 @@ where
             ExecutionResult::Revert { output, .. } => {
                 Err(RpcInvalidTransactionError::Revert(RevertError::new(output)))
             }
-            ExecutionResult::Success { .. } => Ok(()),
+            ExecutionResult::Success { gas_used, .. } => Ok(gas_used),
         }?;
-        Ok(inspector.into_access_list())
+
+        // the inspector collects the items in a map, sort them so lists are comparable
+        let mut new_access_list = inspector.into_access_list();
+        new_access_list.0.sort_by_key(|item| item.address);
+
+        if new_access_list == access_list || iteration >= MAX_ACCESS_LIST_ITERATIONS {
+            trace!(target: "rpc::eth::call", ?iteration, "Access list creation finished");
+            return Ok(AccessListWithGasUsed { access_list, gas_used: U256::from(gas_used) })
+        }
+
+        access_list = new_access_list;
     }
 }
 
@@ -402,6 +1620,8 @@ where
 {
     let req_gas_limit = env.tx.gas_limit;
     env.tx.gas_limit = env_gas_limit.try_into().unwrap_or(u64::MAX);
+    // the block gas limit was capped by the request's gas limit, so it needs to be restored
+    env.block.gas_limit = env_gas_limit;
     let (res, _) = match transact(&mut db, env) {
         Ok(res) => res,
         Err(err) => return err,
@@ -410,12 +1630,1487 @@ where
         ExecutionResult::Success { .. } => {
             // transaction succeeded by manually increasing the gas limit to
             // highest, which means the caller lacks funds to pay for the tx
-            RpcInvalidTransactionError::BasicOutOfGas(U256::from(req_gas_limit)).into()
+            EstimateGasError::OutOfGas { provided: U256::from(req_gas_limit) }.into()
         }
         ExecutionResult::Revert { output, .. } => {
             // reverted again after bumping the limit
-            RpcInvalidTransactionError::Revert(RevertError::new(output)).into()
+            EstimateGasError::revert(output).into()
+        }
+        ExecutionResult::Halt { reason, gas_used } => {
+            EstimateGasError::Halt { reason, gas_used }.into()
         }
-        ExecutionResult::Halt { reason, .. } => RpcInvalidTransactionError::EvmHalt(reason).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eth::{cache::EthStateCache, gas_oracle::GasPriceOracle, revm_utils::CallLimits},
+        TracingCallPool,
+    };
+    use assert_matches::assert_matches;
+    use jsonrpsee::types::ErrorObject;
+    use reth_network_api::noop::NoopNetwork;
+    use reth_primitives::{
+        bloom::Input, constants::ETHEREUM_BLOCK_GAS_LIMIT, AccessListItem, Block, ChainSpecBuilder,
+        Header, EIP2930_TX_TYPE_ID, H256, U8,
+    };
+    use reth_provider::test_utils::{ExtendedAccount, MockEthProvider};
+    use reth_revm::tracing::OpcodeCountInspector;
+    use reth_rpc_types::state::AccountOverride;
+    use reth_transaction_pool::{
+        test_utils::{testing_pool, MockTransaction, TestPool},
+        TransactionOrigin,
+    };
+    use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+    #[test]
+    fn gas_estimate_without_margin() {
+        let exact = U256::from(50_000u64);
+        let estimate = GasEstimate::new(exact, 0, U256::from(30_000_000u64));
+        assert_eq!(estimate.exact, exact);
+        assert_eq!(estimate.recommended, exact);
+    }
+
+    #[test]
+    fn gas_estimate_with_margin() {
+        let estimate = GasEstimate::new(U256::from(50_000u64), 20, U256::from(30_000_000u64));
+        assert_eq!(estimate.recommended, U256::from(60_000u64));
+    }
+
+    #[test]
+    fn gas_estimate_clamped_to_block_gas_limit() {
+        let block_gas_limit = U256::from(30_000_000u64);
+
+        // margin would exceed the block gas limit
+        let exact = U256::from(29_000_000u64);
+        let estimate = GasEstimate::new(exact, 10, block_gas_limit);
+        assert_eq!(estimate.exact, exact);
+        assert_eq!(estimate.recommended, block_gas_limit);
+
+        // exact estimate is already at the block gas limit
+        let estimate = GasEstimate::new(block_gas_limit, 10, block_gas_limit);
+        assert_eq!(estimate.recommended, block_gas_limit);
+
+        // recommended is never lower than the exact estimate
+        let exact = U256::from(31_000_000u64);
+        let estimate = GasEstimate::new(exact, 10, block_gas_limit);
+        assert_eq!(estimate.recommended, exact);
+    }
+
+    #[test]
+    fn access_list_converges_with_dependent_slot() {
+        let from = Address::random();
+        let to = Address::random();
+
+        // PUSH1 0x00 SLOAD SLOAD STOP: reads slot 0 and then the slot stored at slot 0
+        let code = Bytes::from(vec![0x60, 0x00, 0x54, 0x54, 0x00]);
+        let first_slot = H256::zero();
+        let dependent_slot = H256::from_low_u64_be(5);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(from, ExtendedAccount::new(0, U256::ZERO));
+        provider.add_account(
+            to,
+            ExtendedAccount::new(0, U256::ZERO)
+                .with_bytecode(code)
+                .extend_storage([(first_slot, U256::from(5)), (dependent_slot, U256::from(1))]),
+        );
+        let mut db = SubState::new(State::new(provider));
+
+        let env = test_call_env(from, to);
+
+        let options = AccessListOptions::default();
+        let res = create_access_list_with(
+            env.clone(),
+            &mut db,
+            from,
+            to,
+            AccessList::default(),
+            &options,
+        )
+        .unwrap();
+        let expected = AccessList(vec![AccessListItem {
+            address: to,
+            storage_keys: vec![first_slot, dependent_slot],
+        }]);
+        assert_eq!(res.access_list, expected);
+
+        // running again with the final list applied yields the same list and gas
+        let again =
+            create_access_list_with(env, &mut db, from, to, expected.clone(), &options).unwrap();
+        assert_eq!(again.access_list, expected);
+        assert_eq!(again.gas_used, res.gas_used);
+    }
+
+    #[test]
+    fn access_list_excluded_addresses() {
+        let from = Address::random();
+        let to = Address::random();
+        let slot = H256::zero();
+
+        // CALLER BALANCE POP PUSH1 0x00 SLOAD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN:
+        // touches the sender and returns slot 0
+        let code = Bytes::from(vec![
+            0x33, 0x31, 0x50, 0x60, 0x00, 0x54, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+        ]);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(from, ExtendedAccount::new(0, U256::ZERO));
+        provider.add_account(
+            to,
+            ExtendedAccount::new(0, U256::ZERO)
+                .with_bytecode(code)
+                .extend_storage([(slot, U256::from(42))]),
+        );
+        let mut db = SubState::new(State::new(provider));
+
+        let env = test_call_env(from, to);
+
+        let create = |db: &mut CacheDB<State<MockEthProvider>>, options: AccessListOptions| {
+            create_access_list_with(env.clone(), db, from, to, AccessList::default(), &options)
+                .unwrap()
+                .access_list
+        };
+
+        let slots = AccessListItem { address: to, storage_keys: vec![slot] };
+        assert_eq!(create(&mut db, Default::default()), AccessList(vec![slots.clone()]));
+
+        // the sender is only included on request
+        let sender = AccessListItem { address: from, storage_keys: vec![] };
+        let options = AccessListOptions { include_sender: true, ..Default::default() };
+        let mut expected = vec![slots, sender.clone()];
+        expected.sort_by_key(|item| item.address);
+        assert_eq!(create(&mut db, options), AccessList(expected));
+
+        // the excluded contract is left out although its slot is accessed
+        let options =
+            AccessListOptions { exclude: vec![to], include_sender: true, ..Default::default() };
+        assert_eq!(create(&mut db, options.clone()), AccessList(vec![sender]));
+
+        let precompiles = get_precompiles(&env.cfg.spec_id);
+        let mut inspector = AccessListInspector::new(AccessList::default(), from, to, precompiles)
+            .with_removed_addresses(options.exclude);
+        let (res, _) = inspect(&mut db, env.clone(), &mut inspector).unwrap();
+        let output = ensure_success(res.result).unwrap();
+        assert_eq!(output, Bytes::from(H256::from_low_u64_be(42).as_bytes().to_vec()));
+        assert_eq!(inspector.into_access_list(), AccessList::default());
+    }
+
+    #[test]
+    fn access_list_scoped_to_contract() {
+        let from = Address::random();
+        let to = Address::random();
+        let (first, second) = (Address::from_low_u64_be(0xb0), Address::from_low_u64_be(0xc0));
+
+        // CALL(gas, addr, 0, 0, 0, 0, 0) POP, for both callees, then STOP:
+        // PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 addr GAS CALL POP
+        let call =
+            |addr: u8| [0x60, 0, 0x60, 0, 0x60, 0, 0x60, 0, 0x60, 0, 0x60, addr, 0x5a, 0xf1, 0x50];
+        let mut code = [call(0xb0), call(0xc0)].concat();
+        code.push(0x00);
+        // PUSH1 slot SLOAD POP STOP
+        let sload = |slot: u8| Bytes::from(vec![0x60, slot, 0x54, 0x50, 0x00]);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()));
+        provider.add_account(first, ExtendedAccount::new(0, U256::ZERO).with_bytecode(sload(0)));
+        provider.add_account(second, ExtendedAccount::new(0, U256::ZERO).with_bytecode(sload(1)));
+        let mut db = SubState::new(State::new(provider));
+
+        let env = test_call_env(from, to);
+
+        let create = |db: &mut CacheDB<State<MockEthProvider>>, options: AccessListOptions| {
+            create_access_list_with(env.clone(), db, from, to, AccessList::default(), &options)
+                .unwrap()
+                .access_list
+        };
+
+        let first_item = AccessListItem { address: first, storage_keys: vec![H256::zero()] };
+        let second_item =
+            AccessListItem { address: second, storage_keys: vec![H256::from_low_u64_be(1)] };
+        assert_eq!(
+            create(&mut db, Default::default()),
+            AccessList(vec![first_item.clone(), second_item])
+        );
+
+        // only the contract in scope and its slot are listed
+        let options = AccessListOptions { scope: Some(vec![first]), ..Default::default() };
+        assert_eq!(create(&mut db, options), AccessList(vec![first_item]));
+
+        // an empty scope lists nothing
+        let options = AccessListOptions { scope: Some(vec![]), ..Default::default() };
+        assert_eq!(create(&mut db, options), AccessList::default());
+    }
+
+    #[test]
+    fn access_list_with_gas_limit_override() {
+        let from = Address::random();
+        let to = Address::random();
+        let (first_slot, second_slot) = (H256::zero(), H256::from_low_u64_be(1));
+
+        // PUSH1 0x00 SLOAD POP PUSH2 0xffff MLOAD POP PUSH1 0x01 SLOAD STOP: reads slot 0, expands
+        // the memory to 64KiB and reads slot 1
+        let code = Bytes::from(vec![
+            0x60, 0x00, 0x54, 0x50, 0x61, 0xff, 0xff, 0x51, 0x50, 0x60, 0x01, 0x54, 0x00,
+        ]);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(from, ExtendedAccount::new(0, U256::ZERO));
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code));
+        let mut db = SubState::new(State::new(provider));
+
+        let env = test_call_env(from, to);
+
+        let unbounded = create_access_list_with(
+            env.clone(),
+            &mut db,
+            from,
+            to,
+            AccessList::default(),
+            &AccessListOptions::default(),
+        )
+        .unwrap();
+        let expected = AccessListItem { address: to, storage_keys: vec![first_slot, second_slot] };
+        assert_eq!(unbounded.access_list, AccessList(vec![expected]));
+
+        // the memory expansion runs out of gas before the second slot is read
+        let options = AccessListOptions { gas_limit_override: Some(30_000), ..Default::default() };
+        let bounded =
+            create_access_list_with(env, &mut db, from, to, AccessList::default(), &options)
+                .unwrap();
+        let expected = AccessListItem { address: to, storage_keys: vec![first_slot] };
+        assert_eq!(bounded.access_list, AccessList(vec![expected]));
+        assert_eq!(bounded.gas_used, U256::from(30_000));
+    }
+
+    #[test]
+    fn call_many_applies_staged_state_overrides() {
+        let from = Address::random();
+        let to = Address::random();
+
+        // PUSH1 0x00 SLOAD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN: returns slot 0
+        let code =
+            Bytes::from(vec![0x60, 0x00, 0x54, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(
+            to,
+            ExtendedAccount::new(0, U256::ZERO)
+                .with_bytecode(code)
+                .extend_storage([(H256::zero(), U256::from(1))]),
+        );
+        let mut db = SubState::new(State::new(provider));
+
+        let request = CallRequest { from: Some(from), to: Some(to), ..Default::default() };
+        let state_override = HashMap::from([(
+            to,
+            AccountOverride {
+                state_diff: Some(HashMap::from([(H256::zero(), U256::from(7))])),
+                ..Default::default()
+            },
+        )]);
+
+        let results = call_many_with(
+            CfgEnv::default(),
+            BlockEnv::default(),
+            &mut db,
+            vec![request.clone(), request],
+            vec![None, Some(state_override)],
+            None,
+            30_000_000,
+            CallLimits::default(),
+            false,
+        )
+        .unwrap()
+        .responses;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].output, Some(Bytes::from(U256::from(1).to_be_bytes::<32>())));
+        assert_eq!(results[1].output, Some(Bytes::from(U256::from(7).to_be_bytes::<32>())));
+    }
+
+    #[test]
+    fn call_many_stop_on_error() {
+        let from = Address::random();
+        let ok = Address::random();
+        let reverts = Address::random();
+
+        // STOP
+        let ok_code = Bytes::from(vec![0x00]);
+        // PUSH1 0x00 PUSH1 0x00 REVERT
+        let revert_code = Bytes::from(vec![0x60, 0x00, 0x60, 0x00, 0xfd]);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(ok, ExtendedAccount::new(0, U256::ZERO).with_bytecode(ok_code));
+        provider
+            .add_account(reverts, ExtendedAccount::new(0, U256::ZERO).with_bytecode(revert_code));
+
+        let call = |to| CallRequest { from: Some(from), to: Some(to), ..Default::default() };
+        let run = |stop_on_error| {
+            let mut db = SubState::new(State::new(provider.clone()));
+            call_many_with(
+                CfgEnv::default(),
+                BlockEnv::default(),
+                &mut db,
+                vec![call(ok), call(reverts), call(ok)],
+                vec![None; 3],
+                None,
+                30_000_000,
+                CallLimits::default(),
+                stop_on_error,
+            )
+            .unwrap()
+        };
+
+        // continue and collect
+        let res = run(false);
+        assert_eq!(res.failed_index, None);
+        assert_eq!(res.responses.len(), 3);
+        assert!(res.responses[0].error.is_none());
+        assert!(res.responses[1].error.is_some());
+        assert!(res.responses[2].error.is_none());
+
+        // bail out on the reverting transaction
+        let res = run(true);
+        assert_eq!(res.failed_index, Some(1));
+        assert_eq!(res.responses.len(), 2);
+        assert!(res.responses[0].error.is_none());
+        assert!(res.responses[1].error.is_some());
+    }
+
+    #[test]
+    fn call_many_reports_gas_refund() {
+        let from = Address::random();
+        let to = Address::random();
+
+        // PUSH1 0x00 PUSH1 0x00 SSTORE STOP
+        let code = Bytes::from(vec![0x60, 0x00, 0x60, 0x00, 0x55, 0x00]);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(
+            to,
+            ExtendedAccount::new(0, U256::ZERO)
+                .with_bytecode(code)
+                .extend_storage([(H256::zero(), U256::from(1))]),
+        );
+        let mut db = SubState::new(State::new(provider));
+
+        let res = call_many_with(
+            CfgEnv::default(),
+            BlockEnv::default(),
+            &mut db,
+            vec![CallRequest { from: Some(from), to: Some(to), ..Default::default() }],
+            vec![None],
+            None,
+            30_000_000,
+            CallLimits::default(),
+            false,
+        )
+        .unwrap();
+
+        // clearing a storage slot grants the EIP-3529 refund
+        assert_eq!(res.responses[0].gas_refunded, Some(U256::from(4_800)));
+    }
+
+    #[test]
+    fn call_many_reports_created_address() {
+        let from = Address::random();
+        let to = Address::random();
+        let provider = MockEthProvider::default();
+        provider.add_account(from, ExtendedAccount::new(3, U256::ZERO));
+        let mut db = SubState::new(State::new(provider));
+
+        // PUSH1 0x00 PUSH1 0x00 RETURN, deploys empty code
+        let input = Bytes::from(vec![0x60, 0x00, 0x60, 0x00, 0xf3]).into();
+        let create = CallRequest { from: Some(from), input, ..Default::default() };
+        let call = CallRequest { from: Some(from), to: Some(to), ..Default::default() };
+        let res = call_many_with(
+            CfgEnv::default(),
+            BlockEnv::default(),
+            &mut db,
+            vec![create, call],
+            vec![None; 2],
+            None,
+            30_000_000,
+            CallLimits::default(),
+            false,
+        )
+        .unwrap();
+
+        assert!(res.responses.iter().all(|response| response.error.is_none()));
+        assert_eq!(res.responses[0].created_address, Some(get_contract_address(from, 3).into()));
+        assert_eq!(res.responses[1].created_address, None);
+    }
+
+    #[test]
+    fn simulate_blocks_ramps_base_fee() {
+        let from = Address::random();
+        let to = Address::random();
+
+        // JUMPDEST PUSH1 0x00 JUMP, loops until it runs out of gas
+        let code = Bytes::from(vec![0x5b, 0x60, 0x00, 0x56]);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code));
+        let mut db = SubState::new(State::new(provider));
+
+        let parent = BlockEnv {
+            number: U256::from(100),
+            timestamp: U256::from(1_681_338_443u64),
+            gas_limit: U256::from(30_000_000u64),
+            basefee: U256::from(1_000_000_000u64),
+            ..Default::default()
+        };
+        // every block is full
+        let call = CallRequest { from: Some(from), to: Some(to), ..Default::default() };
+        let blocks = vec![vec![call.clone()], vec![call.clone()], vec![call]];
+
+        let simulated = simulate_blocks_with(
+            CfgEnv::default(),
+            parent,
+            &mut db,
+            blocks,
+            BaseFeeParams::ethereum(),
+            30_000_000,
+        )
+        .unwrap();
+
+        let numbers = simulated.iter().map(|block| block.number).collect::<Vec<_>>();
+        assert_eq!(numbers, vec![101, 102, 103]);
+        // every block is one slot after its predecessor
+        let timestamps = simulated.iter().map(|block| block.timestamp).collect::<Vec<_>>();
+        assert_eq!(timestamps, vec![1_681_338_455, 1_681_338_467, 1_681_338_479]);
+        assert!(simulated.iter().all(|block| block.gas_used == 30_000_000));
+        assert!(simulated.iter().all(|block| block.calls[0].error.is_some()));
+        // the first block inherits the parent's base fee, each full block raises it by 12.5%
+        let base_fees = simulated.iter().map(|block| block.base_fee).collect::<Vec<_>>();
+        assert_eq!(
+            base_fees,
+            vec![
+                U256::from(1_000_000_000u64),
+                U256::from(1_125_000_000u64),
+                U256::from(1_265_625_000u64)
+            ]
+        );
+    }
+
+    #[test]
+    fn gas_profile_of_storage_heavy_call() {
+        let from = Address::random();
+        let to = Address::random();
+
+        // PUSH1 0x01 PUSH1 0x00 SSTORE PUSH1 0x01 PUSH1 0x01 SSTORE PUSH1 0x01 PUSH1 0x02 SSTORE
+        let code = Bytes::from(vec![
+            0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x01, 0x60, 0x01, 0x55, 0x60, 0x01, 0x60, 0x02,
+            0x55,
+        ]);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code));
+        let mut db = SubState::new(State::new(provider));
+
+        let env = test_call_env(from, to);
+
+        let profile = inspect_gas_profile(&mut db, env, CallLimits::default()).unwrap();
+        // three cold SSTOREs of a zero slot and six PUSH1s
+        assert_eq!(profile.storage, 3 * 22_100);
+        assert_eq!(profile.compute, 6 * 3);
+        assert_eq!(profile.memory, 0);
+        assert_eq!(profile.calls, 0);
+        assert_eq!(profile.logs, 0);
+        assert_eq!(profile.gas_used, MIN_TRANSACTION_GAS + profile.storage + profile.compute);
+    }
+
+    #[test]
+    fn access_counts_of_storage_reading_call() {
+        let from = Address::random();
+        let to = Address::random();
+        let other = Address::random();
+
+        // PUSH1 0x00 SLOAD POP PUSH1 0x01 SLOAD POP PUSH1 0x00 SLOAD POP PUSH20 <other> BALANCE
+        // POP STOP
+        let mut code = vec![0x60, 0x00, 0x54, 0x50, 0x60, 0x01, 0x54, 0x50, 0x60, 0x00, 0x54, 0x50];
+        code.push(0x73);
+        code.extend_from_slice(other.as_bytes());
+        code.extend_from_slice(&[0x31, 0x50, 0x00]);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()));
+        let mut db = SubState::new(State::new(provider));
+
+        let mut env = test_call_env(from, to);
+        // slot 1 is warmed by the access list
+        env.tx.access_list = vec![(to, vec![U256::from(1)])];
+
+        let (output, counts) = inspect_access_counts(&mut db, env, CallLimits::default()).unwrap();
+        assert!(output.is_empty());
+        assert_eq!(
+            counts,
+            AccessCounts {
+                // the sender, the callee and the balance lookup
+                accounts: 3,
+                slots: 2,
+                cold_accounts: 1,
+                cold_slots: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn call_logs_include_reverted_logs() {
+        let from = Address::random();
+        let to = Address::random();
+
+        // PUSH1 0x2a PUSH1 0x00 PUSH1 0x00 LOG1 PUSH1 0x00 PUSH1 0x00 REVERT
+        let code = Bytes::from(vec![
+            0x60, 0x2a, 0x60, 0x00, 0x60, 0x00, 0xa1, 0x60, 0x00, 0x60, 0x00, 0xfd,
+        ]);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code));
+        let mut db = SubState::new(State::new(provider));
+
+        let env = test_call_env(from, to);
+
+        let (res, logs) = inspect_logs(&mut db, env, CallLimits::default()).unwrap();
+        assert_matches!(res, ExecutionResult::Revert { .. });
+        let expected =
+            Log { address: to, topics: vec![H256::from_low_u64_be(0x2a)], data: Bytes::default() };
+        assert_eq!(logs, vec![expected]);
+    }
+
+    fn build_test_eth_api(
+        provider: MockEthProvider,
+    ) -> EthApi<MockEthProvider, TestPool, NoopNetwork> {
+        let cache = EthStateCache::spawn(provider.clone(), Default::default());
+        EthApi::new(
+            provider.clone(),
+            testing_pool(),
+            NoopNetwork::default(),
+            cache.clone(),
+            GasPriceOracle::new(provider, Default::default(), cache),
+            ETHEREUM_BLOCK_GAS_LIMIT,
+            TracingCallPool::build().expect("failed to build tracing pool"),
+        )
+    }
+
+    /// Adds a block with the mainnet gas limit to the `provider` and builds the [EthApi] on top.
+    fn build_test_eth_api_with_block(
+        provider: MockEthProvider,
+    ) -> EthApi<MockEthProvider, TestPool, NoopNetwork> {
+        let header = Header { gas_limit: ETHEREUM_BLOCK_GAS_LIMIT, ..Default::default() };
+        provider.add_block(H256::random(), Block { header, ..Default::default() });
+        build_test_eth_api(provider)
+    }
+
+    /// Returns an [Env] for a call from `from` to `to` with a gas limit of `1_000_000`, in a block
+    /// with a gas limit of `30_000_000` and without base fee checks.
+    fn test_call_env(from: Address, to: Address) -> Env {
+        let mut env = Env::default();
+        env.cfg.disable_base_fee = true;
+        env.block.gas_limit = U256::from(30_000_000u64);
+        env.tx.caller = from;
+        env.tx.transact_to = TransactTo::Call(to);
+        env.tx.gas_limit = 1_000_000;
+        env
+    }
+
+    /// Returns a [MockEthProvider] whose blocks are executed with the rules of Shanghai.
+    fn shanghai_provider() -> MockEthProvider {
+        let chain_spec = ChainSpecBuilder::mainnet().shanghai_activated().build();
+        MockEthProvider { chain_spec: Arc::new(chain_spec), ..Default::default() }
+    }
+
+    /// Estimates the gas of calling a contract with the given `code`
+    fn estimate_gas_with_code(code: Vec<u8>, request: CallRequest) -> EthResult<U256> {
+        estimate_gas_with_code_at_spec(code, request, SpecId::LATEST)
+    }
+
+    /// Same as [estimate_gas_with_code] but with the EVM configured for the given [SpecId].
+    fn estimate_gas_with_code_at_spec(
+        code: Vec<u8>,
+        request: CallRequest,
+        spec_id: SpecId,
+    ) -> EthResult<U256> {
+        let to = Address::random();
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()));
+
+        let eth_api = build_test_eth_api(provider.clone());
+        let block = BlockEnv {
+            gas_limit: U256::from(ETHEREUM_BLOCK_GAS_LIMIT),
+            prevrandao: Some(H256::zero()),
+            ..Default::default()
+        };
+        let request = CallRequest { to: Some(to), ..request };
+        let cfg = CfgEnv { spec_id, ..Default::default() };
+        eth_api.estimate_gas_with(cfg, block, request, provider)
+    }
+
+    #[test]
+    fn estimate_gas_includes_access_list_cost() {
+        let access_list = AccessList(vec![
+            AccessListItem { address: Address::random(), storage_keys: vec![H256::random()] },
+            AccessListItem { address: Address::random(), storage_keys: vec![] },
+        ]);
+        assert_eq!(access_list_intrinsic_gas(&access_list), 2 * 2_400 + 1_900);
+        let with_access_list =
+            || CallRequest { access_list: Some(access_list.clone()), ..Default::default() };
+
+        // PUSH1 0x01 PUSH1 0x00 MSTORE STOP
+        let code = vec![0x60, 0x01, 0x60, 0x00, 0x52, 0x00];
+        let without = estimate_gas_with_code(code.clone(), Default::default()).unwrap();
+        let with = estimate_gas_with_code(code, with_access_list()).unwrap();
+        assert_eq!(with - without, U256::from(2 * 2_400 + 1_900));
+
+        // simple transfer
+        let transfer = estimate_gas_with_code(vec![], with_access_list()).unwrap();
+        assert_eq!(transfer, U256::from(21_000 + 2 * 2_400 + 1_900));
+    }
+
+    #[test]
+    fn estimate_gas_with_max_iterations() {
+        let to = Address::random();
+        // PUSH1 0x01 PUSH1 0x00 SSTORE STOP
+        let code = vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00];
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()));
+
+        let eth_api = build_test_eth_api(provider.clone());
+        let block = BlockEnv {
+            gas_limit: U256::from(ETHEREUM_BLOCK_GAS_LIMIT),
+            prevrandao: Some(H256::zero()),
+            ..Default::default()
+        };
+        let request = CallRequest { to: Some(to), ..Default::default() };
+        let estimate = |max_iterations| {
+            let db = SubState::new(State::new(provider.clone()));
+            eth_api
+                .estimate_gas_bounded_with_db(
+                    CfgEnv::default(),
+                    block.clone(),
+                    request.clone(),
+                    db,
+                    max_iterations,
+                )
+                .unwrap()
+        };
+
+        let exact = estimate(None);
+        assert!(!exact.truncated);
+
+        let bounded = estimate(Some(2));
+        assert!(bounded.truncated);
+        assert!(bounded.gas > exact.gas);
+
+        // the truncated estimate is enough for the call to succeed
+        let mut db = SubState::new(State::new(provider.clone()));
+        let request = CallRequest { gas: Some(bounded.gas), ..request };
+        let env = build_call_evm_env(CfgEnv::default(), block, request).unwrap();
+        let (res, _) = transact(&mut db, env).unwrap();
+        assert_matches!(res.result, ExecutionResult::Success { .. });
+    }
+
+    #[tokio::test]
+    async fn estimate_gas_on_top_of_pending_tx() {
+        let sender = Address::random();
+        // the recipient of every mock transaction
+        let caller = Address::from_str("0xd3e8763675e4c425df46cc3b5c0f6cbdac396046").unwrap();
+        let value = 693361000000000u64;
+
+        let provider = MockEthProvider::default();
+        provider.add_account(sender, ExtendedAccount::new(0, U256::from(value * 2)));
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request = CallRequest {
+            from: Some(caller),
+            to: Some(Address::random()),
+            value: Some(U256::from(value / 2)),
+            ..Default::default()
+        };
+
+        // the caller has no funds in the committed state
+        let pending_block = BlockId::Number(BlockNumberOrTag::Pending);
+        let res = eth_api.estimate_gas_with_pending_txs_at(request.clone(), pending_block).await;
+        assert_matches!(res, Err(EthApiError::EstimateGas(EstimateGasError::InsufficientFunds)));
+
+        // a pending transaction funds the caller
+        let pending =
+            MockTransaction::legacy().with_sender(sender).with_gas_limit(MIN_TRANSACTION_GAS);
+        eth_api.pool().add_transaction(TransactionOrigin::External, pending).await.unwrap();
+
+        let res = eth_api.estimate_gas_with_pending_txs_at(request.clone(), pending_block);
+        assert_eq!(res.await.unwrap(), U256::from(MIN_TRANSACTION_GAS));
+
+        // the pending transactions are ignored for other blocks
+        let latest = BlockId::Number(BlockNumberOrTag::Latest);
+        let res = eth_api.estimate_gas_with_pending_txs_at(request, latest).await;
+        assert_matches!(res, Err(EthApiError::EstimateGas(EstimateGasError::InsufficientFunds)));
+    }
+
+    #[tokio::test]
+    async fn concurrent_estimates_dont_block_runtime() {
+        // loops until less than 100_000 gas is left:
+        // JUMPDEST PUSH3 0x0186a0 GAS GT PUSH1 0x00 JUMPI STOP
+        let code = vec![0x5b, 0x62, 0x01, 0x86, 0xa0, 0x5a, 0x11, 0x60, 0x00, 0x57, 0x00];
+        let to = Address::random();
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()));
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request = CallRequest { to: Some(to), ..Default::default() };
+        let at = BlockId::Number(BlockNumberOrTag::Latest);
+        let expected = eth_api.estimate_gas_at(request.clone(), at).await.unwrap();
+
+        let estimates = (0..4)
+            .map(|_| {
+                let eth_api = eth_api.clone();
+                let request = request.clone();
+                tokio::spawn(async move { eth_api.estimate_gas_at(request, at).await })
+            })
+            .collect::<Vec<_>>();
+
+        // the test runtime is single threaded, so this task only makes progress while the
+        // estimates are running if they don't block the runtime
+        let mut ticks = 0;
+        while !estimates.iter().all(|estimate| estimate.is_finished()) {
+            ticks += 1;
+            tokio::task::yield_now().await;
+        }
+        assert!(ticks > 1);
+
+        for estimate in estimates {
+            assert_eq!(estimate.await.unwrap().unwrap(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn call_tree_gas_per_frame() {
+        let (cheap, expensive) = (Address::from_low_u64_be(0xb0), Address::from_low_u64_be(0xc0));
+        // CALL(gas, addr, 0, 0, 0, 0, 0) POP, for both callees, then STOP:
+        // PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 addr GAS CALL POP
+        let call =
+            |addr: u8| [0x60, 0, 0x60, 0, 0x60, 0, 0x60, 0, 0x60, 0, 0x60, addr, 0x5a, 0xf1, 0x50];
+        let mut code = [call(0xb0), call(0xc0)].concat();
+        code.push(0x00);
+        // PUSH1 0x01 POP STOP
+        let cheap_code = vec![0x60, 0x01, 0x50, 0x00];
+        // PUSH1 0x01 PUSH1 0x00 SSTORE STOP
+        let expensive_code = vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00];
+
+        let to = Address::random();
+        let provider = shanghai_provider();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()));
+        provider.add_account(
+            cheap,
+            ExtendedAccount::new(0, U256::ZERO).with_bytecode(cheap_code.into()),
+        );
+        provider.add_account(
+            expensive,
+            ExtendedAccount::new(0, U256::ZERO).with_bytecode(expensive_code.into()),
+        );
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request = CallRequest { to: Some(to), ..Default::default() };
+        let root = eth_api.call_with_calls(request, None, Default::default()).await.unwrap();
+
+        assert_eq!(root.calls.len(), 2);
+        let (first, second) = (&root.calls[0], &root.calls[1]);
+        assert_eq!((first.to, second.to), (Some(cheap), Some(expensive)));
+        // PUSH1 POP
+        assert_eq!(first.gas_used, U256::from(5));
+        // PUSH1 PUSH1 and a cold SSTORE
+        assert_eq!(second.gas_used, U256::from(6 + 22_100));
+        for frame in [&root, first, second] {
+            assert!(frame.gas_used <= frame.gas);
+        }
+
+        // the gas used by the root frame is the gas used by the whole call: the intrinsic gas, the
+        // subcalls and the code of the root frame itself, which are 6 PUSH1, GAS, a CALL to a cold
+        // account and POP per subcall
+        let root_code = 2 * (6 * 3 + 2 + 2_600 + 2);
+        let expected = MIN_TRANSACTION_GAS + root_code;
+        assert_eq!(root.gas_used, first.gas_used + second.gas_used + U256::from(expected));
+    }
+
+    #[tokio::test]
+    async fn call_tree_frame_outputs() {
+        let (implementation, reverter) =
+            (Address::from_low_u64_be(0xb0), Address::from_low_u64_be(0xc0));
+        // DELEGATECALL(gas, implementation, 0, 0, 0, 0x20) POP,
+        // CALL(gas, reverter, 0, 0, 0, 0, 0) POP, then RETURN(0, 0x20):
+        // PUSH1 0x20 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 addr GAS DELEGATECALL POP
+        // PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 addr GAS CALL POP
+        // PUSH1 0x20 PUSH1 0x00 RETURN
+        let code = vec![
+            0x60, 0x20, 0x60, 0, 0x60, 0, 0x60, 0, 0x60, 0xb0, 0x5a, 0xf4, 0x50, 0x60, 0, 0x60, 0,
+            0x60, 0, 0x60, 0, 0x60, 0, 0x60, 0xc0, 0x5a, 0xf1, 0x50, 0x60, 0x20, 0x60, 0, 0xf3,
+        ];
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        let implementation_code = vec![0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+        // PUSH1 0x2b PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 REVERT
+        let reverter_code = vec![0x60, 0x2b, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xfd];
+
+        let proxy = Address::random();
+        let provider = shanghai_provider();
+        provider.add_account(proxy, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()));
+        provider.add_account(
+            implementation,
+            ExtendedAccount::new(0, U256::ZERO).with_bytecode(implementation_code.into()),
+        );
+        provider.add_account(
+            reverter,
+            ExtendedAccount::new(0, U256::ZERO).with_bytecode(reverter_code.into()),
+        );
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request = CallRequest { to: Some(proxy), ..Default::default() };
+        let options = CallTreeOptions { include_outputs: true };
+        let root = eth_api
+            .call_with_calls_options(request.clone(), None, Default::default(), options)
+            .await
+            .unwrap();
+
+        let word = |value: u64| Some(Bytes::from(H256::from_low_u64_be(value).0.to_vec()));
+        assert_eq!(root.calls.len(), 2);
+        let (delegated, reverted) = (&root.calls[0], &root.calls[1]);
+        // the proxy returns what the implementation returned to it
+        assert_eq!(root.output, word(0x2a));
+        assert_eq!(root.error, None);
+        assert_eq!(delegated.typ, "DELEGATECALL");
+        assert_eq!(delegated.output, word(0x2a));
+        assert_eq!(delegated.error, None);
+        // the revert data is the output of the reverted frame
+        assert_eq!(reverted.output, word(0x2b));
+        assert!(reverted.error.is_some());
+
+        // outputs are left out by default
+        let root = eth_api.call_with_calls(request, None, Default::default()).await.unwrap();
+        assert_eq!(root.output, None);
+        assert!(root.calls.iter().all(|frame| frame.output.is_none()));
+        assert!(root.calls[1].error.is_some());
+    }
+
+    #[tokio::test]
+    async fn call_traced_only_on_revert() {
+        let (returner, reverter) = (Address::random(), Address::from_low_u64_be(0xc0));
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        let returner_code = vec![0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+        // PUSH1 0x2b PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 REVERT
+        let reverter_code = vec![0x60, 0x2b, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xfd];
+        // CALL(gas, reverter, 0, 0, 0, 0, 0x20) POP, then REVERT(0, 0x20) with its revert data:
+        // PUSH1 0x20 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 addr GAS CALL POP
+        // PUSH1 0x20 PUSH1 0x00 REVERT
+        let caller_code = vec![
+            0x60, 0x20, 0x60, 0, 0x60, 0, 0x60, 0, 0x60, 0, 0x60, 0xc0, 0x5a, 0xf1, 0x50, 0x60,
+            0x20, 0x60, 0, 0xfd,
+        ];
+
+        let caller = Address::random();
+        let provider = shanghai_provider();
+        for (address, code) in
+            [(returner, returner_code), (reverter, reverter_code), (caller, caller_code)]
+        {
+            provider.add_account(
+                address,
+                ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()),
+            );
+        }
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let word = |value: u64| Bytes::from(H256::from_low_u64_be(value).0.to_vec());
+        let request = CallRequest { to: Some(returner), ..Default::default() };
+        let (output, frames) =
+            eth_api.call_trace_on_revert(request, None, Default::default()).await.unwrap();
+        assert_eq!(output, word(0x2a));
+        assert!(frames.is_none());
+
+        let request = CallRequest { to: Some(caller), ..Default::default() };
+        let (output, frames) =
+            eth_api.call_trace_on_revert(request, None, Default::default()).await.unwrap();
+        assert_eq!(output, word(0x2b));
+        let frames = frames.unwrap();
+        assert_eq!(frames.len(), 2);
+        let (root, reverted) = (&frames[0], &frames[1]);
+        assert_eq!(root.to, Some(caller));
+        assert!(root.error.is_some());
+        assert!(root.calls.is_empty());
+        assert_eq!(reverted.to, Some(reverter));
+        assert_eq!(reverted.output, Some(word(0x2b)));
+        assert!(reverted.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn call_from_sender_with_code() {
+        let sender = Address::random();
+        let to = Address::random();
+        let provider = MockEthProvider::default();
+        // STOP
+        let account = ExtendedAccount::new(0, U256::ZERO).with_bytecode(vec![0x00].into());
+        provider.add_account(sender, account);
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request = CallRequest { from: Some(sender), to: Some(to), ..Default::default() };
+        let at = BlockId::Number(BlockNumberOrTag::Latest);
+        // calls and estimates from senders with code are allowed by default
+        let res = eth_api.call(request.clone(), None, EvmOverrides::default()).await;
+        assert_matches!(res, Ok(_));
+        let res = eth_api.estimate_gas_at(request.clone(), at).await;
+        assert_matches!(res, Ok(_));
+
+        let overrides = EvmOverrides::default().with_eip3607_enforced(true);
+        let res = eth_api.call(request.clone(), None, overrides).await;
+        assert_matches!(
+            res,
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::SenderNoEOA))
+        );
+        let options = EstimateGasOptions { enforce_eip3607: true, ..Default::default() };
+        let res = eth_api.estimate_gas_with_options_at(request, at, options).await;
+        assert_matches!(
+            res,
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::SenderNoEOA))
+        );
+    }
+
+    #[tokio::test]
+    async fn call_with_gas_refund_disabled() {
+        let to = Address::random();
+        // PUSH1 0x00 PUSH1 0x00 SSTORE STOP
+        let code = vec![0x60, 0x00, 0x60, 0x00, 0x55, 0x00];
+        let provider = shanghai_provider();
+        provider.add_account(
+            to,
+            ExtendedAccount::new(0, U256::ZERO)
+                .with_bytecode(code.into())
+                .extend_storage([(H256::zero(), U256::from(1))]),
+        );
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request = CallRequest { to: Some(to), ..Default::default() };
+        let at = BlockId::Number(BlockNumberOrTag::Latest);
+        let gas_used = |overrides| {
+            let (eth_api, request) = (eth_api.clone(), request.clone());
+            async move {
+                let (res, _) = eth_api.transact_call_at(request, at, overrides).await.unwrap();
+                res.result.gas_used()
+            }
+        };
+
+        // two PUSH1 and clearing a cold slot
+        let without_refund = MIN_TRANSACTION_GAS + 2 * 3 + 5_000;
+        assert_eq!(gas_used(EvmOverrides::default()).await, without_refund - 4_800);
+        let overrides = EvmOverrides::default().with_gas_refund_disabled(true);
+        assert_eq!(gas_used(overrides).await, without_refund);
+    }
+
+    #[tokio::test]
+    async fn call_output_size_limit() {
+        let to = Address::random();
+        // PUSH2 0x1000 PUSH1 0x00 RETURN, returns 4096 zero bytes
+        let code = vec![0x61, 0x10, 0x00, 0x60, 0x00, 0xf3];
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()));
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request = CallRequest { to: Some(to), ..Default::default() };
+        let limited = |limit| {
+            EvmOverrides::default().with_limits(CallLimits::default().with_max_output_bytes(limit))
+        };
+        let call = |limit| eth_api.call(request.clone(), None, limited(limit));
+
+        let res = call(1024).await;
+        assert_matches!(res, Err(EthApiError::CallOutputTooLarge { size: 4096, limit: 1024 }));
+
+        let output = call(4096).await.unwrap();
+        assert_eq!(output.len(), 4096);
+
+        // unlimited by default
+        let output = eth_api.call(request.clone(), None, Default::default()).await.unwrap();
+        assert_eq!(output.len(), 4096);
+
+        // the variants that execute the call with an inspector are limited as well
+        let res = eth_api.call_with_bloom(request.clone(), None, limited(1024)).await;
+        assert_matches!(res, Err(EthApiError::CallOutputTooLarge { .. }));
+        let res = eth_api.call_with_access_counts(request.clone(), None, limited(1024)).await;
+        assert_matches!(res, Err(EthApiError::CallOutputTooLarge { .. }));
+        let res = eth_api.call_trace_on_revert(request.clone(), None, limited(1024)).await;
+        assert_matches!(res, Err(EthApiError::CallOutputTooLarge { .. }));
+        let inspector = OpcodeCountInspector::default();
+        let res = eth_api.call_with_inspector(request, None, limited(1024), inspector).await;
+        assert_matches!(res, Err(EthApiError::CallOutputTooLarge { .. }));
+    }
+
+    #[tokio::test]
+    async fn call_depth_limit() {
+        let to = Address::random();
+        // calls itself with all gas: PUSH1 0x00 (x5) ADDRESS GAS CALL STOP
+        let code = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x30, 0x5a, 0xf1, 0x00,
+        ];
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()));
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request = CallRequest { to: Some(to), ..Default::default() };
+        let limited =
+            || EvmOverrides::default().with_limits(CallLimits::default().with_max_call_depth(8));
+
+        assert!(eth_api.call(request.clone(), None, Default::default()).await.is_ok());
+
+        // every variant is limited, including the ones executing the call with an inspector
+        let res = eth_api.call(request.clone(), None, limited()).await;
+        assert_matches!(
+            res,
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::CallDepthExceeded))
+        );
+        let res = eth_api.call_with_logs(request.clone(), None, limited()).await;
+        assert_matches!(
+            res,
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::CallDepthExceeded))
+        );
+        let res = eth_api.call_trace_on_revert(request.clone(), None, limited()).await;
+        assert_matches!(
+            res,
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::CallDepthExceeded))
+        );
+        let inspector = OpcodeCountInspector::default();
+        let res = eth_api.call_with_inspector(request, None, limited(), inspector).await;
+        assert_matches!(
+            res,
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::CallDepthExceeded))
+        );
+    }
+
+    #[tokio::test]
+    async fn call_with_gas_left() {
+        let to = Address::random();
+        // clears the set slot 0: PUSH1 0x00 PUSH1 0x00 SSTORE STOP
+        let code = vec![0x60, 0x00, 0x60, 0x00, 0x55, 0x00];
+        let provider = shanghai_provider();
+        provider.add_account(
+            to,
+            ExtendedAccount::new(0, U256::ZERO)
+                .with_bytecode(code.into())
+                .extend_storage([(H256::zero(), U256::from(1))]),
+        );
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request =
+            CallRequest { to: Some(to), gas: Some(U256::from(100_000)), ..Default::default() };
+        let res = eth_api.call_with_gas(request, None, Default::default()).await.unwrap();
+        assert!(res.output.is_empty());
+        assert_eq!(res.gas_limit, 100_000);
+        // the cold SSTORE resetting the slot costs 5_000 and refunds 4_800
+        let spent = MIN_TRANSACTION_GAS + 3 + 3 + 5_000;
+        assert_eq!(res.gas_limit - res.gas_left, spent);
+        assert_eq!(res.gas_used, spent - 4_800);
+    }
+
+    #[tokio::test]
+    async fn call_with_counting_inspector() {
+        // PUSH1 0x01 PUSH1 0x02 ADD STOP
+        let code = vec![0x60, 0x01, 0x60, 0x02, 0x01, 0x00];
+        let to = Address::random();
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()));
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request = CallRequest { to: Some(to), ..Default::default() };
+        let (output, inspector) = eth_api
+            .call_with_inspector(request, None, Default::default(), OpcodeCountInspector::default())
+            .await
+            .unwrap();
+        assert!(output.is_empty());
+        assert_eq!(inspector.count(), 4);
+    }
+
+    #[tokio::test]
+    async fn call_with_logs_bloom() {
+        // PUSH1 0x2b PUSH1 0x2a PUSH1 0x00 PUSH1 0x00 LOG2 STOP
+        let code = vec![0x60, 0x2b, 0x60, 0x2a, 0x60, 0x00, 0x60, 0x00, 0xa2, 0x00];
+        let to = Address::random();
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.into()));
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request = CallRequest { to: Some(to), ..Default::default() };
+        let (output, bloom) =
+            eth_api.call_with_bloom(request, None, Default::default()).await.unwrap();
+        assert!(output.is_empty());
+
+        let topics = [H256::from_low_u64_be(0x2a), H256::from_low_u64_be(0x2b)];
+        for input in topics.iter().map(|topic| topic.as_bytes()).chain([to.as_bytes()]) {
+            assert!(bloom.contains_bloom(&Bloom::from(Input::Raw(input))));
+        }
+        let expected = Log { address: to, topics: topics.to_vec(), data: Bytes::default() };
+        assert_eq!(bloom, logs_bloom(&[expected]));
+    }
+
+    #[tokio::test]
+    async fn call_batch_matches_individual_calls() {
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        let returns = vec![0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+        // PUSH1 0x00 PUSH1 0x00 REVERT
+        let reverts = vec![0x60, 0x00, 0x60, 0x00, 0xfd];
+        let (returning, reverting) = (Address::random(), Address::random());
+        let provider = MockEthProvider::default();
+        provider.add_account(
+            returning,
+            ExtendedAccount::new(0, U256::ZERO).with_bytecode(returns.into()),
+        );
+        provider.add_account(
+            reverting,
+            ExtendedAccount::new(0, U256::ZERO).with_bytecode(reverts.into()),
+        );
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let requests = vec![
+            CallRequest { to: Some(returning), ..Default::default() },
+            CallRequest { to: Some(reverting), ..Default::default() },
+            CallRequest { to: Some(Address::random()), ..Default::default() },
+        ];
+        let results = eth_api.call_batch(requests.clone(), None, Default::default()).await.unwrap();
+        assert_eq!(results.len(), requests.len());
+
+        // the reverting call doesn't abort the batch
+        let expected = Bytes::from(H256::from_low_u64_be(0x2a).as_bytes().to_vec());
+        assert_eq!(results[0].as_ref().unwrap(), &expected);
+        assert_matches!(results[1], Err(EthApiError::InvalidTransaction(_)));
+        assert!(results[2].as_ref().unwrap().is_empty());
+
+        for (request, result) in requests.into_iter().zip(results) {
+            let individual = eth_api.call(request, None, Default::default()).await;
+            assert_eq!(
+                individual.map_err(|err| err.to_string()),
+                result.map_err(|err| err.to_string())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn call_session_accumulates_state() {
+        // PUSH1 0x00 SLOAD PUSH1 0x01 ADD DUP1 PUSH1 0x00 SSTORE PUSH1 0x00 MSTORE PUSH1 0x20
+        // PUSH1 0x00 RETURN
+        let counter = vec![
+            0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x80, 0x60, 0x00, 0x55, 0x60, 0x00, 0x52, 0x60,
+            0x20, 0x60, 0x00, 0xf3,
+        ];
+        let to = Address::random();
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(counter.into()));
+        let eth_api = build_test_eth_api_with_block(provider);
+
+        let request = CallRequest { to: Some(to), ..Default::default() };
+        let counter_value = |value| Bytes::from(H256::from_low_u64_be(value).as_bytes().to_vec());
+
+        let mut session = eth_api.call_session(None, Default::default()).await.unwrap();
+        assert_eq!(session.call(request.clone()).unwrap(), counter_value(1));
+        // the second call observes the increment of the first one
+        assert_eq!(session.call(request.clone()).unwrap(), counter_value(2));
+
+        // the session's changes are never written to the underlying state
+        let output = eth_api.call(request, None, Default::default()).await.unwrap();
+        assert_eq!(output, counter_value(1));
+    }
+
+    #[tokio::test]
+    async fn call_variants_validate_requests() {
+        let provider = shanghai_provider();
+        let eth_api = build_test_eth_api_with_block(provider);
+        let at = BlockId::Number(BlockNumberOrTag::Latest);
+
+        // EIP-2930 requests are priced with the gas price only, which only the validation rejects
+        let request = CallRequest {
+            to: Some(Address::random()),
+            transaction_type: Some(U8::from(EIP2930_TX_TYPE_ID)),
+            max_fee_per_gas: Some(U256::from(1)),
+            ..Default::default()
+        };
+        fn conflicting<T>(res: EthResult<T>) {
+            assert_matches!(res.map(|_| ()), Err(EthApiError::ConflictingFeeFieldsInRequest));
+        }
+
+        conflicting(eth_api.call(request.clone(), None, Default::default()).await);
+        conflicting(eth_api.call_with_gas(request.clone(), None, Default::default()).await);
+        conflicting(eth_api.call_with_logs(request.clone(), None, Default::default()).await);
+        conflicting(eth_api.estimate_gas_bounded_at(request.clone(), at, None).await);
+        conflicting(eth_api.estimate_gas_with_margin_at(request.clone(), at, 10).await);
+
+        let batch = eth_api.call_batch(vec![request.clone()], None, Default::default()).await;
+        conflicting(batch.unwrap().remove(0));
+
+        let mut session = eth_api.call_session(None, Default::default()).await.unwrap();
+        conflicting(session.call(request));
+    }
+
+    #[tokio::test]
+    async fn estimate_gas_strict_fees() {
+        let (caller, to) = (Address::random(), Address::random());
+        let provider = shanghai_provider();
+        provider.add_account(caller, ExtendedAccount::new(0, U256::from(10u64.pow(18))));
+        let header = Header {
+            gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
+            base_fee_per_gas: Some(100),
+            ..Default::default()
+        };
+        provider.add_block(H256::random(), Block { header, ..Default::default() });
+        let eth_api = build_test_eth_api(provider);
+        let at = BlockId::Number(BlockNumberOrTag::Latest);
+        let strict = EstimateGasOptions { strict_fees: true, ..Default::default() };
+
+        // underpriced requests are only rejected in strict mode
+        let legacy = CallRequest {
+            from: Some(caller),
+            to: Some(to),
+            gas_price: Some(U256::from(50)),
+            ..Default::default()
+        };
+        let lenient =
+            eth_api.estimate_gas_with_options_at(legacy.clone(), at, Default::default()).await;
+        assert_eq!(lenient.unwrap(), U256::from(MIN_TRANSACTION_GAS));
+        assert_matches!(
+            eth_api.estimate_gas_with_options_at(legacy, at, strict).await,
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::FeeCapTooLow))
+        );
+
+        let eip1559 = CallRequest {
+            from: Some(caller),
+            to: Some(to),
+            max_fee_per_gas: Some(U256::from(50)),
+            max_priority_fee_per_gas: Some(U256::from(1)),
+            ..Default::default()
+        };
+        let lenient =
+            eth_api.estimate_gas_with_options_at(eip1559.clone(), at, Default::default()).await;
+        assert_eq!(lenient.unwrap(), U256::from(MIN_TRANSACTION_GAS));
+        assert_matches!(
+            eth_api.estimate_gas_with_options_at(eip1559.clone(), at, strict).await,
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::FeeCapTooLow))
+        );
+
+        // a fee cap above the base fee passes strict validation
+        let priced = CallRequest { max_fee_per_gas: Some(U256::from(200)), ..eip1559 };
+        let estimate = eth_api.estimate_gas_with_options_at(priced, at, strict).await;
+        assert_eq!(estimate.unwrap(), U256::from(MIN_TRANSACTION_GAS));
+    }
+
+    #[tokio::test]
+    async fn estimate_gas_revert() {
+        // PUSH1 0x00 PUSH1 0x00 REVERT
+        let res = estimate_gas_with_code(vec![0x60, 0x00, 0x60, 0x00, 0xfd], Default::default());
+        assert_matches!(
+            res,
+            Err(EthApiError::EstimateGas(EstimateGasError::Revert { reason: None, data: None }))
+        );
+    }
+
+    #[test]
+    fn revert_data_of_custom_error() {
+        let to = Address::random();
+        // PUSH4 0xdeadbeef PUSH1 0x00 MSTORE PUSH1 0x2a PUSH1 0x20 MSTORE PUSH1 0x24 PUSH1 0x1c
+        // REVERT
+        let code = vec![
+            0x63, 0xde, 0xad, 0xbe, 0xef, 0x60, 0x00, 0x52, 0x60, 0x2a, 0x60, 0x20, 0x52, 0x60,
+            0x24, 0x60, 0x1c, 0xfd,
+        ];
+        // the selector of the custom error followed by its argument
+        let mut expected = vec![0xde, 0xad, 0xbe, 0xef];
+        expected.extend_from_slice(H256::from_low_u64_be(0x2a).as_bytes());
+        let expected_data = format!("\"0x{}\"", hex::encode(&expected));
+
+        let provider = MockEthProvider::default();
+        provider.add_account(
+            to,
+            ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.clone().into()),
+        );
+        let mut db = SubState::new(State::new(provider));
+        let block = BlockEnv {
+            gas_limit: U256::from(ETHEREUM_BLOCK_GAS_LIMIT),
+            prevrandao: Some(H256::zero()),
+            ..Default::default()
+        };
+        let request = CallRequest { to: Some(to), ..Default::default() };
+        let env = build_call_evm_env(CfgEnv::default(), block, request).unwrap();
+        let (res, _) = transact(&mut db, env).unwrap();
+        let call_err = ensure_success(res.result).unwrap_err();
+        assert_eq!(call_err.revert_data().map(|data| data.to_vec()), Some(expected.clone()));
+        let call_err = ErrorObject::from(call_err);
+        assert_eq!(call_err.data().map(|data| data.get()), Some(expected_data.as_str()));
+
+        let estimate_err = estimate_gas_with_code(code, Default::default()).unwrap_err();
+        assert_eq!(estimate_err.revert_data().map(|data| data.to_vec()), Some(expected));
+        let estimate_err = ErrorObject::from(estimate_err);
+        assert_eq!(estimate_err.data().map(|data| data.get()), Some(expected_data.as_str()));
+    }
+
+    #[test]
+    fn halt_reason_in_rpc_error() {
+        // ADD with an empty stack, an unassigned opcode and INVALID
+        for (code, message, reason) in [
+            (0x01, "EVM error: stack underflow", "StackUnderflow"),
+            (0x0c, "EVM error: invalid opcode", "OpcodeNotFound"),
+            (0xfe, "EVM error: invalid opcode: INVALID", "InvalidFEOpcode"),
+        ] {
+            let to = Address::random();
+            let provider = MockEthProvider::default();
+            provider.add_account(
+                to,
+                ExtendedAccount::new(0, U256::ZERO).with_bytecode(vec![code].into()),
+            );
+            let mut db = SubState::new(State::new(provider));
+            let block = BlockEnv {
+                gas_limit: U256::from(ETHEREUM_BLOCK_GAS_LIMIT),
+                prevrandao: Some(H256::zero()),
+                ..Default::default()
+            };
+            let request = CallRequest { to: Some(to), ..Default::default() };
+            let env = build_call_evm_env(CfgEnv::default(), block, request).unwrap();
+            let (res, _) = transact(&mut db, env).unwrap();
+
+            let err = ErrorObject::from(ensure_success(res.result).unwrap_err());
+            assert_eq!(err.message(), message);
+            let expected_data = format!("\"{reason}\"");
+            assert_eq!(err.data().map(|data| data.get()), Some(expected_data.as_str()));
+        }
+    }
+
+    #[tokio::test]
+    async fn estimate_gas_too_low_explicit_gas() {
+        // the intrinsic gas of the calldata exceeds the provided gas limit
+        let request = CallRequest {
+            gas: Some(U256::from(MIN_TRANSACTION_GAS)),
+            input: Bytes::from(vec![0x01]).into(),
+            ..Default::default()
+        };
+        // STOP
+        let res = estimate_gas_with_code(vec![0x00], request);
+        assert_matches!(
+            res,
+            Err(EthApiError::EstimateGas(EstimateGasError::OutOfGas { provided }))
+                if provided == U256::from(MIN_TRANSACTION_GAS)
+        );
+    }
+
+    #[tokio::test]
+    async fn estimate_gas_selfdestruct_across_forks() {
+        // CALLER SELFDESTRUCT
+        let code = vec![0x33, 0xff];
+
+        // the refund of SELFDESTRUCT before London is only applied after execution, so it doesn't
+        // lower the gas limit the call needs: 21000 + CALLER (2) + SELFDESTRUCT (5000)
+        for spec_id in [SpecId::BERLIN, SpecId::LONDON, SpecId::CANCUN] {
+            let estimate =
+                estimate_gas_with_code_at_spec(code.clone(), Default::default(), spec_id).unwrap();
+            assert_eq!(estimate, U256::from(26_002), "{spec_id:?}");
+
+            // estimating is stable
+            let again =
+                estimate_gas_with_code_at_spec(code.clone(), Default::default(), spec_id).unwrap();
+            assert_eq!(again, estimate, "{spec_id:?}");
+        }
+
+        // the gas used reflects the fork specific refund, capped at half of the gas used before
+        // London and removed with EIP-3529
+        for (spec_id, expected) in [(SpecId::BERLIN, 13_001), (SpecId::LONDON, 26_002)] {
+            let to = Address::random();
+            let provider = MockEthProvider::default();
+            provider.add_account(
+                to,
+                ExtendedAccount::new(0, U256::ZERO).with_bytecode(code.clone().into()),
+            );
+            let mut db = SubState::new(State::new(provider));
+            let cfg = CfgEnv { spec_id, ..Default::default() };
+            let block = BlockEnv {
+                gas_limit: U256::from(ETHEREUM_BLOCK_GAS_LIMIT),
+                prevrandao: Some(H256::zero()),
+                ..Default::default()
+            };
+            let request = CallRequest { to: Some(to), ..Default::default() };
+            let env = build_call_evm_env(cfg, block, request).unwrap();
+
+            let (res, _) = transact(&mut db, env).unwrap();
+            assert_matches!(res.result, ExecutionResult::Success { .. });
+            assert_eq!(res.result.gas_used(), expected, "{spec_id:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn estimate_gas_halt() {
+        // INVALID
+        let res = estimate_gas_with_code(vec![0xfe], Default::default());
+        assert_matches!(res, Err(EthApiError::EstimateGas(EstimateGasError::Halt { .. })));
+    }
+
+    #[tokio::test]
+    async fn spec_id_at_shanghai_activation() {
+        let provider = MockEthProvider::default();
+        // mainnet terminal total difficulty, reached before the blocks below
+        provider.add_header(
+            H256::random(),
+            Header {
+                number: 0,
+                difficulty: U256::from(58_750_000_000_000_000_000_000u128),
+                ..Default::default()
+            },
+        );
+        // last block before and first block after the mainnet shanghai activation
+        for (number, timestamp) in [(17_034_869u64, 1_681_338_443u64), (17_034_870, 1_681_338_455)]
+        {
+            let header = Header { number, timestamp, ..Default::default() };
+            provider.add_block(H256::random(), Block { header, ..Default::default() });
+        }
+        let eth_api = build_test_eth_api(provider);
+
+        let pre = eth_api.spec_id_at(BlockId::Number(17_034_869u64.into())).await.unwrap();
+        assert_eq!(pre, SpecId::MERGE);
+
+        let post = eth_api.spec_id_at(BlockId::Number(17_034_870u64.into())).await.unwrap();
+        assert_eq!(post, SpecId::SHANGHAI);
+    }
+
+    #[tokio::test]
+    async fn call_requires_canonical_block_hash() {
+        let provider = MockEthProvider::default();
+        let canonical = H256::random();
+        provider.add_block(canonical, Block::default());
+        // a block at the same height that was reorged out
+        let orphaned = H256::random();
+        provider.add_header(orphaned, Header { gas_limit: 1, ..Default::default() });
+        let eth_api = build_test_eth_api(provider);
+
+        let call = |hash: H256, require_canonical: Option<bool>| {
+            let request = CallRequest { to: Some(Address::random()), ..Default::default() };
+            let at = BlockId::from((hash, require_canonical));
+            eth_api.call(request, Some(at), Default::default())
+        };
+
+        assert!(call(canonical, Some(true)).await.is_ok());
+        assert_matches!(
+            call(orphaned, Some(true)).await,
+            Err(EthApiError::BlockHashNotCanonical(hash)) if hash == orphaned
+        );
+        let unknown = H256::random();
+        assert_matches!(call(unknown, Some(true)).await, Err(EthApiError::UnknownBlockNumber));
+
+        // non-canonical blocks are fine if not required otherwise
+        assert!(call(orphaned, None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn is_canonical_block_hash() {
+        let provider = MockEthProvider::default();
+        let canonical = H256::random();
+        let header = Header { number: 1, ..Default::default() };
+        provider.add_block(canonical, Block { header: header.clone(), ..Default::default() });
+        // a block at the same height that was reorged out
+        let orphaned = H256::random();
+        provider.add_header(orphaned, Header { gas_limit: 1, ..header });
+        let eth_api = build_test_eth_api(provider);
+
+        assert!(eth_api.is_canonical(canonical).unwrap());
+        assert!(!eth_api.is_canonical(orphaned).unwrap());
+        assert!(!eth_api.is_canonical(H256::random()).unwrap());
     }
 }
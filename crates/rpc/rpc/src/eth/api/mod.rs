@@ -40,6 +40,11 @@ mod state;
 mod transactions;
 
 use crate::TracingCallPool;
+pub use call::{
+    AccessCounts, AccessListOptions, BoundedGasEstimate, CallManyResult, CallSession,
+    CallTreeOptions, CallWithGas, EstimateGasOptions, GasEstimate, GasProfile, SimulatedBlock,
+    VerboseGasEstimate,
+};
 pub use transactions::{EthTransactions, TransactionSource};
 
 /// `Eth` API trait.
@@ -201,9 +206,42 @@ where
 {
     /// Returns the state at the given [BlockId] enum.
     pub fn state_at_block_id(&self, at: BlockId) -> EthResult<StateProviderBox<'_>> {
+        self.ensure_canonical_block(at)?;
         Ok(self.provider().state_by_block_id(at)?)
     }
 
+    /// Ensures that the block of a [BlockId::Hash] is canonical if the id requires it.
+    ///
+    /// Returns [EthApiError::UnknownBlockNumber] if the block is unknown and
+    /// [EthApiError::BlockHashNotCanonical] if the block is known but not part of the canonical
+    /// chain, for example because it was reorged out.
+    pub(crate) fn ensure_canonical_block(&self, at: BlockId) -> EthResult<()> {
+        let BlockId::Hash(hash) = at else { return Ok(()) };
+        if hash.require_canonical != Some(true) {
+            return Ok(())
+        }
+
+        let block_hash = hash.block_hash;
+        if !self.is_canonical(block_hash)? {
+            // tell unknown blocks apart from blocks that were reorged out
+            if self.provider().header(&block_hash)?.is_none() {
+                return Err(EthApiError::UnknownBlockNumber)
+            }
+            return Err(EthApiError::BlockHashNotCanonical(block_hash))
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the block with the given hash is part of the canonical chain.
+    ///
+    /// Only the number of the block and the canonical hash at that number are looked up, the block
+    /// itself is not loaded. Unknown hashes are not canonical, an error is only returned if a
+    /// lookup fails.
+    pub fn is_canonical(&self, hash: H256) -> EthResult<bool> {
+        let Some(number) = self.provider().block_number(hash)? else { return Ok(false) };
+        Ok(self.provider().block_hash(number)? == Some(hash))
+    }
+
     /// Returns the state at the given [BlockId] enum or the latest.
     ///
     /// Convenience function to interprets `None` as `BlockId::Number(BlockNumberOrTag::Latest)`
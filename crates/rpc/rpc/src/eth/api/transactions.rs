@@ -1,11 +1,13 @@
 //! Contains RPC handler implementations specific to transactions
+#[cfg(feature = "optimism")]
+use crate::eth::optimism::L1BlockInfo;
 use crate::{
     eth::{
         api::pending_block::PendingBlockEnv,
         error::{EthApiError, EthResult, SignError},
         revm_utils::{
-            inspect, inspect_and_return_db, prepare_call_env, replay_transactions_until, transact,
-            EvmOverrides,
+            inspect, inspect_and_return_db, inspect_with_limits, prepare_call_env,
+            replay_transactions_until, transact_with_limits, validate_call_request, EvmOverrides,
         },
         utils::recover_raw_transaction,
     },
@@ -14,10 +16,9 @@ use crate::{
 use async_trait::async_trait;
 use reth_network_api::NetworkInfo;
 use reth_primitives::{
-    Address, BlockId, BlockNumberOrTag, Bytes, FromRecoveredTransaction, Header,
-    IntoRecoveredTransaction, Receipt, SealedBlock,
-    TransactionKind::{Call, Create},
-    TransactionMeta, TransactionSigned, TransactionSignedEcRecovered, H256, U128, U256, U64,
+    Address, BlockId, BlockNumberOrTag, Bloom, Bytes, FromRecoveredTransaction, Header,
+    IntoRecoveredTransaction, Receipt, SealedBlock, TransactionMeta, TransactionSigned,
+    TransactionSignedEcRecovered, H256, U256,
 };
 use reth_rpc_types_compat::from_recovered_with_block_context;
 
@@ -30,7 +31,7 @@ use reth_revm::{
     tracing::{TracingInspector, TracingInspectorConfig},
 };
 use reth_rpc_types::{
-    CallRequest, Index, Log, Transaction, TransactionInfo, TransactionReceipt, TransactionRequest,
+    CallRequest, Index, Transaction, TransactionInfo, TransactionReceipt, TransactionRequest,
     TypedTransactionRequest,
 };
 use reth_transaction_pool::{TransactionOrigin, TransactionPool};
@@ -39,7 +40,7 @@ use revm::{
     primitives::{BlockEnv, CfgEnv},
     Inspector,
 };
-use revm_primitives::{utilities::create_address, Env, ResultAndState, SpecId};
+use revm_primitives::{Env, ResultAndState, SpecId};
 
 /// Helper alias type for the state's [CacheDB]
 pub(crate) type StateCacheDB<'r> = CacheDB<State<StateProviderBox<'r>>>;
@@ -135,6 +136,8 @@ pub trait EthTransactions: Send + Sync {
 
     /// Prepares the state and env for the given [CallRequest] at the given [BlockId] and executes
     /// the closure on a new task returning the result of the closure.
+    ///
+    /// Requests with conflicting or unsupported fields are rejected before the task is spawned.
     async fn spawn_with_call_at<F, R>(
         &self,
         request: CallRequest,
@@ -281,6 +284,7 @@ where
             let PendingBlockEnv { cfg, block_env, origin } = self.pending_block_env_and_cfg()?;
             Ok((cfg, block_env, origin.header().hash.into()))
         } else {
+            self.ensure_canonical_block(at)?;
             //  Use cached values if there is no pending block
             let block_hash = self
                 .provider()
@@ -516,6 +520,7 @@ where
         R: Send + 'static,
     {
         let (cfg, block_env, at) = self.evm_env_at(at).await?;
+        validate_call_request(&request, cfg.spec_id)?;
         let this = self.clone();
         self.inner
             .tracing_call_pool
@@ -543,8 +548,11 @@ where
         at: BlockId,
         overrides: EvmOverrides,
     ) -> EthResult<(ResultAndState, Env)> {
-        self.spawn_with_call_at(request, at, overrides, move |mut db, env| transact(&mut db, env))
-            .await
+        let limits = overrides.limits;
+        self.spawn_with_call_at(request, at, overrides, move |mut db, env| {
+            transact_with_limits(&mut db, env, limits)
+        })
+        .await
     }
 
     async fn spawn_inspect_call_at<I>(
@@ -557,8 +565,11 @@ where
     where
         I: for<'r> Inspector<StateCacheDB<'r>> + Send + 'static,
     {
-        self.spawn_with_call_at(request, at, overrides, move |db, env| inspect(db, env, inspector))
-            .await
+        let limits = overrides.limits;
+        self.spawn_with_call_at(request, at, overrides, move |db, env| {
+            inspect_with_limits(db, env, inspector, limits)
+        })
+        .await
     }
 
     fn trace_at<F, R>(
@@ -691,7 +702,16 @@ where
             Some(recpts) => recpts,
             None => return Err(EthApiError::UnknownBlockNumber),
         };
-        build_transaction_receipt_with_block_receipts(tx, meta, receipt, &all_receipts)
+        #[cfg(feature = "optimism")]
+        let l1_block_info = L1BlockInfo::from_state(&*self.state_at_hash(meta.block_hash)?)?;
+        build_transaction_receipt_with_block_receipts(
+            tx,
+            meta,
+            receipt,
+            &all_receipts,
+            #[cfg(feature = "optimism")]
+            &l1_block_info,
+        )
     }
 }
 
@@ -837,77 +857,102 @@ impl From<TransactionSource> for Transaction {
 }
 
 /// Helper function to construct a transaction receipt
+///
+/// With the `optimism` feature, the L1 data fee of the receipt is derived from the
+/// `l1_block_info` of the block.
 pub(crate) fn build_transaction_receipt_with_block_receipts(
     tx: TransactionSigned,
     meta: TransactionMeta,
     receipt: Receipt,
     all_receipts: &[Receipt],
+    #[cfg(feature = "optimism")] l1_block_info: &L1BlockInfo,
 ) -> EthResult<TransactionReceipt> {
-    let transaction =
-        tx.clone().into_ecrecovered().ok_or(EthApiError::InvalidTransactionSignature)?;
+    let transaction = tx.into_ecrecovered().ok_or(EthApiError::InvalidTransactionSignature)?;
 
     // get the previous transaction cumulative gas used
-    let gas_used = if meta.index == 0 {
-        receipt.cumulative_gas_used
+    let prev_cumulative_gas_used = if meta.index == 0 {
+        0
     } else {
         let prev_tx_idx = (meta.index - 1) as usize;
         all_receipts
             .get(prev_tx_idx)
-            .map(|prev_receipt| receipt.cumulative_gas_used - prev_receipt.cumulative_gas_used)
-            .unwrap_or_default()
+            .map(|prev_receipt| prev_receipt.cumulative_gas_used)
+            .unwrap_or(receipt.cumulative_gas_used)
     };
 
-    let mut res_receipt = TransactionReceipt {
-        transaction_hash: Some(meta.tx_hash),
-        transaction_index: meta.index.into(),
-        block_hash: Some(meta.block_hash),
-        block_number: Some(U256::from(meta.block_number)),
-        from: transaction.signer(),
-        to: None,
-        cumulative_gas_used: U256::from(receipt.cumulative_gas_used),
-        gas_used: Some(U256::from(gas_used)),
-        contract_address: None,
-        logs: Vec::with_capacity(receipt.logs.len()),
-        effective_gas_price: U128::from(transaction.effective_gas_price(meta.base_fee)),
-        transaction_type: tx.transaction.tx_type().into(),
-        // TODO pre-byzantium receipts have a post-transaction state root
-        state_root: None,
-        logs_bloom: receipt.bloom_slow(),
-        status_code: if receipt.success { Some(U64::from(1)) } else { Some(U64::from(0)) },
-    };
-
-    match tx.transaction.kind() {
-        Create => {
-            res_receipt.contract_address =
-                Some(create_address(transaction.signer(), tx.transaction.nonce()));
-        }
-        Call(addr) => {
-            res_receipt.to = Some(*addr);
-        }
-    }
-
     // get number of logs in the block
     let mut num_logs = 0;
     for prev_receipt in all_receipts.iter().take(meta.index as usize) {
         num_logs += prev_receipt.logs.len();
     }
 
-    for (tx_log_idx, log) in receipt.logs.into_iter().enumerate() {
-        let rpclog = Log {
-            address: log.address,
-            topics: log.topics,
-            data: log.data,
-            block_hash: Some(meta.block_hash),
-            block_number: Some(U256::from(meta.block_number)),
-            transaction_hash: Some(meta.tx_hash),
-            transaction_index: Some(U256::from(meta.index)),
-            log_index: Some(U256::from(num_logs + tx_log_idx)),
-            removed: false,
+    let tx_receipt = TransactionReceipt::from_execution(
+        &transaction,
+        receipt,
+        prev_cumulative_gas_used,
+        meta,
+        num_logs,
+    );
+    #[cfg(feature = "optimism")]
+    let tx_receipt = l1_block_info.apply(tx_receipt, &transaction);
+    Ok(tx_receipt)
+}
+
+/// Builds the receipts of all transactions of the block, together with the logs bloom of the block.
+///
+/// This is done in a single pass: the cumulative gas used and the log index are carried over from
+/// the preceding receipt, and the bloom of every receipt is computed once and accrued into the
+/// block bloom.
+///
+/// If `include_first_log_index` is set, every receipt carries the non-standard
+/// [TransactionReceipt::first_log_index].
+///
+/// With the `optimism` feature, the L1 data fee of every receipt is derived from the
+/// `l1_block_info` of the block.
+pub(crate) fn build_block_receipts(
+    block: SealedBlock,
+    receipts: Vec<Receipt>,
+    include_first_log_index: bool,
+    #[cfg(feature = "optimism")] l1_block_info: &L1BlockInfo,
+) -> EthResult<(Vec<TransactionReceipt>, Bloom)> {
+    let mut block_bloom = Bloom::zero();
+    let mut prev_cumulative_gas_used = 0;
+    let mut num_logs = 0;
+
+    let mut tx_receipts = Vec::with_capacity(receipts.len());
+    for (idx, (tx, receipt)) in block.body.into_iter().zip(receipts).enumerate() {
+        let meta = TransactionMeta {
+            tx_hash: tx.hash,
+            index: idx as u64,
+            block_hash: block.header.hash,
+            block_number: block.header.number,
+            base_fee: block.header.base_fee_per_gas,
         };
-        res_receipt.logs.push(rpclog);
+        let transaction = tx.into_ecrecovered().ok_or(EthApiError::InvalidTransactionSignature)?;
+
+        let (cumulative_gas_used, logs_count) = (receipt.cumulative_gas_used, receipt.logs.len());
+        let mut tx_receipt = TransactionReceipt::from_execution(
+            &transaction,
+            receipt,
+            prev_cumulative_gas_used,
+            meta,
+            num_logs,
+        );
+        if include_first_log_index {
+            tx_receipt = tx_receipt.with_first_log_index(num_logs as u64);
+        }
+        #[cfg(feature = "optimism")]
+        {
+            tx_receipt = l1_block_info.apply(tx_receipt, &transaction);
+        }
+        block_bloom.accrue_bloom(&tx_receipt.logs_bloom);
+        tx_receipts.push(tx_receipt);
+
+        prev_cumulative_gas_used = cumulative_gas_used;
+        num_logs += logs_count;
     }
 
-    Ok(res_receipt)
+    Ok((tx_receipts, block_bloom))
 }
 
 #[cfg(test)]
@@ -917,11 +962,149 @@ mod tests {
         eth::{cache::EthStateCache, gas_oracle::GasPriceOracle},
         EthApi, TracingCallPool,
     };
+    use reth_interfaces::test_utils::generators::{self, random_block, random_receipt};
     use reth_network_api::noop::NoopNetwork;
     use reth_primitives::{constants::ETHEREUM_BLOCK_GAS_LIMIT, hex_literal::hex, Bytes};
     use reth_provider::test_utils::NoopProvider;
     use reth_transaction_pool::{test_utils::testing_pool, TransactionPool};
 
+    #[test]
+    fn block_receipts_with_bloom() {
+        let mut rng = generators::rng();
+        let block = random_block(&mut rng, 1, None, Some(3), Some(0));
+        let mut cumulative_gas_used = 0;
+        let receipts = block
+            .body
+            .iter()
+            .map(|tx| {
+                let mut receipt = random_receipt(&mut rng, tx, Some(2));
+                cumulative_gas_used += 21_000;
+                receipt.cumulative_gas_used = cumulative_gas_used;
+                receipt
+            })
+            .collect::<Vec<_>>();
+
+        let (tx_receipts, bloom) = build_block_receipts(
+            block.clone(),
+            receipts.clone(),
+            false,
+            #[cfg(feature = "optimism")]
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(tx_receipts.len(), 3);
+
+        let mut expected_bloom = Bloom::zero();
+        for (idx, (tx, receipt)) in block.body.into_iter().zip(&receipts).enumerate() {
+            let receipt_bloom = receipt.bloom_slow();
+            assert_eq!(tx_receipts[idx].logs_bloom, receipt_bloom);
+            expected_bloom.accrue_bloom(&receipt_bloom);
+
+            // same as building the receipt on its own
+            let meta = TransactionMeta {
+                tx_hash: tx.hash,
+                index: idx as u64,
+                block_hash: block.header.hash,
+                block_number: block.header.number,
+                base_fee: block.header.base_fee_per_gas,
+            };
+            let expected = build_transaction_receipt_with_block_receipts(
+                tx,
+                meta,
+                receipt.clone(),
+                &receipts,
+                #[cfg(feature = "optimism")]
+                &Default::default(),
+            )
+            .unwrap();
+            assert_eq!(tx_receipts[idx], expected);
+        }
+        assert_eq!(bloom, expected_bloom);
+    }
+
+    #[test]
+    fn block_receipts_first_log_index() {
+        let mut rng = generators::rng();
+        let block = random_block(&mut rng, 1, None, Some(5), Some(0));
+        let receipts = block
+            .body
+            .iter()
+            .enumerate()
+            .map(|(idx, tx)| {
+                let mut receipt = random_receipt(&mut rng, tx, Some(idx as u8 + 1));
+                receipt.cumulative_gas_used = 21_000 * (idx as u64 + 1);
+                receipt
+            })
+            .collect::<Vec<_>>();
+
+        // the field is only set if requested
+        let (tx_receipts, _) = build_block_receipts(
+            block.clone(),
+            receipts.clone(),
+            false,
+            #[cfg(feature = "optimism")]
+            &Default::default(),
+        )
+        .unwrap();
+        assert!(tx_receipts.iter().all(|receipt| receipt.first_log_index.is_none()));
+
+        let (tx_receipts, _) = build_block_receipts(
+            block,
+            receipts.clone(),
+            true,
+            #[cfg(feature = "optimism")]
+            &Default::default(),
+        )
+        .unwrap();
+
+        let mut cumulative_logs = 0;
+        for (tx_receipt, receipt) in tx_receipts.iter().zip(&receipts) {
+            // the first log index is the number of logs of all preceding receipts
+            assert_eq!(tx_receipt.first_log_index, Some(U256::from(cumulative_logs)));
+            if let Some(first_log) = tx_receipt.logs.first() {
+                assert_eq!(first_log.log_index, tx_receipt.first_log_index);
+            }
+            cumulative_logs += receipt.logs.len();
+        }
+    }
+
+    #[cfg(feature = "optimism")]
+    #[test]
+    fn block_receipts_l1_fee() {
+        let mut rng = generators::rng();
+        let block = random_block(&mut rng, 1, None, Some(3), Some(0));
+        let receipts = block
+            .body
+            .iter()
+            .enumerate()
+            .map(|(idx, tx)| {
+                let mut receipt = random_receipt(&mut rng, tx, Some(0));
+                receipt.cumulative_gas_used = 21_000 * (idx as u64 + 1);
+                receipt
+            })
+            .collect::<Vec<_>>();
+
+        let l1_block_info = L1BlockInfo {
+            l1_base_fee: U256::from(30_000_000_000u64),
+            l1_fee_overhead: U256::from(188),
+            l1_fee_scalar: U256::from(684_000),
+        };
+        let (tx_receipts, _) =
+            build_block_receipts(block.clone(), receipts, false, &l1_block_info).unwrap();
+
+        for (tx, receipt) in block.body.iter().zip(&tx_receipts) {
+            let encoded = tx.envelope_encoded();
+            let zeros = encoded.iter().filter(|byte| **byte == 0).count() as u64;
+            let l1_gas_used = zeros * 4 + (encoded.len() as u64 - zeros) * 16 + 188;
+            let l1_fee = U256::from(l1_gas_used) * l1_block_info.l1_base_fee * U256::from(684_000) /
+                U256::from(1_000_000);
+
+            assert_eq!(receipt.l1_gas_used, Some(U256::from(l1_gas_used)));
+            assert_eq!(receipt.l1_gas_price, Some(l1_block_info.l1_base_fee));
+            assert_eq!(receipt.l1_fee, Some(l1_fee));
+        }
+    }
+
     #[tokio::test]
     async fn send_raw_transaction() {
         let noop_provider = NoopProvider::default();
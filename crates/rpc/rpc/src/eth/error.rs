@@ -5,7 +5,7 @@ use jsonrpsee::{
     core::Error as RpcError,
     types::{error::CALL_EXECUTION_FAILED_CODE, ErrorObject},
 };
-use reth_primitives::{abi::decode_revert_reason, Address, Bytes, U256};
+use reth_primitives::{abi::decode_revert_reason, Address, Bytes, H256, U256};
 use reth_revm::tracing::js::JsInspectorError;
 use reth_rpc_types::{error::EthRpcErrorCode, BlockError, CallInputError};
 use reth_transaction_pool::error::{InvalidPoolTransactionError, PoolError, PoolTransactionError};
@@ -36,6 +36,10 @@ pub enum EthApiError {
     UnknownSafeOrFinalizedBlock,
     #[error("Unknown block or tx index")]
     UnknownBlockOrTxIndex,
+    /// Thrown when a block hash that must be canonical is known but not part of the canonical
+    /// chain, <https://eips.ethereum.org/EIPS/eip-1898>
+    #[error("block hash {0:?} is not canonical")]
+    BlockHashNotCanonical(H256),
     #[error("Invalid block range")]
     InvalidBlockRange,
     /// An internal error where prevrandao is not set in the evm's environment
@@ -87,11 +91,40 @@ pub enum EthApiError {
     /// Error thrown when a (tracing) call exceeded the configured timeout.
     #[error("execution aborted (timeout = {0:?})")]
     ExecutionTimedOut(Duration),
+    /// Thrown when the output of a call exceeds the configured maximum size.
+    #[error("call output of {size} bytes exceeds the limit of {limit} bytes")]
+    CallOutputTooLarge {
+        /// The size of the output, in bytes.
+        size: usize,
+        /// The maximum size of the output, in bytes.
+        limit: usize,
+    },
     /// Internal Error thrown by the javascript tracer
     #[error("{0}")]
     InternalJsTracerError(String),
     #[error(transparent)]
     CallInputError(#[from] CallInputError),
+    /// Thrown if gas estimation failed
+    #[error(transparent)]
+    EstimateGas(#[from] EstimateGasError),
+}
+
+impl EthApiError {
+    /// Returns the raw output of the reverted call if this is a revert error.
+    ///
+    /// This is the ABI encoded revert reason or custom error, which is also included as hex
+    /// encoded `data` in the JSON-RPC error.
+    pub fn revert_data(&self) -> Option<&bytes::Bytes> {
+        match self {
+            EthApiError::InvalidTransaction(RpcInvalidTransactionError::Revert(revert)) => {
+                revert.output()
+            }
+            EthApiError::EstimateGas(EstimateGasError::Revert { data, .. }) => {
+                data.as_ref().map(|data| &data.0)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl From<EthApiError> for ErrorObject<'static> {
@@ -114,6 +147,9 @@ impl From<EthApiError> for ErrorObject<'static> {
             EthApiError::UnknownBlockNumber | EthApiError::UnknownBlockOrTxIndex => {
                 rpc_error_with_code(EthRpcErrorCode::ResourceNotFound.code(), error.to_string())
             }
+            EthApiError::BlockHashNotCanonical(_) => {
+                rpc_error_with_code(EthRpcErrorCode::InvalidInput.code(), error.to_string())
+            }
             EthApiError::UnknownSafeOrFinalizedBlock => {
                 rpc_error_with_code(EthRpcErrorCode::UnknownBlock.code(), error.to_string())
             }
@@ -121,12 +157,13 @@ impl From<EthApiError> for ErrorObject<'static> {
             EthApiError::InternalJsTracerError(msg) => internal_rpc_err(msg),
             EthApiError::InvalidParams(msg) => invalid_params_rpc_err(msg),
             EthApiError::InvalidRewardPercentiles => internal_rpc_err(error.to_string()),
-            err @ EthApiError::ExecutionTimedOut(_) => {
+            err @ (EthApiError::ExecutionTimedOut(_) | EthApiError::CallOutputTooLarge { .. }) => {
                 rpc_error_with_code(CALL_EXECUTION_FAILED_CODE, err.to_string())
             }
             err @ EthApiError::InternalTracingError => internal_rpc_err(err.to_string()),
             err @ EthApiError::InternalEthError => internal_rpc_err(err.to_string()),
             err @ EthApiError::CallInputError(_) => invalid_params_rpc_err(err.to_string()),
+            EthApiError::EstimateGas(err) => RpcInvalidTransactionError::from(err).into(),
         }
     }
 }
@@ -264,11 +301,19 @@ pub enum RpcInvalidTransactionError {
     /// revm's Type cast error, U256 casts down to a u64 with overflow
     #[error("Out of gas: revm's Type cast error, U256 casts down to a u64 with overflow {0:?}")]
     InvalidOperandOutOfGas(U256),
+    /// Thrown if a call frame exceeded the configured memory limit.
+    #[error("memory limit exceeded")]
+    MemoryLimitExceeded,
+    /// Thrown if a call exceeded the configured call depth limit.
+    #[error("call depth limit exceeded")]
+    CallDepthExceeded,
     /// Thrown if executing a transaction failed during estimate/call
     #[error("{0}")]
     Revert(RevertError),
     /// Unspecific evm halt error
-    #[error("EVM error {0:?}")]
+    ///
+    /// The JSON-RPC error includes the name of the [Halt] variant as `data`.
+    #[error("EVM error: {}", halt_description(.0))]
     EvmHalt(Halt),
     /// Invalid chain id set for the transaction.
     #[error("Invalid chain id")]
@@ -311,7 +356,7 @@ impl RpcInvalidTransactionError {
             OutOfGasError::InvalidOperand => {
                 RpcInvalidTransactionError::InvalidOperandOutOfGas(gas_limit)
             }
-            OutOfGasError::MemoryLimit => RpcInvalidTransactionError::MemoryOutOfGas(gas_limit),
+            OutOfGasError::MemoryLimit => RpcInvalidTransactionError::MemoryLimitExceeded,
         }
     }
 }
@@ -327,11 +372,35 @@ impl From<RpcInvalidTransactionError> for ErrorObject<'static> {
                     revert.output.as_ref().map(|out| out.as_ref()),
                 )
             }
+            RpcInvalidTransactionError::EvmHalt(halt) => {
+                let data = format!("{halt:?}");
+                let err = RpcInvalidTransactionError::EvmHalt(halt);
+                ErrorObject::owned(err.error_code(), err.to_string(), Some(data))
+            }
             err => rpc_err(err.error_code(), err.to_string(), None),
         }
     }
 }
 
+/// Returns a human readable description of why the EVM halted.
+fn halt_description(halt: &Halt) -> String {
+    let description = match halt {
+        Halt::OpcodeNotFound => "invalid opcode",
+        Halt::InvalidFEOpcode => "invalid opcode: INVALID",
+        Halt::InvalidJump => "invalid jump destination",
+        Halt::NotActivated => "opcode not activated",
+        Halt::StackUnderflow => "stack underflow",
+        Halt::StackOverflow => "stack overflow",
+        Halt::OutOfOffset => "return data out of bounds",
+        Halt::CreateCollision => "contract address collision",
+        Halt::PrecompileError => "precompile error",
+        Halt::CreateContractSizeLimit => "max code size exceeded",
+        Halt::CreateContractStartingWithEF => "invalid code: must not begin with 0xef",
+        halt => return format!("{halt:?}"),
+    };
+    description.to_string()
+}
+
 impl From<revm::primitives::InvalidTransaction> for RpcInvalidTransactionError {
     fn from(err: revm::primitives::InvalidTransaction) -> Self {
         use revm::primitives::InvalidTransaction;
@@ -401,9 +470,82 @@ impl From<reth_primitives::InvalidTransactionError> for RpcInvalidTransactionErr
     }
 }
 
+/// Classification of the reasons why gas estimation failed.
+///
+/// This is returned as [EthApiError::EstimateGas] so callers can branch on the failure, and is
+/// mapped to the corresponding [RpcInvalidTransactionError] at the JSON-RPC boundary.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum EstimateGasError {
+    /// The call reverted.
+    #[error(
+        "execution reverted{}",
+        .reason.as_ref().map(|r| format!(": {r}")).unwrap_or_default()
+    )]
+    Revert {
+        /// The decoded revert reason, if the output is an `Error(string)`
+        reason: Option<String>,
+        /// The output of the call, `None` if empty
+        data: Option<Bytes>,
+    },
+    /// The call succeeds, but not with the gas limit provided in the request.
+    #[error("Out of gas: gas required exceeds allowance: {provided:?}")]
+    OutOfGas {
+        /// The gas limit provided in the request
+        provided: U256,
+    },
+    /// The call halted.
+    #[error("EVM error: {}", halt_description(.reason))]
+    Halt {
+        /// The reason the call halted
+        reason: Halt,
+        /// The gas used until the halt
+        gas_used: u64,
+    },
+    /// The sender has insufficient funds to cover the value transfer.
+    #[error("insufficient funds for transfer")]
+    InsufficientFunds,
+}
+
+impl EstimateGasError {
+    /// Creates a [EstimateGasError::Revert] from the output of the call
+    pub(crate) fn revert(output: bytes::Bytes) -> Self {
+        if output.is_empty() {
+            return EstimateGasError::Revert { reason: None, data: None }
+        }
+        let reason = decode_revert_reason(&output);
+        EstimateGasError::Revert { reason, data: Some(output.into()) }
+    }
+}
+
+impl From<EstimateGasError> for RpcInvalidTransactionError {
+    fn from(err: EstimateGasError) -> Self {
+        match err {
+            EstimateGasError::Revert { data, .. } => RpcInvalidTransactionError::Revert(
+                RevertError::new(data.map(|data| data.0).unwrap_or_default()),
+            ),
+            EstimateGasError::OutOfGas { provided } => {
+                RpcInvalidTransactionError::BasicOutOfGas(provided)
+            }
+            EstimateGasError::Halt { reason, gas_used } => {
+                RpcInvalidTransactionError::halt(reason, gas_used)
+            }
+            EstimateGasError::InsufficientFunds => {
+                RpcInvalidTransactionError::InsufficientFundsForTransfer
+            }
+        }
+    }
+}
+
+/// Selector of the standard `Error(string)` revert.
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Selector of the standard `Panic(uint256)` revert.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
 /// Represents a reverted transaction and its output data.
 ///
-/// Displays "execution reverted(: reason)?" if the reason is a string.
+/// Displays "execution reverted(: reason)?" if the reason is a string, and "execution reverted:
+/// custom error 0x..." if the output starts with the selector of a custom error.
 #[derive(Debug, Clone)]
 pub struct RevertError {
     /// The transaction output data
@@ -426,6 +568,21 @@ impl RevertError {
         }
     }
 
+    /// Returns the output of the reverted call, `None` if it was empty.
+    pub fn output(&self) -> Option<&bytes::Bytes> {
+        self.output.as_ref()
+    }
+
+    /// Returns the 4-byte selector of the custom error the call reverted with.
+    ///
+    /// This is `None` if the output is shorter than 4 bytes or is a standard `Error(string)` or
+    /// `Panic(uint256)` revert. The selector can be looked up in the ABI of the called contract,
+    /// the full output is still available via [RevertError::output].
+    pub fn selector(&self) -> Option<[u8; 4]> {
+        let selector: [u8; 4] = self.output.as_ref()?.get(..4)?.try_into().ok()?;
+        (selector != ERROR_STRING_SELECTOR && selector != PANIC_SELECTOR).then_some(selector)
+    }
+
     fn error_code(&self) -> i32 {
         EthRpcErrorCode::ExecutionError.code()
     }
@@ -436,6 +593,8 @@ impl std::fmt::Display for RevertError {
         f.write_str("execution reverted")?;
         if let Some(reason) = self.output.as_ref().and_then(decode_revert_reason) {
             write!(f, ": {reason}")?;
+        } else if let Some(selector) = self.selector() {
+            write!(f, ": custom error 0x{}", hex::encode(selector))?;
         }
         Ok(())
     }
@@ -562,4 +721,40 @@ mod tests {
         let err = EthApiError::ExecutionTimedOut(Duration::from_secs(10));
         assert_eq!(err.to_string(), "execution aborted (timeout = 10s)");
     }
+
+    #[test]
+    fn custom_error_selector() {
+        // the selector of a custom error followed by its argument
+        let mut output = vec![0xde, 0xad, 0xbe, 0xef];
+        output.extend_from_slice(H256::from_low_u64_be(0x2a).as_bytes());
+        let err = RevertError::new(output.clone().into());
+        assert_eq!(err.selector(), Some([0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(err.output().map(|out| out.to_vec()), Some(output.clone()));
+        assert_eq!(err.to_string(), "execution reverted: custom error 0xdeadbeef");
+
+        // the raw output is preserved in the error data
+        let err = ErrorObject::from(RpcInvalidTransactionError::Revert(err));
+        let expected_data = format!("\"0x{}\"", hex::encode(&output));
+        assert_eq!(err.data().map(|data| data.get()), Some(expected_data.as_str()));
+        assert_eq!(err.message(), "execution reverted: custom error 0xdeadbeef");
+    }
+
+    #[test]
+    fn standard_revert_has_no_selector() {
+        // Error(string) with the reason "fail"
+        let output = hex::decode(
+            "08c379a0\
+             0000000000000000000000000000000000000000000000000000000000000020\
+             0000000000000000000000000000000000000000000000000000000000000004\
+             6661696c00000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        let err = RevertError::new(output.into());
+        assert_eq!(err.selector(), None);
+        assert_eq!(err.to_string(), "execution reverted: fail");
+
+        // too short for a selector
+        assert_eq!(RevertError::new(vec![0xde, 0xad].into()).selector(), None);
+        assert_eq!(RevertError::new(Default::default()).selector(), None);
+    }
 }
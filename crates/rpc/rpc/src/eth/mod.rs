@@ -7,12 +7,19 @@ mod filter;
 pub mod gas_oracle;
 mod id_provider;
 mod logs_utils;
+#[cfg(feature = "optimism")]
+pub(crate) mod optimism;
 mod pubsub;
 pub mod revm_utils;
 mod signer;
 pub(crate) mod utils;
 
-pub use api::{EthApi, EthApiSpec, EthTransactions, TransactionSource, RPC_DEFAULT_GAS_CAP};
+pub use api::{
+    AccessCounts, AccessListOptions, BoundedGasEstimate, CallManyResult, CallSession,
+    CallTreeOptions, CallWithGas, EstimateGasOptions, EthApi, EthApiSpec, EthTransactions,
+    GasEstimate, GasProfile, SimulatedBlock, TransactionSource, VerboseGasEstimate,
+    RPC_DEFAULT_GAS_CAP,
+};
 pub use filter::EthFilter;
 pub use id_provider::EthSubscriptionIdProvider;
 pub use pubsub::EthPubSub;
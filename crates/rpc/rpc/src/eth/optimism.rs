@@ -0,0 +1,74 @@
+//! Helpers to derive the OP-stack L1 data fee of transactions.
+
+use crate::eth::error::EthResult;
+use reth_primitives::{hex_literal::hex, Address, TransactionSigned, H160, H256, U256};
+use reth_provider::StateProvider;
+use reth_rpc_types::TransactionReceipt;
+
+/// Address of the `L1Block` predeploy, which stores the inputs of the L1 fee oracle.
+const L1_BLOCK_CONTRACT: Address = H160(hex!("4200000000000000000000000000000000000015"));
+
+/// Storage slot of the L1 base fee in the `L1Block` predeploy.
+const L1_BASE_FEE_SLOT: u64 = 1;
+
+/// Storage slot of the fixed L1 gas overhead in the `L1Block` predeploy.
+const L1_FEE_OVERHEAD_SLOT: u64 = 5;
+
+/// Storage slot of the dynamic L1 fee scalar in the `L1Block` predeploy.
+const L1_FEE_SCALAR_SLOT: u64 = 6;
+
+/// The inputs of the L1 fee oracle, as stored in the `L1Block` predeploy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct L1BlockInfo {
+    /// The L1 base fee.
+    pub(crate) l1_base_fee: U256,
+    /// The fixed L1 gas overhead of every transaction.
+    pub(crate) l1_fee_overhead: U256,
+    /// The dynamic overhead, with 6 decimals.
+    pub(crate) l1_fee_scalar: U256,
+}
+
+impl L1BlockInfo {
+    /// Reads the L1 fee oracle inputs from the `L1Block` predeploy in the given `state`.
+    ///
+    /// The predeploy is updated by the first transaction of every block, so this must be the
+    /// state after the block the transactions are included in.
+    pub(crate) fn from_state<S>(state: &S) -> EthResult<Self>
+    where
+        S: StateProvider + ?Sized,
+    {
+        let slot = |slot: u64| -> EthResult<U256> {
+            let key = H256::from_low_u64_be(slot);
+            Ok(state.storage(L1_BLOCK_CONTRACT, key)?.unwrap_or_default())
+        };
+        Ok(Self {
+            l1_base_fee: slot(L1_BASE_FEE_SLOT)?,
+            l1_fee_overhead: slot(L1_FEE_OVERHEAD_SLOT)?,
+            l1_fee_scalar: slot(L1_FEE_SCALAR_SLOT)?,
+        })
+    }
+
+    /// Returns the L1 gas used to post the data of the `transaction`, including the fixed
+    /// overhead.
+    ///
+    /// Every zero byte of the enveloped encoding costs 4 gas and every other byte 16 gas, as
+    /// priced since Regolith.
+    pub(crate) fn l1_gas_used(&self, transaction: &TransactionSigned) -> u64 {
+        let data_gas = transaction
+            .envelope_encoded()
+            .iter()
+            .map(|byte| if *byte == 0 { 4 } else { 16 })
+            .sum::<u64>();
+        data_gas.saturating_add(self.l1_fee_overhead.saturating_to())
+    }
+
+    /// Sets the L1 data fee fields of the `receipt` of the `transaction`.
+    pub(crate) fn apply(
+        &self,
+        receipt: TransactionReceipt,
+        transaction: &TransactionSigned,
+    ) -> TransactionReceipt {
+        let l1_gas_used = self.l1_gas_used(transaction);
+        receipt.with_l1_fee(l1_gas_used, self.l1_base_fee, self.l1_fee_scalar.saturating_to())
+    }
+}
@@ -2,18 +2,21 @@
 
 use crate::eth::error::{EthApiError, EthResult, RpcInvalidTransactionError};
 use reth_primitives::{
-    AccessList, Address, TransactionSigned, TransactionSignedEcRecovered, TxHash, H256, U256,
+    bytes::Bytes, AccessList, Address, TransactionSigned, TransactionSignedEcRecovered, TxHash,
+    EIP1559_TX_TYPE_ID, EIP2930_TX_TYPE_ID, H256, LEGACY_TX_TYPE_ID, U256,
 };
 use reth_revm::env::{fill_tx_env, fill_tx_env_with_recovered};
 use reth_rpc_types::{
     state::{AccountOverride, StateOverride},
     BlockOverrides, CallRequest,
 };
+use reth_transaction_pool::validate::MAX_INIT_CODE_SIZE;
 use revm::{
     db::{CacheDB, EmptyDB},
+    interpreter::{CallInputs, CreateInputs, Gas, InstructionResult, Interpreter},
     precompile::{Precompiles, SpecId as PrecompilesSpecId},
     primitives::{BlockEnv, CfgEnv, Env, ResultAndState, SpecId, TransactTo, TxEnv},
-    Database, Inspector,
+    Database, EVMData, Inspector,
 };
 use revm_primitives::{
     db::{DatabaseCommit, DatabaseRef},
@@ -21,6 +24,29 @@ use revm_primitives::{
 };
 use tracing::trace;
 
+/// The maximum call depth allowed by the protocol.
+pub const CALL_STACK_LIMIT: u64 = 1024;
+
+/// The default memory limit of revm, in bytes.
+const DEFAULT_MEMORY_LIMIT: u64 = u32::MAX as u64;
+
+/// Intrinsic gas of every transaction.
+const TRANSACTION_GAS: u64 = 21_000;
+/// Additional intrinsic gas of a contract creation, since Homestead.
+const CREATE_GAS: u64 = 32_000;
+/// Intrinsic gas per zero byte of the input.
+const ZERO_BYTE_GAS: u64 = 4;
+/// Intrinsic gas per non-zero byte of the input, since Istanbul, see EIP-2028.
+const NON_ZERO_BYTE_GAS: u64 = 16;
+/// Intrinsic gas per non-zero byte of the input, before Istanbul.
+const NON_ZERO_BYTE_GAS_FRONTIER: u64 = 68;
+/// Intrinsic gas per word of the init code of a contract creation, see EIP-3860.
+const INIT_CODE_WORD_GAS: u64 = 2;
+/// Intrinsic gas per address of an access list, see EIP-2930.
+const ACCESS_LIST_ADDRESS_GAS: u64 = 2_400;
+/// Intrinsic gas per storage key of an access list, see EIP-2930.
+const ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1_900;
+
 /// Helper type that bundles various overrides for EVM Execution.
 ///
 /// By `Default`, no overrides are included.
@@ -32,17 +58,66 @@ pub struct EvmOverrides {
     ///
     /// This is a `Box` because less common and only available in debug trace endpoints.
     pub block: Option<Box<BlockOverrides>>,
+    /// Limits the resources available to the execution.
+    pub limits: CallLimits,
+    /// Overrides the chain id of the chain spec.
+    ///
+    /// This is observed by the `CHAINID` opcode and the chain id of the request must match it.
+    pub chain_id: Option<u64>,
+    /// Whether the EIP-3607 check is enforced, so calls from senders that have code are rejected.
+    ///
+    /// By default the check is disabled, because calls are commonly simulated from contracts.
+    pub enforce_eip3607: bool,
+    /// Whether gas refunds are disabled, so the reported gas is the upper bound without refunds.
+    pub disable_gas_refund: bool,
 }
 
 impl EvmOverrides {
     /// Creates a new instance with the given overrides
     pub fn new(state: Option<StateOverride>, block: Option<Box<BlockOverrides>>) -> Self {
-        Self { state, block }
+        Self { state, block, ..Default::default() }
     }
 
     /// Creates a new instance with the given state overrides.
     pub fn state(state: Option<StateOverride>) -> Self {
-        Self { state, block: None }
+        Self { state, ..Default::default() }
+    }
+
+    /// Overrides the base fee of the block.
+    ///
+    /// The fees of the call request are applied against the overridden base fee.
+    pub fn with_base_fee(mut self, base_fee: U256) -> Self {
+        self.block.get_or_insert_with(Default::default).base_fee = Some(base_fee);
+        self
+    }
+
+    /// Overrides the chain id the call is executed with.
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Sets whether the EIP-3607 check is enforced.
+    ///
+    /// If enforced, calls from senders that have code are rejected like a mined transaction.
+    pub fn with_eip3607_enforced(mut self, enforce: bool) -> Self {
+        self.enforce_eip3607 = enforce;
+        self
+    }
+
+    /// Sets whether gas refunds are disabled.
+    ///
+    /// If disabled, the gas used by the execution is not reduced by the refund of cleared storage
+    /// slots, which is the gas a call needs in the worst case.
+    pub fn with_gas_refund_disabled(mut self, disabled: bool) -> Self {
+        self.disable_gas_refund = disabled;
+        self
+    }
+
+    /// Sets the [CallLimits] of the execution.
+    pub fn with_limits(mut self, limits: CallLimits) -> Self {
+        self.limits = limits;
+        self
     }
 
     /// Returns `true` if the overrides contain state overrides.
@@ -57,6 +132,249 @@ impl From<Option<StateOverride>> for EvmOverrides {
     }
 }
 
+/// Resource limits of a call that can be set below the protocol defaults.
+///
+/// By `Default`, the limits match the protocol rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallLimits {
+    /// The maximum memory of a call frame, in bytes.
+    pub memory_limit: u64,
+    /// The maximum depth of nested calls and creates.
+    pub max_call_depth: u64,
+    /// The maximum size of the output of a call, in bytes, or `None` if it is unlimited.
+    pub max_output_bytes: Option<usize>,
+}
+
+impl CallLimits {
+    /// Sets the maximum memory of a call frame, in bytes.
+    pub fn with_memory_limit(mut self, memory_limit: u64) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// Sets the maximum depth of nested calls and creates.
+    pub fn with_max_call_depth(mut self, max_call_depth: u64) -> Self {
+        self.max_call_depth = max_call_depth;
+        self
+    }
+
+    /// Sets the maximum size of the output of a call, in bytes.
+    ///
+    /// Calls that return or revert with more data fail with [EthApiError::CallOutputTooLarge]
+    /// instead.
+    pub fn with_max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.max_output_bytes = Some(max_output_bytes);
+        self
+    }
+
+    /// Returns an error if the output or the revert data of the `result` exceeds the maximum
+    /// output size.
+    pub(crate) fn ensure_output_size(&self, result: &ExecutionResult) -> EthResult<()> {
+        let size = result_output(result).map_or(0, |output| output.len());
+        match self.max_output_bytes {
+            Some(limit) if size > limit => Err(EthApiError::CallOutputTooLarge { size, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns `true` if the call depth is limited below [CALL_STACK_LIMIT].
+    pub fn has_call_depth_limit(&self) -> bool {
+        self.max_call_depth < CALL_STACK_LIMIT
+    }
+}
+
+impl Default for CallLimits {
+    fn default() -> Self {
+        Self {
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+            max_call_depth: CALL_STACK_LIMIT,
+            max_output_bytes: None,
+        }
+    }
+}
+
+/// An [Inspector] that fails all calls and creates above the configured depth.
+///
+/// revm always enforces [CALL_STACK_LIMIT], this allows to enforce a lower limit.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CallDepthLimit {
+    /// The maximum depth of nested calls and creates.
+    max_depth: u64,
+    /// Whether a call or create exceeded the limit.
+    exceeded: bool,
+}
+
+impl CallDepthLimit {
+    /// Creates a new inspector with the given maximum depth.
+    pub(crate) fn new(max_depth: u64) -> Self {
+        Self { max_depth, exceeded: false }
+    }
+
+    /// Returns `true` if a call or create exceeded the limit.
+    pub(crate) fn exceeded(&self) -> bool {
+        self.exceeded
+    }
+
+    /// Marks the limit as exceeded if the current depth is above the limit.
+    fn check<DB: Database>(&mut self, data: &EVMData<'_, DB>) -> bool {
+        if data.journaled_state.depth() > self.max_depth {
+            self.exceeded = true;
+        }
+        self.exceeded
+    }
+}
+
+impl<DB: Database> Inspector<DB> for CallDepthLimit {
+    fn call(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CallInputs,
+        _is_static: bool,
+    ) -> (InstructionResult, Gas, Bytes) {
+        if self.check(data) {
+            return (InstructionResult::CallTooDeep, Gas::new(inputs.gas_limit), Bytes::new())
+        }
+        (InstructionResult::Continue, Gas::new(0), Bytes::new())
+    }
+
+    fn create(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        if self.check(data) {
+            let gas = Gas::new(inputs.gas_limit);
+            return (InstructionResult::CallTooDeep, None, gas, Bytes::new())
+        }
+        (InstructionResult::Continue, None, Gas::new(0), Bytes::new())
+    }
+}
+
+/// An [Inspector] that enforces the call depth of the [CallLimits] on top of another inspector.
+///
+/// Every hook is forwarded to the inner inspector first, so it observes the calls and creates
+/// that are failed because they exceed the limit like any other failed call.
+#[derive(Debug)]
+pub(crate) struct LimitedInspector<I> {
+    /// The inspector the hooks are forwarded to.
+    inner: I,
+    /// The call depth limit, if it is below the protocol limit.
+    depth_limit: Option<CallDepthLimit>,
+}
+
+impl<I> LimitedInspector<I> {
+    /// Wraps the `inner` inspector with the given limits.
+    pub(crate) fn new(inner: I, limits: &CallLimits) -> Self {
+        let depth_limit =
+            limits.has_call_depth_limit().then(|| CallDepthLimit::new(limits.max_call_depth));
+        Self { inner, depth_limit }
+    }
+
+    /// Returns `true` if a call or create exceeded the call depth limit.
+    pub(crate) fn depth_exceeded(&self) -> bool {
+        self.depth_limit.map_or(false, |limit| limit.exceeded())
+    }
+}
+
+impl<DB: Database, I: Inspector<DB>> Inspector<DB> for LimitedInspector<I> {
+    fn initialize_interp(
+        &mut self,
+        interpreter: &mut Interpreter,
+        data: &mut EVMData<'_, DB>,
+        is_static: bool,
+    ) -> InstructionResult {
+        self.inner.initialize_interp(interpreter, data, is_static)
+    }
+
+    fn step(
+        &mut self,
+        interpreter: &mut Interpreter,
+        data: &mut EVMData<'_, DB>,
+        is_static: bool,
+    ) -> InstructionResult {
+        self.inner.step(interpreter, data, is_static)
+    }
+
+    fn log(
+        &mut self,
+        evm_data: &mut EVMData<'_, DB>,
+        address: &Address,
+        topics: &[H256],
+        data: &Bytes,
+    ) {
+        self.inner.log(evm_data, address, topics, data)
+    }
+
+    fn step_end(
+        &mut self,
+        interpreter: &mut Interpreter,
+        data: &mut EVMData<'_, DB>,
+        is_static: bool,
+        eval: InstructionResult,
+    ) -> InstructionResult {
+        self.inner.step_end(interpreter, data, is_static, eval)
+    }
+
+    fn call(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CallInputs,
+        is_static: bool,
+    ) -> (InstructionResult, Gas, Bytes) {
+        let res = self.inner.call(data, inputs, is_static);
+        if res.0 != InstructionResult::Continue {
+            return res
+        }
+        match &mut self.depth_limit {
+            Some(limit) => limit.call(data, inputs, is_static),
+            None => res,
+        }
+    }
+
+    fn call_end(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &CallInputs,
+        remaining_gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+        is_static: bool,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.inner.call_end(data, inputs, remaining_gas, ret, out, is_static)
+    }
+
+    fn create(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        let res = self.inner.create(data, inputs);
+        if res.0 != InstructionResult::Continue {
+            return res
+        }
+        match &mut self.depth_limit {
+            Some(limit) => limit.create(data, inputs),
+            None => res,
+        }
+    }
+
+    fn create_end(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<Address>,
+        remaining_gas: Gas,
+        out: Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        self.inner.create_end(data, inputs, ret, address, remaining_gas, out)
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address) {
+        Inspector::<DB>::selfdestruct(&mut self.inner, contract, target)
+    }
+}
+
 /// Helper type to work with different transaction types when configuring the EVM env.
 ///
 /// This makes it easier to handle errors.
@@ -139,6 +457,57 @@ where
     Ok((res, evm.env))
 }
 
+/// Same as [transact] but fails if the execution exceeded the call depth or the output size of
+/// the [CallLimits].
+///
+/// The memory limit is already applied to the [Env] by [prepare_call_env].
+pub(crate) fn transact_with_limits<DB>(
+    db: DB,
+    env: Env,
+    limits: CallLimits,
+) -> EthResult<(ResultAndState, Env)>
+where
+    DB: Database,
+    <DB as Database>::Error: Into<EthApiError>,
+{
+    let res = if limits.has_call_depth_limit() {
+        let mut inspector = CallDepthLimit::new(limits.max_call_depth);
+        let res = inspect(db, env, &mut inspector)?;
+        if inspector.exceeded() {
+            return Err(RpcInvalidTransactionError::CallDepthExceeded.into())
+        }
+        res
+    } else {
+        transact(db, env)?
+    };
+    limits.ensure_output_size(&res.0.result)?;
+    Ok(res)
+}
+
+/// Same as [inspect] but fails if the execution exceeded the call depth or the output size of the
+/// [CallLimits].
+///
+/// The call depth is enforced by wrapping the `inspector` in a [LimitedInspector].
+pub(crate) fn inspect_with_limits<DB, I>(
+    db: DB,
+    env: Env,
+    inspector: I,
+    limits: CallLimits,
+) -> EthResult<(ResultAndState, Env)>
+where
+    DB: Database,
+    <DB as Database>::Error: Into<EthApiError>,
+    I: Inspector<DB>,
+{
+    let mut inspector = LimitedInspector::new(inspector, &limits);
+    let res = inspect(db, env, &mut inspector)?;
+    if inspector.depth_exceeded() {
+        return Err(RpcInvalidTransactionError::CallDepthExceeded.into())
+    }
+    limits.ensure_output_size(&res.0.result)?;
+    Ok(res)
+}
+
 /// Same as [inspect] but also returns the database again.
 ///
 /// Even though [Database] is also implemented on `&mut`
@@ -195,12 +564,21 @@ where
     Ok(())
 }
 
+/// Configures whether the EIP-3607 check is applied to calls and gas estimates.
+///
+/// Disabled unless `enforce` is set, because eth_call and eth_estimateGas are sometimes used with
+/// contract senders.
+/// See <https://github.com/paradigmxyz/reth/issues/1959>
+pub(crate) fn configure_eip3607(cfg: &mut CfgEnv, enforce: bool) {
+    cfg.disable_eip3607 = !enforce;
+}
+
 /// Prepares the [Env] for execution.
 ///
 /// Does not commit any changes to the underlying database.
 pub(crate) fn prepare_call_env<DB>(
     mut cfg: CfgEnv,
-    block: BlockEnv,
+    mut block: BlockEnv,
     request: CallRequest,
     gas_limit: u64,
     db: &mut CacheDB<DB>,
@@ -214,17 +592,34 @@ where
     // impls and providers <https://github.com/foundry-rs/foundry/issues/4388>
     cfg.disable_block_gas_limit = true;
 
-    // Disabled because eth_call is sometimes used with eoa senders
-    // See <https://github.com/paradigmxyz/reth/issues/1959>
-    cfg.disable_eip3607 = true;
+    configure_eip3607(&mut cfg, overrides.enforce_eip3607);
+
+    cfg.disable_gas_refund = overrides.disable_gas_refund;
 
     // The basefee should be ignored for eth_call
     // See:
     // <https://github.com/ethereum/go-ethereum/blob/ee8e83fa5f6cb261dad2ed0a7bbcde4930c41e6c/internal/ethapi/api.go#L985>
     cfg.disable_base_fee = true;
 
+    cfg.memory_limit = overrides.limits.memory_limit;
+
+    if let Some(chain_id) = overrides.chain_id {
+        cfg.chain_id = U256::from(chain_id);
+    }
+
     let request_gas = request.gas;
 
+    // apply block overrides, before the fees of the request are applied against the base fee
+    if let Some(block_overrides) = overrides.block {
+        let base_fee_overridden = block_overrides.base_fee.is_some();
+        apply_block_overrides(*block_overrides, &mut block);
+        // the base fee is still disabled for the execution, but a request simulated against an
+        // explicitly overridden base fee must be able to pay it
+        if base_fee_overridden {
+            ensure_fee_cap_covers_base_fee(&request, block.basefee)?;
+        }
+    }
+
     let mut env = build_call_evm_env(cfg, block, request)?;
 
     // apply state overrides
@@ -232,11 +627,6 @@ where
         apply_state_overrides(state_overrides, db)?;
     }
 
-    // apply block overrides
-    if let Some(block_overrides) = overrides.block {
-        apply_block_overrides(*block_overrides, &mut env.block);
-    }
-
     if request_gas.is_none() {
         // No gas limit was provided in the request, so we need to cap the transaction gas limit
         if env.tx.gas_price > U256::ZERO {
@@ -257,6 +647,57 @@ where
     Ok(env)
 }
 
+/// Returns the intrinsic gas of the [CallRequest] under the rules of the given [SpecId].
+///
+/// This is the gas that is charged before any code is executed: the base cost of a transaction,
+/// the cost of the input, and the costs of a contract creation, its init code and the access list
+/// if they apply to the spec.
+pub fn intrinsic_gas(request: &CallRequest, spec: SpecId) -> u64 {
+    let input: &[u8] = request
+        .input
+        .input
+        .as_ref()
+        .or(request.input.data.as_ref())
+        .map(|input| input.as_ref())
+        .unwrap_or_default();
+    let zero_bytes = input.iter().filter(|byte| **byte == 0).count() as u64;
+    let non_zero_bytes = input.len() as u64 - zero_bytes;
+    let non_zero_byte_gas = if SpecId::enabled(spec, SpecId::ISTANBUL) {
+        NON_ZERO_BYTE_GAS
+    } else {
+        NON_ZERO_BYTE_GAS_FRONTIER
+    };
+
+    let mut gas = TRANSACTION_GAS + zero_bytes * ZERO_BYTE_GAS + non_zero_bytes * non_zero_byte_gas;
+
+    if request.to.is_none() {
+        if SpecId::enabled(spec, SpecId::HOMESTEAD) {
+            gas += CREATE_GAS;
+        }
+        if SpecId::enabled(spec, SpecId::SHANGHAI) {
+            let words = (zero_bytes + non_zero_bytes + 31) / 32;
+            gas += words * INIT_CODE_WORD_GAS;
+        }
+    }
+
+    if SpecId::enabled(spec, SpecId::BERLIN) {
+        gas += request.access_list.as_ref().map(access_list_intrinsic_gas).unwrap_or_default();
+    }
+
+    gas
+}
+
+/// Returns the intrinsic gas of the access list, see EIP-2930.
+pub(crate) fn access_list_intrinsic_gas(access_list: &AccessList) -> u64 {
+    access_list
+        .0
+        .iter()
+        .map(|item| {
+            ACCESS_LIST_ADDRESS_GAS + ACCESS_LIST_STORAGE_KEY_GAS * item.storage_keys.len() as u64
+        })
+        .sum()
+}
+
 /// Creates a new [Env] to be used for executing the [CallRequest] in `eth_call`.
 ///
 /// Note: this does _not_ access the Database to check the sender.
@@ -356,6 +797,67 @@ where
         .unwrap_or_default())
 }
 
+/// Validates the fields of the [CallRequest] before it is executed with the given [SpecId].
+///
+/// This rejects requests that can't be turned into a transaction up front, instead of failing
+/// during execution:
+///  - `gasPrice` combined with EIP-1559 fee fields, or fee fields that don't match the EIP-2718
+///    type of the request
+///  - transaction types and fee fields that aren't active at the [SpecId]
+///  - differing `input` and `data` fields
+///  - init code that exceeds the EIP-3860 limit
+pub(crate) fn validate_call_request(request: &CallRequest, spec_id: SpecId) -> EthResult<()> {
+    let has_eip1559_fees =
+        request.max_fee_per_gas.is_some() || request.max_priority_fee_per_gas.is_some();
+    if request.gas_price.is_some() && has_eip1559_fees {
+        return Err(EthApiError::ConflictingFeeFieldsInRequest)
+    }
+
+    let tx_type = request.transaction_type.map(|tx_type| tx_type.to::<u8>());
+    match tx_type {
+        // legacy and EIP-2930 transactions are priced with the gas price only
+        Some(LEGACY_TX_TYPE_ID | EIP2930_TX_TYPE_ID) if has_eip1559_fees => {
+            return Err(EthApiError::ConflictingFeeFieldsInRequest)
+        }
+        Some(EIP1559_TX_TYPE_ID) if request.gas_price.is_some() => {
+            return Err(EthApiError::ConflictingFeeFieldsInRequest)
+        }
+        Some(LEGACY_TX_TYPE_ID | EIP2930_TX_TYPE_ID | EIP1559_TX_TYPE_ID) | None => {}
+        Some(_) => return Err(RpcInvalidTransactionError::TxTypeNotSupported.into()),
+    }
+
+    let is_eip1559 = has_eip1559_fees || tx_type == Some(EIP1559_TX_TYPE_ID);
+    let is_eip2930 = request.access_list.is_some() || tx_type == Some(EIP2930_TX_TYPE_ID);
+    if (is_eip1559 && spec_id < SpecId::LONDON) || (is_eip2930 && spec_id < SpecId::BERLIN) {
+        return Err(RpcInvalidTransactionError::TxTypeNotSupported.into())
+    }
+
+    let input = request.input.unique_input()?;
+    if request.to.is_none() &&
+        spec_id >= SpecId::SHANGHAI &&
+        input.map_or(false, |input| input.len() > MAX_INIT_CODE_SIZE)
+    {
+        return Err(RpcInvalidTransactionError::MaxInitCodeSizeExceeded.into())
+    }
+
+    Ok(())
+}
+
+/// Ensures the fee cap of the request covers the `base_fee`, as required for the request to be
+/// included in a block as a transaction.
+///
+/// The fee cap is the `maxFeePerGas` of EIP-1559 requests and the `gasPrice` of legacy requests.
+/// Requests without fee fields are not checked.
+pub(crate) fn ensure_fee_cap_covers_base_fee(
+    request: &CallRequest,
+    base_fee: U256,
+) -> EthResult<()> {
+    match request.max_fee_per_gas.or(request.gas_price) {
+        Some(fee_cap) if fee_cap < base_fee => Err(RpcInvalidTransactionError::FeeCapTooLow.into()),
+        _ => Ok(()),
+    }
+}
+
 /// Helper type for representing the fees of a [CallRequest]
 pub(crate) struct CallFees {
     /// EIP-1559 priority fee
@@ -438,7 +940,10 @@ fn apply_block_overrides(overrides: BlockOverrides, env: &mut BlockEnv) {
 }
 
 /// Applies the given state overrides (a set of [AccountOverride]) to the [CacheDB].
-fn apply_state_overrides<DB>(overrides: StateOverride, db: &mut CacheDB<DB>) -> EthResult<()>
+pub(crate) fn apply_state_overrides<DB>(
+    overrides: StateOverride,
+    db: &mut CacheDB<DB>,
+) -> EthResult<()>
 where
     DB: DatabaseRef,
     EthApiError: From<<DB as DatabaseRef>::Error>,
@@ -533,6 +1038,12 @@ pub(crate) fn result_output(res: &ExecutionResult) -> Option<bytes::Bytes> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::eth::error::ensure_success;
+    use assert_matches::assert_matches;
+    use reth_primitives::{AccessListItem, Bytes, U64, U8};
+    use reth_provider::test_utils::{ExtendedAccount, MockEthProvider};
+    use reth_revm::database::State;
+    use reth_rpc_types::CallInput;
 
     #[test]
     fn test_ensure_0_fallback() {
@@ -540,4 +1051,317 @@ mod tests {
             CallFees::ensure_fees(None, None, None, U256::from(99)).unwrap();
         assert_eq!(gas_price, U256::ZERO);
     }
+
+    #[test]
+    fn block_overrides_timestamp_and_prevrandao() {
+        // TIMESTAMP PUSH1 0x00 MSTORE PREVRANDAO PUSH1 0x20 MSTORE PUSH1 0x40 PUSH1 0x00 RETURN
+        let code = Bytes::from(vec![
+            0x42, 0x60, 0x00, 0x52, 0x44, 0x60, 0x20, 0x52, 0x60, 0x40, 0x60, 0x00, 0xf3,
+        ]);
+        let to = Address::random();
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code));
+
+        let block = BlockEnv {
+            timestamp: U256::from(1),
+            prevrandao: Some(H256::from_low_u64_be(1)),
+            ..Default::default()
+        };
+
+        let call = |overrides: BlockOverrides| {
+            let mut db = CacheDB::new(State::new(provider.clone()));
+            let request = CallRequest { to: Some(to), ..Default::default() };
+            let overrides = EvmOverrides::new(None, Some(Box::new(overrides)));
+            let env = prepare_call_env(
+                CfgEnv::default(),
+                block.clone(),
+                request,
+                30_000_000,
+                &mut db,
+                overrides,
+            )
+            .unwrap();
+            let (res, _) = transact(&mut db, env).unwrap();
+            let output = res.result.output().cloned().unwrap();
+            (U256::try_from_be_slice(&output[..32]).unwrap(), H256::from_slice(&output[32..]))
+        };
+
+        // no overrides, inherits the block's values
+        assert_eq!(call(Default::default()), (U256::from(1), H256::from_low_u64_be(1)));
+
+        // only prevrandao
+        let random = H256::from_low_u64_be(1337);
+        let overrides = BlockOverrides { random: Some(random), ..Default::default() };
+        assert_eq!(call(overrides), (U256::from(1), random));
+
+        // only timestamp
+        let overrides = BlockOverrides { time: Some(U64::from(42)), ..Default::default() };
+        assert_eq!(call(overrides), (U256::from(42), H256::from_low_u64_be(1)));
+
+        // both
+        let overrides = BlockOverrides {
+            time: Some(U64::from(42)),
+            random: Some(random),
+            ..Default::default()
+        };
+        assert_eq!(call(overrides), (U256::from(42), random));
+    }
+
+    #[test]
+    fn base_fee_override_applies_to_request_fees() {
+        let from = Address::random();
+        let to = Address::random();
+
+        // GASPRICE PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        let code = Bytes::from(vec![0x3a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]);
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code));
+        provider.add_account(from, ExtendedAccount::new(0, U256::from(u64::MAX)));
+
+        let call = |base_fee: Option<u64>, max_fee: Option<u64>, priority_fee: Option<u64>| {
+            let mut db = CacheDB::new(State::new(provider.clone()));
+            let request = CallRequest {
+                from: Some(from),
+                to: Some(to),
+                gas: Some(U256::from(100_000)),
+                max_fee_per_gas: max_fee.map(U256::from),
+                max_priority_fee_per_gas: priority_fee.map(U256::from),
+                ..Default::default()
+            };
+            let mut overrides = EvmOverrides::default();
+            if let Some(base_fee) = base_fee {
+                overrides = overrides.with_base_fee(U256::from(base_fee));
+            }
+            let env = prepare_call_env(
+                CfgEnv::default(),
+                BlockEnv { basefee: U256::from(10), ..Default::default() },
+                request,
+                30_000_000,
+                &mut db,
+                overrides,
+            )?;
+            let (res, _) = transact(&mut db, env)?;
+            Ok::<_, EthApiError>(U256::try_from_be_slice(&ensure_success(res.result)?).unwrap())
+        };
+
+        // base fee + priority fee
+        assert_eq!(call(Some(50), Some(100), Some(2)).unwrap(), U256::from(52));
+        // capped by the max fee
+        assert_eq!(call(Some(50), Some(51), Some(2)).unwrap(), U256::from(51));
+        // the max fee defaults to the base fee
+        assert_eq!(call(Some(50), None, Some(2)).unwrap(), U256::from(50));
+
+        // the max fee is below the overridden base fee, but not below the block's base fee
+        assert_matches!(
+            call(Some(50), Some(40), Some(2)),
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::FeeCapTooLow))
+        );
+        // without an override, a max fee below the block's base fee is still executed
+        assert_eq!(call(None, Some(5), None).unwrap(), U256::from(5));
+    }
+
+    #[test]
+    fn intrinsic_gas_vectors() {
+        let call = CallRequest { to: Some(Address::random()), ..Default::default() };
+        assert_eq!(intrinsic_gas(&call, SpecId::SHANGHAI), 21_000);
+
+        // two zero and three non-zero bytes
+        let input = Bytes::from(vec![0x00, 0x01, 0x00, 0x02, 0x03]);
+        let call = CallRequest { input: input.clone().into(), ..call };
+        assert_eq!(intrinsic_gas(&call, SpecId::SHANGHAI), 21_000 + 2 * 4 + 3 * 16);
+        // non-zero bytes were more expensive before EIP-2028
+        assert_eq!(intrinsic_gas(&call, SpecId::PETERSBURG), 21_000 + 2 * 4 + 3 * 68);
+
+        // 33 bytes of init code are two words
+        let init_code = Bytes::from(vec![0x01; 33]);
+        let create = CallRequest { input: init_code.into(), ..Default::default() };
+        assert_eq!(intrinsic_gas(&create, SpecId::SHANGHAI), 21_000 + 33 * 16 + 32_000 + 2 * 2);
+        // no init code cost before EIP-3860
+        assert_eq!(intrinsic_gas(&create, SpecId::MERGE), 21_000 + 33 * 16 + 32_000);
+
+        let access_list = AccessList(vec![
+            AccessListItem { address: Address::random(), storage_keys: vec![H256::random(); 2] },
+            AccessListItem { address: Address::random(), storage_keys: vec![] },
+        ]);
+        let call = CallRequest { access_list: Some(access_list), ..call };
+        assert_eq!(
+            intrinsic_gas(&call, SpecId::SHANGHAI),
+            21_000 + 2 * 4 + 3 * 16 + 2 * 2_400 + 2 * 1_900
+        );
+        // access lists were introduced in Berlin
+        assert_eq!(intrinsic_gas(&call, SpecId::ISTANBUL), 21_000 + 2 * 4 + 3 * 16);
+    }
+
+    #[test]
+    fn chain_id_override() {
+        // CHAINID PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        let code = Bytes::from(vec![0x46, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]);
+        let to = Address::random();
+        let provider = MockEthProvider::default();
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code));
+
+        let call = |chain_id: Option<u64>, overrides: EvmOverrides| {
+            let mut db = CacheDB::new(State::new(provider.clone()));
+            let request = CallRequest {
+                to: Some(to),
+                chain_id: chain_id.map(U64::from),
+                ..Default::default()
+            };
+            let cfg = CfgEnv { chain_id: U256::from(1), ..Default::default() };
+            let env = prepare_call_env(
+                cfg,
+                BlockEnv::default(),
+                request,
+                30_000_000,
+                &mut db,
+                overrides,
+            )?;
+            let (res, _) = transact(&mut db, env)?;
+            Ok::<_, EthApiError>(U256::try_from_be_slice(&ensure_success(res.result)?).unwrap())
+        };
+
+        // the chain id of the chain spec
+        assert_eq!(call(None, EvmOverrides::default()).unwrap(), U256::from(1));
+
+        let overrides = EvmOverrides::default().with_chain_id(10);
+        assert_eq!(call(None, overrides.clone()).unwrap(), U256::from(10));
+
+        // replay protected requests must use the overridden chain id
+        assert_eq!(call(Some(10), overrides.clone()).unwrap(), U256::from(10));
+        assert_matches!(
+            call(Some(1), overrides),
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::InvalidChainId))
+        );
+    }
+
+    #[test]
+    fn validate_call_request_fields() {
+        let fee = Some(U256::from(10));
+        let typed = |tx_type: u8| CallRequest {
+            transaction_type: Some(U8::from(tx_type)),
+            ..Default::default()
+        };
+        let validate = |request: CallRequest| validate_call_request(&request, SpecId::SHANGHAI);
+
+        assert!(validate(CallRequest::default()).is_ok());
+        assert!(validate(CallRequest { gas_price: fee, ..typed(0) }).is_ok());
+        assert!(validate(CallRequest { max_fee_per_gas: fee, ..typed(2) }).is_ok());
+
+        // legacy gas price next to EIP-1559 fee fields
+        assert_matches!(
+            validate(CallRequest { gas_price: fee, max_fee_per_gas: fee, ..Default::default() }),
+            Err(EthApiError::ConflictingFeeFieldsInRequest)
+        );
+        assert_matches!(
+            validate(CallRequest {
+                gas_price: fee,
+                max_priority_fee_per_gas: fee,
+                ..Default::default()
+            }),
+            Err(EthApiError::ConflictingFeeFieldsInRequest)
+        );
+
+        // fee fields that don't match the type of the request
+        assert_matches!(
+            validate(CallRequest { gas_price: fee, ..typed(2) }),
+            Err(EthApiError::ConflictingFeeFieldsInRequest)
+        );
+        assert_matches!(
+            validate(CallRequest { max_fee_per_gas: fee, ..typed(0) }),
+            Err(EthApiError::ConflictingFeeFieldsInRequest)
+        );
+        assert_matches!(
+            validate(CallRequest { max_priority_fee_per_gas: fee, ..typed(1) }),
+            Err(EthApiError::ConflictingFeeFieldsInRequest)
+        );
+        assert_matches!(
+            validate(typed(5)),
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::TxTypeNotSupported))
+        );
+
+        // typed requests before their fork
+        let eip1559 = CallRequest { max_fee_per_gas: fee, ..Default::default() };
+        assert_matches!(
+            validate_call_request(&eip1559, SpecId::BERLIN),
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::TxTypeNotSupported))
+        );
+        assert!(validate_call_request(&eip1559, SpecId::LONDON).is_ok());
+        let eip2930 = CallRequest { access_list: Some(AccessList::default()), ..typed(1) };
+        assert_matches!(
+            validate_call_request(&eip2930, SpecId::ISTANBUL),
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::TxTypeNotSupported))
+        );
+
+        // calldata
+        let input = |input: Vec<u8>, data: Vec<u8>| CallInput {
+            input: Some(Bytes::from(input)),
+            data: Some(Bytes::from(data)),
+        };
+        assert_matches!(
+            validate(CallRequest { input: input(vec![1], vec![2]), ..Default::default() }),
+            Err(EthApiError::CallInputError(_))
+        );
+        let init_code = vec![0; MAX_INIT_CODE_SIZE + 1];
+        let create =
+            CallRequest { input: input(init_code.clone(), init_code), ..Default::default() };
+        assert_matches!(
+            validate(create.clone()),
+            Err(EthApiError::InvalidTransaction(
+                RpcInvalidTransactionError::MaxInitCodeSizeExceeded
+            ))
+        );
+        // the limit only applies to contract creations since shanghai
+        assert!(validate_call_request(&create, SpecId::MERGE).is_ok());
+        assert!(validate(CallRequest { to: Some(Address::random()), ..create }).is_ok());
+    }
+
+    #[test]
+    fn call_limits() {
+        let to = Address::random();
+        let provider = MockEthProvider::default();
+
+        // PUSH3 0x100000 MLOAD STOP, expands the memory to 1MiB
+        let memory = Address::random();
+        let code = Bytes::from(vec![0x62, 0x10, 0x00, 0x00, 0x51, 0x00]);
+        provider.add_account(memory, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code));
+
+        // PUSH1 0x00 (x5) ADDRESS GAS CALL STOP, calls itself recursively
+        let code = Bytes::from(vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x30, 0x5a, 0xf1, 0x00,
+        ]);
+        provider.add_account(to, ExtendedAccount::new(0, U256::ZERO).with_bytecode(code));
+
+        let call = |to: Address, limits: CallLimits| {
+            let mut db = CacheDB::new(State::new(provider.clone()));
+            let request = CallRequest { to: Some(to), ..Default::default() };
+            let overrides = EvmOverrides::default().with_limits(limits);
+            let env = prepare_call_env(
+                CfgEnv::default(),
+                BlockEnv::default(),
+                request,
+                30_000_000,
+                &mut db,
+                overrides,
+            )
+            .unwrap();
+            let (res, _) = transact_with_limits(&mut db, env, limits)?;
+            ensure_success(res.result)
+        };
+
+        // protocol defaults
+        assert!(call(memory, CallLimits::default()).is_ok());
+        assert!(call(to, CallLimits::default()).is_ok());
+
+        let limits = CallLimits::default().with_memory_limit(1024);
+        assert_matches!(
+            call(memory, limits),
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::MemoryLimitExceeded))
+        );
+
+        let limits = CallLimits::default().with_max_call_depth(8);
+        assert_matches!(
+            call(to, limits),
+            Err(EthApiError::InvalidTransaction(RpcInvalidTransactionError::CallDepthExceeded))
+        );
+    }
 }
@@ -0,0 +1,58 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use pprof::criterion::{Output, PProfProfiler};
+use reth_db::{
+    database::Database,
+    tables::CanonicalHeaders,
+    test_utils::create_test_rw_db,
+    transaction::{DbTx, DbTxMut},
+};
+use reth_primitives::H256;
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+    targets = bulk_load
+}
+criterion_main!(benches);
+
+/// Number of loaded entries.
+const ENTRIES: u64 = 100_000;
+
+/// Measures loading sorted entries into an empty table with `bulk_load` vs per-entry `put` calls.
+pub fn bulk_load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bulk_load");
+    group.measurement_time(std::time::Duration::from_millis(200));
+    group.warm_up_time(std::time::Duration::from_millis(200));
+
+    let db = create_test_rw_db();
+    let entries = (0..ENTRIES).map(|n| (n, H256::from_low_u64_be(n))).collect::<Vec<_>>();
+
+    let clear = || {
+        let tx = db.tx_mut().expect("tx");
+        tx.clear::<CanonicalHeaders>().expect("clear");
+        tx.commit().expect("commit");
+        entries.clone()
+    };
+
+    group.bench_function("put", |b| {
+        b.iter_batched(
+            clear,
+            |entries| {
+                let tx = db.tx_mut().expect("tx");
+                for (key, value) in entries {
+                    tx.put::<CanonicalHeaders>(key, value).expect("put");
+                }
+                tx.commit().expect("commit");
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.bench_function("bulk_load", |b| {
+        b.iter_batched(
+            clear,
+            |entries| db.bulk_load::<CanonicalHeaders>(entries).expect("bulk load"),
+            BatchSize::SmallInput,
+        )
+    });
+}
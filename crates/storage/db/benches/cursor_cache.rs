@@ -0,0 +1,62 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pprof::criterion::{Output, PProfProfiler};
+use reth_db::{
+    cursor::DbCursorRO,
+    database::Database,
+    tables::{CanonicalHeaders, HeaderNumbers, TxHashNumber},
+    test_utils::create_test_rw_db,
+    transaction::{DbTx, DbTxMut},
+};
+use reth_primitives::H256;
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+    targets = cursor_cache
+}
+criterion_main!(benches);
+
+/// Number of entries per table.
+const ENTRIES: u64 = 1_000;
+
+/// Measures many small point lookups across a few tables, opening a new cursor for every lookup
+/// vs reusing cursors from the transaction cursor cache.
+pub fn cursor_cache(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cursor_cache");
+    group.measurement_time(std::time::Duration::from_millis(200));
+    group.warm_up_time(std::time::Duration::from_millis(200));
+
+    let db = create_test_rw_db();
+    let tx = db.tx_mut().expect("tx");
+    for n in 0..ENTRIES {
+        let hash = H256::from_low_u64_be(n);
+        tx.put::<CanonicalHeaders>(n, hash).expect("put");
+        tx.put::<HeaderNumbers>(hash, n).expect("put");
+        tx.put::<TxHashNumber>(hash, n).expect("put");
+    }
+    tx.commit().expect("commit");
+
+    group.bench_function("cursor_read", |b| {
+        b.iter(|| {
+            let tx = db.tx().expect("tx");
+            for n in 0..ENTRIES {
+                let hash = H256::from_low_u64_be(n);
+                black_box(tx.cursor_read::<CanonicalHeaders>().unwrap().seek_exact(n).unwrap());
+                black_box(tx.cursor_read::<HeaderNumbers>().unwrap().seek_exact(hash).unwrap());
+                black_box(tx.cursor_read::<TxHashNumber>().unwrap().seek_exact(hash).unwrap());
+            }
+        })
+    });
+
+    group.bench_function("cursor_cached", |b| {
+        b.iter(|| {
+            let tx = db.tx().expect("tx");
+            for n in 0..ENTRIES {
+                let hash = H256::from_low_u64_be(n);
+                black_box(tx.cursor_cached::<CanonicalHeaders>().unwrap().seek_exact(n).unwrap());
+                black_box(tx.cursor_cached::<HeaderNumbers>().unwrap().seek_exact(hash).unwrap());
+                black_box(tx.cursor_cached::<TxHashNumber>().unwrap().seek_exact(hash).unwrap());
+            }
+        })
+    });
+}
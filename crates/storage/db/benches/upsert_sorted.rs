@@ -0,0 +1,61 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use pprof::criterion::{Output, PProfProfiler};
+use reth_db::{
+    database::Database,
+    tables::CanonicalHeaders,
+    test_utils::create_test_rw_db,
+    transaction::{DbTx, DbTxMut},
+};
+use reth_primitives::H256;
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+    targets = upsert_sorted
+}
+criterion_main!(benches);
+
+/// Number of upserted entries, half of which already exist.
+const ENTRIES: u64 = 10_000;
+
+/// Measures upserting sorted entries with a single cursor vs repeated `put` calls.
+pub fn upsert_sorted(c: &mut Criterion) {
+    let mut group = c.benchmark_group("upsert_sorted");
+    group.measurement_time(std::time::Duration::from_millis(200));
+    group.warm_up_time(std::time::Duration::from_millis(200));
+
+    let db = create_test_rw_db();
+    let tx = db.tx_mut().expect("tx");
+    for n in (0..ENTRIES).step_by(2) {
+        tx.put::<CanonicalHeaders>(n, H256::zero()).expect("put");
+    }
+    tx.commit().expect("commit");
+
+    let entries = (0..ENTRIES).map(|n| (n, H256::from_low_u64_be(n))).collect::<Vec<_>>();
+
+    group.bench_function("put", |b| {
+        b.iter_batched(
+            || entries.clone(),
+            |entries| {
+                let tx = db.tx_mut().expect("tx");
+                for (key, value) in entries {
+                    tx.put::<CanonicalHeaders>(key, value).expect("put");
+                }
+                drop(tx);
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.bench_function("upsert_sorted", |b| {
+        b.iter_batched(
+            || entries.clone(),
+            |entries| {
+                let tx = db.tx_mut().expect("tx");
+                tx.upsert_sorted::<CanonicalHeaders>(entries).expect("upsert");
+                drop(tx);
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
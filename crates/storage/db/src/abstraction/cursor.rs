@@ -4,7 +4,7 @@ use std::{
 };
 
 use crate::{
-    common::{IterPairResult, PairResult, ValueOnlyResult},
+    common::{IterPairResult, KeyValue, PairResult, ValueOnlyResult},
     table::{DupSort, Table, TableRow},
     DatabaseError,
 };
@@ -20,6 +20,36 @@ pub trait DbCursorRO<'tx, T: Table> {
     /// Seeks to the KV pair whose key is greater than or equal to `key`.
     fn seek(&mut self, key: T::Key) -> PairResult<T>;
 
+    /// Seeks to the KV pair whose key is less than or equal to `key`.
+    ///
+    /// Returns the last entry if `key` is beyond the last key of the table, and `None` if all keys
+    /// are greater than `key`.
+    fn seek_floor(&mut self, key: T::Key) -> PairResult<T> {
+        match self.seek(key.clone())? {
+            Some((found, value)) if found == key => Ok(Some((found, value))),
+            // overshot, step back to the greatest key lower than the target
+            Some(_) => self.prev(),
+            None => self.last(),
+        }
+    }
+
+    /// Positions the cursor at the entry with the zero-based index `n` in the table, returning it.
+    ///
+    /// Returns `None` if the table has `n` or fewer entries. For `DUPSORT` tables every duplicate
+    /// value counts as an entry.
+    ///
+    /// Note: MDBX can't position a cursor by index, so this advances from the first entry and is
+    /// `O(n)`. Seeking by key should be preferred wherever offsets aren't genuinely needed.
+    fn seek_nth(&mut self, n: u64) -> PairResult<T> {
+        let mut entry = self.first()?;
+        let mut index = 0;
+        while entry.is_some() && index < n {
+            entry = self.next()?;
+            index += 1;
+        }
+        Ok(entry)
+    }
+
     /// Position the cursor at the next KV pair, returning it.
     #[allow(clippy::should_implement_trait)]
     fn next(&mut self) -> PairResult<T>;
@@ -31,6 +61,8 @@ pub trait DbCursorRO<'tx, T: Table> {
     fn last(&mut self) -> PairResult<T>;
 
     /// Get the KV pair at the cursor's current position.
+    ///
+    /// Returns `None` if the cursor is not positioned on an entry. The position is not changed.
     fn current(&mut self) -> PairResult<T>;
 
     /// Get an iterator that walks through the table.
@@ -44,6 +76,22 @@ pub trait DbCursorRO<'tx, T: Table> {
     where
         Self: Sized;
 
+    /// Get an iterator that walks through the table, yielding at most `limit` entries.
+    ///
+    /// Starts at the same position as [`DbCursorRO::walk`]. Once the walker is exhausted,
+    /// [`LimitedWalker::has_more`] reports whether it stopped because the limit was reached while
+    /// entries remained in the table.
+    fn walk_limited<'cursor>(
+        &'cursor mut self,
+        start_key: Option<T::Key>,
+        limit: usize,
+    ) -> Result<LimitedWalker<'cursor, 'tx, T, Self>, DatabaseError>
+    where
+        Self: Sized,
+    {
+        Ok(LimitedWalker::new(self.walk(start_key)?, limit))
+    }
+
     /// Get an iterator that walks over a range of keys in the table.
     fn walk_range<'cursor>(
         &'cursor mut self,
@@ -75,6 +123,21 @@ pub trait DbDupCursorRO<'tx, T: DupSort> {
     /// Positions the cursor at the next duplicate value of the current key.
     fn next_dup_val(&mut self) -> ValueOnlyResult<T>;
 
+    /// Positions the cursor at the previous duplicate value of the current key, returning it.
+    fn prev_dup(&mut self) -> PairResult<T>;
+
+    /// Positions the cursor at the last duplicate value of `key`, returning it.
+    ///
+    /// This is the value with the greatest subkey, found without walking all duplicates of `key`.
+    /// Returns `None` if there is no entry for `key`.
+    fn last_dup(&mut self, key: T::Key) -> ValueOnlyResult<T>;
+
+    /// Returns the number of duplicate values of `key`, without reading them.
+    ///
+    /// Returns `0` if there is no entry for `key`. Otherwise the cursor is left positioned at the
+    /// first duplicate of `key`.
+    fn dup_count(&mut self, key: T::Key) -> Result<u64, DatabaseError>;
+
     /// Positions the cursor at the entry greater than or equal to the provided key/subkey pair.
     ///
     /// # Note
@@ -101,6 +164,55 @@ pub trait DbDupCursorRO<'tx, T: DupSort> {
     ) -> Result<DupWalker<'cursor, 'tx, T, Self>, DatabaseError>
     where
         Self: Sized;
+
+    /// Get an iterator that walks through the duplicates of `key` in descending subkey order,
+    /// starting at the [last duplicate](DbDupCursorRO::last_dup).
+    fn walk_dup_back<'cursor>(
+        &'cursor mut self,
+        key: T::Key,
+    ) -> Result<DupReverseWalker<'cursor, 'tx, T, Self>, DatabaseError>
+    where
+        Self: Sized,
+    {
+        let start = self.last_dup(key.clone()).transpose().map(|res| res.map(|value| (key, value)));
+        Ok(DupReverseWalker::new(self, start))
+    }
+
+    /// Get an iterator that walks through all key value pairs of the dup table, starting at the
+    /// first key greater than or equal to `start`, or at the first entry of the table if `start`
+    /// is `None`.
+    ///
+    /// Unlike [`DbDupCursorRO::walk_dup`], this does not stop at the last duplicate of a key but
+    /// continues with the duplicates of the following keys, sorted by key and subkey.
+    fn walk_all_dup<'cursor>(
+        &'cursor mut self,
+        start: Option<T::Key>,
+    ) -> Result<Walker<'cursor, 'tx, T, Self>, DatabaseError>
+    where
+        Self: DbCursorRO<'tx, T> + Sized,
+    {
+        let start = match start {
+            Some(key) => self.seek(key),
+            None => self.first(),
+        }
+        .transpose();
+        Ok(Walker::new(self, start))
+    }
+
+    /// Returns all duplicate values of `key`, sorted by subkey, or an empty list if there is no
+    /// entry for `key`.
+    fn dup_values(&mut self, key: T::Key) -> Result<Vec<T::Value>, DatabaseError>
+    where
+        Self: DbCursorRO<'tx, T>,
+    {
+        let mut values = Vec::new();
+        let Some((_, first)) = self.seek_exact(key)? else { return Ok(values) };
+        values.push(first);
+        while let Some(value) = self.next_dup_val()? {
+            values.push(value);
+        }
+        Ok(values)
+    }
 }
 
 /// Read write cursor over table.
@@ -173,6 +285,29 @@ impl<'cursor, 'tx, T: Table, CURSOR: DbCursorRO<'tx, T>> Walker<'cursor, 'tx, T,
         let start = self.cursor.current().transpose();
         ReverseWalker::new(self.cursor, start)
     }
+
+    /// Converts the [`Walker`] into a [`FilteredWalker`] that only yields the entries whose
+    /// value satisfies the `predicate`.
+    pub fn filter_values<F>(self, predicate: F) -> FilteredWalker<'cursor, 'tx, T, CURSOR, F>
+    where
+        F: FnMut(&T::Value) -> bool,
+    {
+        FilteredWalker { walker: self, predicate }
+    }
+
+    /// Converts the [`Walker`] into a [`GroupedWalker`] that yields the runs of consecutive
+    /// entries whose keys map to the same group under `key_fn`.
+    ///
+    /// Entries are walked in key order, so the groups are built in a single pass. A group only
+    /// contains all entries that map to it if those entries are adjacent in key order, e.g. if
+    /// `key_fn` projects a prefix of the key.
+    pub fn group_by<G, F>(self, key_fn: F) -> GroupedWalker<'cursor, 'tx, T, CURSOR, G, F>
+    where
+        G: PartialEq,
+        F: Fn(&T::Key) -> G,
+    {
+        GroupedWalker { walker: self, key_fn, next_entry: None }
+    }
 }
 
 impl<'cursor, 'tx, T: Table, CURSOR: DbCursorRW<'tx, T> + DbCursorRO<'tx, T>>
@@ -184,6 +319,139 @@ impl<'cursor, 'tx, T: Table, CURSOR: DbCursorRW<'tx, T> + DbCursorRO<'tx, T>>
     }
 }
 
+/// Provides an iterator to `Cursor` that yields at most a fixed number of entries.
+/// Also check [`Walker`]
+pub struct LimitedWalker<'cursor, 'tx, T: Table, CURSOR: DbCursorRO<'tx, T>> {
+    /// Walker yielding the entries.
+    walker: Walker<'cursor, 'tx, T, CURSOR>,
+    /// Number of entries that may still be yielded.
+    remaining: usize,
+    /// Whether entries remained after the limit was reached.
+    has_more: bool,
+    /// flag whether is ended
+    is_done: bool,
+}
+
+impl<'cursor, 'tx, T: Table, CURSOR: DbCursorRO<'tx, T>> LimitedWalker<'cursor, 'tx, T, CURSOR> {
+    /// construct LimitedWalker
+    pub fn new(walker: Walker<'cursor, 'tx, T, CURSOR>, limit: usize) -> Self {
+        Self { walker, remaining: limit, has_more: false, is_done: false }
+    }
+
+    /// Returns `true` if the walk stopped because the limit was reached and more entries remain.
+    ///
+    /// This is only meaningful once the walker has returned `None`.
+    pub fn has_more(&self) -> bool {
+        self.has_more
+    }
+}
+
+impl<'cursor, 'tx, T: Table, CURSOR: DbCursorRO<'tx, T>> std::iter::Iterator
+    for LimitedWalker<'cursor, 'tx, T, CURSOR>
+{
+    type Item = Result<TableRow<T>, DatabaseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None
+        }
+
+        if self.remaining == 0 {
+            self.is_done = true;
+            // peek one entry past the limit to find out whether the table has more entries
+            return match self.walker.next() {
+                Some(Ok(_)) => {
+                    self.has_more = true;
+                    None
+                }
+                Some(Err(err)) => Some(Err(err)),
+                None => None,
+            }
+        }
+
+        let next_item = self.walker.next();
+        match next_item {
+            Some(_) => self.remaining -= 1,
+            None => self.is_done = true,
+        }
+        next_item
+    }
+}
+
+/// Provides an iterator to `Cursor` that skips the entries whose value doesn't satisfy a
+/// predicate, see [`Walker::filter_values`].
+///
+/// Errors, including entries that fail to decode, are yielded as they are.
+pub struct FilteredWalker<'cursor, 'tx, T: Table, CURSOR: DbCursorRO<'tx, T>, F> {
+    /// Walker yielding the unfiltered entries.
+    walker: Walker<'cursor, 'tx, T, CURSOR>,
+    /// Predicate the values of the yielded entries satisfy.
+    predicate: F,
+}
+
+impl<'cursor, 'tx, T, CURSOR, F> std::iter::Iterator for FilteredWalker<'cursor, 'tx, T, CURSOR, F>
+where
+    T: Table,
+    CURSOR: DbCursorRO<'tx, T>,
+    F: FnMut(&T::Value) -> bool,
+{
+    type Item = Result<TableRow<T>, DatabaseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.walker.next()? {
+                Ok((_, value)) if !(self.predicate)(&value) => continue,
+                item => return Some(item),
+            }
+        }
+    }
+}
+
+/// Provides an iterator to `Cursor` that yields runs of consecutive entries grouped by a projection
+/// of their keys, see [`Walker::group_by`].
+///
+/// An error is yielded in place of the group it occurred in.
+pub struct GroupedWalker<'cursor, 'tx, T: Table, CURSOR: DbCursorRO<'tx, T>, G, F> {
+    /// Walker yielding the ungrouped entries.
+    walker: Walker<'cursor, 'tx, T, CURSOR>,
+    /// Projection of a key to its group.
+    key_fn: F,
+    /// First entry of the next group, read while completing the previous one.
+    next_entry: Option<TableRow<T>>,
+}
+
+impl<'cursor, 'tx, T, CURSOR, G, F> std::iter::Iterator
+    for GroupedWalker<'cursor, 'tx, T, CURSOR, G, F>
+where
+    T: Table,
+    CURSOR: DbCursorRO<'tx, T>,
+    G: PartialEq,
+    F: Fn(&T::Key) -> G,
+{
+    type Item = Result<(G, Vec<TableRow<T>>), DatabaseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = match self.next_entry.take() {
+            Some(entry) => entry,
+            None => match self.walker.next()? {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(err)),
+            },
+        };
+
+        let group = (self.key_fn)(&first.0);
+        let mut entries = vec![first];
+        for item in self.walker.by_ref() {
+            match item {
+                Ok(entry) if (self.key_fn)(&entry.0) == group => entries.push(entry),
+                Ok(entry) => {
+                    self.next_entry = Some(entry);
+                    break
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        Some(Ok((group, entries)))
+    }
+}
+
 /// Provides a reverse iterator to `Cursor` when handling `Table`.
 /// Also check [`Walker`]
 pub struct ReverseWalker<'cursor, 'tx, T: Table, CURSOR: DbCursorRO<'tx, T>> {
@@ -343,3 +611,130 @@ impl<'cursor, 'tx, T: DupSort, CURSOR: DbDupCursorRO<'tx, T>> std::iter::Iterato
         self.cursor.next_dup().transpose()
     }
 }
+
+/// Provides a reverse iterator over the duplicates of a single key of a `DupSort` table.
+///
+/// See [`DbDupCursorRO::walk_dup_back`].
+pub struct DupReverseWalker<'cursor, 'tx, T: DupSort, CURSOR: DbDupCursorRO<'tx, T>> {
+    /// Cursor to be used to walk through the table.
+    cursor: &'cursor mut CURSOR,
+    /// Value where to start the walk.
+    start: IterPairResult<T>,
+    /// Phantom data for 'tx. As it is only used for `DbDupCursorRO`.
+    _tx_phantom: PhantomData<&'tx T>,
+}
+
+impl<'cursor, 'tx, T: DupSort, CURSOR: DbDupCursorRO<'tx, T>>
+    DupReverseWalker<'cursor, 'tx, T, CURSOR>
+{
+    /// construct DupReverseWalker
+    pub fn new(cursor: &'cursor mut CURSOR, start: IterPairResult<T>) -> Self {
+        Self { cursor, start, _tx_phantom: PhantomData }
+    }
+}
+
+impl<'cursor, 'tx, T: DupSort, CURSOR: DbCursorRW<'tx, T> + DbDupCursorRO<'tx, T>>
+    DupReverseWalker<'cursor, 'tx, T, CURSOR>
+{
+    /// Delete current item that walker points to.
+    pub fn delete_current(&mut self) -> Result<(), DatabaseError> {
+        self.cursor.delete_current()
+    }
+}
+
+impl<'cursor, 'tx, T: DupSort, CURSOR: DbDupCursorRO<'tx, T>> std::iter::Iterator
+    for DupReverseWalker<'cursor, 'tx, T, CURSOR>
+{
+    type Item = Result<TableRow<T>, DatabaseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.start.take();
+        if start.is_some() {
+            return start
+        }
+        self.cursor.prev_dup().transpose()
+    }
+}
+
+/// A row of a [MergeJoin]: a key and the values of both tables at that key.
+pub type JoinRow<A, B> =
+    (<A as Table>::Key, Option<<A as Table>::Value>, Option<<B as Table>::Value>);
+
+/// Provides an iterator over two tables with the same key type, in key order.
+///
+/// Both cursors are advanced in lockstep, so every entry of both tables is read exactly once.
+/// Keys that are only present in one of the tables yield `None` for the other table's value.
+///
+/// See [`merge_join`](crate::transaction::merge_join).
+pub struct MergeJoin<'tx, A, B, CA, CB>
+where
+    A: Table,
+    B: Table<Key = A::Key>,
+    CA: DbCursorRO<'tx, A>,
+    CB: DbCursorRO<'tx, B>,
+{
+    /// Cursor over the left table.
+    cursor_a: CA,
+    /// Cursor over the right table.
+    cursor_b: CB,
+    /// The next entry of the left table.
+    next_a: Option<KeyValue<A>>,
+    /// The next entry of the right table.
+    next_b: Option<KeyValue<B>>,
+    /// Phantom data for 'tx.
+    _tx_phantom: PhantomData<&'tx (A, B)>,
+}
+
+impl<'tx, A, B, CA, CB> MergeJoin<'tx, A, B, CA, CB>
+where
+    A: Table,
+    B: Table<Key = A::Key>,
+    CA: DbCursorRO<'tx, A>,
+    CB: DbCursorRO<'tx, B>,
+{
+    /// Creates a new [MergeJoin] that starts at the first entries of both cursors.
+    pub fn new(mut cursor_a: CA, mut cursor_b: CB) -> Result<Self, DatabaseError> {
+        let next_a = cursor_a.first()?;
+        let next_b = cursor_b.first()?;
+        Ok(Self { cursor_a, cursor_b, next_a, next_b, _tx_phantom: PhantomData })
+    }
+}
+
+impl<'tx, A, B, CA, CB> std::iter::Iterator for MergeJoin<'tx, A, B, CA, CB>
+where
+    A: Table,
+    B: Table<Key = A::Key>,
+    CA: DbCursorRO<'tx, A>,
+    CB: DbCursorRO<'tx, B>,
+{
+    type Item = Result<JoinRow<A, B>, DatabaseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (advance_a, advance_b) = match (&self.next_a, &self.next_b) {
+            (None, None) => return None,
+            (Some(_), None) => (true, false),
+            (None, Some(_)) => (false, true),
+            (Some((key_a, _)), Some((key_b, _))) => (key_a <= key_b, key_b <= key_a),
+        };
+
+        let (mut key, mut value_a, mut value_b) = (None, None, None);
+        if advance_a {
+            let (k, v) = self.next_a.take()?;
+            key = Some(k);
+            value_a = Some(v);
+            match self.cursor_a.next() {
+                Ok(next) => self.next_a = next,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        if advance_b {
+            let (k, v) = self.next_b.take()?;
+            key = Some(k);
+            value_b = Some(v);
+            match self.cursor_b.next() {
+                Ok(next) => self.next_b = next,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        key.map(|key| Ok((key, value_a, value_b)))
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     common::{Bounds, Sealed},
-    table::TableImporter,
+    table::{Table, TableImporter},
     transaction::{DbTx, DbTxMut},
     DatabaseError,
 };
@@ -20,6 +20,15 @@ pub trait DatabaseGAT<'a, __ImplicitBounds: Sealed = Bounds<&'a Self>>: Send + S
 /// Main Database trait that spawns transactions to be executed.
 pub trait Database: for<'a> DatabaseGAT<'a> {
     /// Create read only transaction.
+    ///
+    /// The transaction borrows the database and can be held across multiple reads, for example
+    /// across await points, as a consistent snapshot of the database. It must be committed or
+    /// dropped to be closed.
+    ///
+    /// Note: every open read transaction occupies a reader slot of the database and keeps the
+    /// pages of its snapshot from being reused by write transactions. Long-lived read transactions
+    /// can exhaust the reader slots and make the database grow, so they should be closed as soon
+    /// as they are no longer needed. See also [Database::view].
     fn tx(&self) -> Result<<Self as DatabaseGAT<'_>>::TX, DatabaseError>;
 
     /// Create read write transaction only possible if database is open with write access.
@@ -54,6 +63,120 @@ pub trait Database: for<'a> DatabaseGAT<'a> {
     }
 }
 
+/// A write transaction that is committed and replaced by a fresh one every `threshold` writes.
+///
+/// Write transactions that accumulate millions of writes can exceed the dirty page limits of MDBX.
+/// This splits the writes into chunks, each of which is committed in its own transaction.
+///
+/// # Atomicity
+///
+/// Only the writes of a single chunk are atomic. If the process crashes or a write fails midway,
+/// the chunks that were already committed persist and can't be rolled back, while the pending
+/// writes are lost. This must only be used where a partially written state is acceptable, e.g. by
+/// a stage that can resume from its checkpoint, which is why it is constructed with
+/// [AutoCommitTx::new_non_atomic].
+///
+/// If committing a chunk or reopening the transaction fails, the transaction stays closed and all
+/// further operations return the error.
+#[derive(Debug)]
+pub struct AutoCommitTx<'db, DB: Database> {
+    /// The database to open the transactions with.
+    db: &'db DB,
+    /// The open transaction, `None` if committing or reopening it failed.
+    tx: Option<<DB as DatabaseGAT<'db>>::TXMut>,
+    /// The error that closed the transaction.
+    error: Option<DatabaseError>,
+    /// The number of writes after which the transaction is committed.
+    threshold: usize,
+    /// The number of writes to the open transaction.
+    pending: usize,
+    /// The number of transactions that were committed so far.
+    commits: usize,
+}
+
+impl<'db, DB: Database> AutoCommitTx<'db, DB> {
+    /// Opens a write transaction that is committed every `threshold` writes, giving up the
+    /// atomicity of the writes as a whole, see [AutoCommitTx].
+    pub fn new_non_atomic(db: &'db DB, threshold: usize) -> Result<Self, DatabaseError> {
+        Ok(Self { db, tx: Some(db.tx_mut()?), error: None, threshold, pending: 0, commits: 0 })
+    }
+
+    /// Returns the open transaction.
+    ///
+    /// Reads see all writes, including those of the previous chunks. Writes made directly to the
+    /// transaction are not counted towards the threshold.
+    ///
+    /// Returns the error that closed the transaction if committing a chunk or reopening the
+    /// transaction failed.
+    pub fn tx(&self) -> Result<&<DB as DatabaseGAT<'db>>::TXMut, DatabaseError> {
+        self.tx.as_ref().ok_or_else(|| self.closed_error())
+    }
+
+    /// Returns the number of transactions that were committed so far.
+    pub fn commits(&self) -> usize {
+        self.commits
+    }
+
+    /// Puts the value in table `T`, committing the transaction if the threshold is reached.
+    pub fn put<T: Table>(&mut self, key: T::Key, value: T::Value) -> Result<(), DatabaseError> {
+        self.tx()?.put::<T>(key, value)?;
+        self.on_write()
+    }
+
+    /// Deletes the value from table `T`, committing the transaction if the threshold is reached.
+    pub fn delete<T: Table>(
+        &mut self,
+        key: T::Key,
+        value: Option<T::Value>,
+    ) -> Result<bool, DatabaseError> {
+        let deleted = self.tx()?.delete::<T>(key, value)?;
+        self.on_write()?;
+        Ok(deleted)
+    }
+
+    /// Commits the pending writes.
+    pub fn commit(mut self) -> Result<bool, DatabaseError> {
+        match self.tx.take() {
+            Some(tx) => tx.commit(),
+            None => Err(self.closed_error()),
+        }
+    }
+
+    /// Counts a write and replaces the transaction with a fresh one once the threshold is
+    /// reached.
+    fn on_write(&mut self) -> Result<(), DatabaseError> {
+        self.pending += 1;
+        if self.pending < self.threshold {
+            return Ok(())
+        }
+
+        let tx = self.tx.take().ok_or_else(|| self.closed_error())?;
+        // MDBX allows a single write transaction, so the old one is committed before the new one
+        // is opened
+        let reopened = tx.commit().and_then(|_| {
+            self.commits += 1;
+            self.pending = 0;
+            self.db.tx_mut()
+        });
+        match reopened {
+            Ok(tx) => {
+                self.tx = Some(tx);
+                Ok(())
+            }
+            Err(err) => {
+                self.error = Some(err.clone());
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns the error that closed the transaction.
+    fn closed_error(&self) -> DatabaseError {
+        // the transaction is only closed by a failed commit or reopen, which stores its error
+        self.error.clone().unwrap_or(DatabaseError::InitTransaction(0))
+    }
+}
+
 // Generic over Arc
 impl<'a, DB: Database> DatabaseGAT<'a> for Arc<DB> {
     type TX = <DB as DatabaseGAT<'a>>::TX;
@@ -85,3 +208,36 @@ impl<DB: Database> Database for &DB {
         <DB as Database>::tx_mut(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tables::CanonicalHeaders, test_utils::create_test_rw_db_with_path};
+    use reth_primitives::H256;
+
+    #[test]
+    fn auto_commit_tx_persists_all_chunks() {
+        let path = tempfile::TempDir::new().unwrap();
+        let db = create_test_rw_db_with_path(path.path());
+
+        let mut tx = AutoCommitTx::new_non_atomic(db.as_ref(), 4).unwrap();
+        for block in 0..10 {
+            tx.put::<CanonicalHeaders>(block, H256::from_low_u64_be(block)).unwrap();
+        }
+        assert_eq!(tx.commits(), 2);
+        // reads see the writes of the committed chunks and of the open transaction
+        assert_eq!(tx.tx().unwrap().entries::<CanonicalHeaders>().unwrap(), 10);
+        tx.commit().unwrap();
+        drop(db);
+
+        let db = create_test_rw_db_with_path(path.path());
+        let tx = db.tx().unwrap();
+        assert_eq!(tx.entries::<CanonicalHeaders>().unwrap(), 10);
+        for block in 0..10 {
+            assert_eq!(
+                tx.get::<CanonicalHeaders>(block).unwrap(),
+                Some(H256::from_low_u64_be(block))
+            );
+        }
+    }
+}
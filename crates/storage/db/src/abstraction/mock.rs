@@ -1,4 +1,5 @@
 //! Mock database
+use bytes::Bytes;
 use std::{collections::BTreeMap, ops::RangeBounds};
 
 use crate::{
@@ -59,6 +60,10 @@ impl<'a> DbTx<'a> for TxMock {
         todo!()
     }
 
+    fn get_raw<T: Table>(&self, _key: T::Key) -> Result<Option<Bytes>, DatabaseError> {
+        todo!()
+    }
+
     fn commit(self) -> Result<bool, DatabaseError> {
         todo!()
     }
@@ -192,6 +197,18 @@ impl<'tx, T: DupSort> DbDupCursorRO<'tx, T> for CursorMock {
         todo!()
     }
 
+    fn prev_dup(&mut self) -> PairResult<T> {
+        todo!()
+    }
+
+    fn last_dup(&mut self, _key: <T as Table>::Key) -> ValueOnlyResult<T> {
+        todo!()
+    }
+
+    fn dup_count(&mut self, _key: <T as Table>::Key) -> Result<u64, DatabaseError> {
+        todo!()
+    }
+
     fn seek_by_key_subkey(
         &mut self,
         _key: <T as Table>::Key,
@@ -37,6 +37,84 @@ pub trait Decompress: Send + Sync + Sized + Debug {
     fn decompress<B: AsRef<[u8]>>(value: B) -> Result<Self, DatabaseError>;
 }
 
+/// A value whose compressed form is prefixed with a schema version byte.
+///
+/// When the layout of a value changes, its [VersionedCompress::VERSION] is bumped. Rows that were
+/// written with an older version are still readable, since [VersionedDecompress] receives the
+/// version a row was written with and can parse the legacy layout, upgrading the value on read.
+/// This allows evolving values without migrating the table.
+///
+/// [Compress] and [Decompress] are implemented for such values with
+/// [impl_versioned_compression](crate::impl_versioned_compression).
+pub trait VersionedCompress: Send + Sync + Sized + Debug {
+    /// Schema version newly compressed values are written with.
+    const VERSION: u8;
+
+    /// Compresses the value to the given buffer, without the version prefix.
+    fn compress_versioned<B: bytes::BufMut + AsMut<[u8]>>(self, buf: &mut B);
+}
+
+/// Decompression of values that were compressed with [VersionedCompress].
+pub trait VersionedDecompress: Send + Sync + Sized + Debug {
+    /// Decompresses a value that was written with schema `version`, without the version prefix.
+    fn decompress_versioned(version: u8, value: &[u8]) -> Result<Self, DatabaseError>;
+}
+
+/// Codec the values of a table are encoded with, which a table is migrated between with
+/// `Env::recompress_table`.
+///
+/// Values are read with the codec the table was written with and written back with the codec it
+/// is migrated to.
+pub trait CompressionCodec<V>: Debug {
+    /// Compresses the value to the given buffer.
+    fn compress_to_buf(&self, value: V, buf: &mut Vec<u8>);
+
+    /// Decompresses a value that was written with this codec.
+    fn decompress(&self, value: &[u8]) -> Result<V, DatabaseError>;
+}
+
+/// [CompressionCodec] that encodes values with their [Compress] and [Decompress]
+/// implementations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DefaultCodec;
+
+impl<V: Compress + Decompress> CompressionCodec<V> for DefaultCodec {
+    fn compress_to_buf(&self, value: V, buf: &mut Vec<u8>) {
+        value.compress_to_buf(buf)
+    }
+
+    fn decompress(&self, value: &[u8]) -> Result<V, DatabaseError> {
+        V::decompress(value)
+    }
+}
+
+#[macro_export]
+/// Implements [Compress] and [Decompress] for types implementing [VersionedCompress] and
+/// [VersionedDecompress], prefixing the compressed values with their schema version.
+macro_rules! impl_versioned_compression {
+    ($($name:ty),+) => {
+        $(
+            impl $crate::table::Compress for $name {
+                type Compressed = Vec<u8>;
+
+                fn compress_to_buf<B: bytes::BufMut + AsMut<[u8]>>(self, buf: &mut B) {
+                    let version = <$name as $crate::table::VersionedCompress>::VERSION;
+                    bytes::BufMut::put_u8(buf, version);
+                    $crate::table::VersionedCompress::compress_versioned(self, buf);
+                }
+            }
+
+            impl $crate::table::Decompress for $name {
+                fn decompress<B: AsRef<[u8]>>(value: B) -> Result<Self, $crate::DatabaseError> {
+                    let (version, value) =
+                        value.as_ref().split_first().ok_or($crate::DatabaseError::DecodeError)?;
+                    $crate::table::VersionedDecompress::decompress_versioned(*version, value)
+                }
+            }
+        )+
+    };
+}
+
 /// Trait that will transform the data to be saved in the DB.
 pub trait Encode: Send + Sync + Sized + Debug {
     /// Encoded type.
@@ -96,6 +174,20 @@ pub trait DupSort: Table {
     type SubKey: Key;
 }
 
+/// A table with a secondary index table that maps a value derived from each entry back to the
+/// entry's key.
+///
+/// The index is kept in sync by writing the table with
+/// [DbTxMut::put_indexed](crate::transaction::DbTxMut::put_indexed) and
+/// [DbTxMut::delete_indexed](crate::transaction::DbTxMut::delete_indexed).
+pub trait IndexedTable: Table {
+    /// The index table, mapping index keys to keys of this table.
+    type Index: Table<Value = Self::Key>;
+
+    /// Returns the index key of the given value.
+    fn index_key(value: &Self::Value) -> <Self::Index as Table>::Key;
+}
+
 /// Allows duplicating tables across databases
 pub trait TableImporter<'tx>: for<'a> DbTxMut<'a> {
     /// Imports all table data from another transaction.
@@ -150,3 +242,106 @@ pub trait TableImporter<'tx>: for<'a> DbTxMut<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::Database, test_utils::create_test_memory_db};
+    use bytes::BufMut;
+
+    /// First version of [Point], which only had a single coordinate.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    struct LegacyPoint {
+        x: u64,
+    }
+
+    impl VersionedCompress for LegacyPoint {
+        const VERSION: u8 = 1;
+
+        fn compress_versioned<B: bytes::BufMut + AsMut<[u8]>>(self, buf: &mut B) {
+            buf.put_u64(self.x);
+        }
+    }
+
+    impl VersionedDecompress for LegacyPoint {
+        fn decompress_versioned(_version: u8, value: &[u8]) -> Result<Self, DatabaseError> {
+            let x = value.try_into().map_err(|_| DatabaseError::DecodeError)?;
+            Ok(Self { x: u64::from_be_bytes(x) })
+        }
+    }
+
+    /// Second version of [LegacyPoint], which added the `y` coordinate.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    struct Point {
+        x: u64,
+        y: u64,
+    }
+
+    impl VersionedCompress for Point {
+        const VERSION: u8 = 2;
+
+        fn compress_versioned<B: bytes::BufMut + AsMut<[u8]>>(self, buf: &mut B) {
+            buf.put_u64(self.x);
+            buf.put_u64(self.y);
+        }
+    }
+
+    impl VersionedDecompress for Point {
+        fn decompress_versioned(version: u8, value: &[u8]) -> Result<Self, DatabaseError> {
+            match version {
+                // legacy points lie on the x axis
+                1 => {
+                    let legacy = LegacyPoint::decompress_versioned(version, value)?;
+                    Ok(Self { x: legacy.x, y: 0 })
+                }
+                2 if value.len() == 16 => {
+                    let (x, y) = value.split_at(8);
+                    let x = u64::from_be_bytes(x.try_into().unwrap());
+                    Ok(Self { x, y: u64::from_be_bytes(y.try_into().unwrap()) })
+                }
+                _ => Err(DatabaseError::DecodeError),
+            }
+        }
+    }
+
+    crate::impl_versioned_compression!(LegacyPoint, Point);
+
+    /// The same table, before and after the layout of its values changed.
+    #[derive(Debug)]
+    struct LegacyPoints;
+
+    impl Table for LegacyPoints {
+        const NAME: &'static str = "Points";
+        type Key = u64;
+        type Value = LegacyPoint;
+    }
+
+    #[derive(Debug)]
+    struct Points;
+
+    impl Table for Points {
+        const NAME: &'static str = LegacyPoints::NAME;
+        type Key = u64;
+        type Value = Point;
+    }
+
+    #[test]
+    fn versioned_value_upgraded_on_read() {
+        let db = create_test_memory_db();
+        let tx = db.tx_mut().unwrap();
+        tx.put::<LegacyPoints>(1, LegacyPoint { x: 7 }).unwrap();
+        tx.put::<Points>(2, Point { x: 3, y: 4 }).unwrap();
+        tx.commit().unwrap();
+
+        let tx = db.tx().unwrap();
+        assert_eq!(tx.get::<Points>(1), Ok(Some(Point { x: 7, y: 0 })));
+        assert_eq!(tx.get::<Points>(2), Ok(Some(Point { x: 3, y: 4 })));
+        // the version prefix is stored in front of the value
+        let raw = tx.get_raw::<Points>(1).unwrap().unwrap();
+        assert_eq!(raw.as_ref(), &[1, 0, 0, 0, 0, 0, 0, 0, 7]);
+
+        // values of unknown versions are rejected
+        assert_eq!(Point::decompress([3u8, 0]), Err(DatabaseError::DecodeError));
+        assert_eq!(Point::decompress(Vec::new()), Err(DatabaseError::DecodeError));
+    }
+}
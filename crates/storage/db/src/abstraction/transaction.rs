@@ -1,9 +1,13 @@
 use crate::{
-    common::{Bounds, Sealed},
-    cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO, DbDupCursorRW},
-    table::{DupSort, Table},
+    common::{Bounds, PairResult, Sealed},
+    cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO, DbDupCursorRW, MergeJoin},
+    table::{DupSort, Encode, IndexedTable, Table, TableRow},
+    tables::RawTable,
     DatabaseError,
 };
+use bytes::Bytes;
+use reth_primitives::U256;
+use std::{collections::HashMap, ops::RangeBounds};
 
 /// Implements the GAT method from:
 /// <https://sabrinajewson.org/blog/the-better-alternative-to-lifetime-gats#the-better-gats>.
@@ -36,6 +40,11 @@ pub trait DbTxMutGAT<'a, __ImplicitBounds: Sealed = Bounds<&'a Self>>: Send + Sy
 pub trait DbTx<'tx>: for<'a> DbTxGAT<'a> {
     /// Get value
     fn get<T: Table>(&self, key: T::Key) -> Result<Option<T::Value>, DatabaseError>;
+    /// Get the raw value bytes as they are stored in the database, without decompressing them.
+    ///
+    /// The returned bytes are the on-disk representation, i.e. the output of
+    /// [`Compress`](crate::table::Compress) for `T::Value`.
+    fn get_raw<T: Table>(&self, key: T::Key) -> Result<Option<Bytes>, DatabaseError>;
     /// Commit for read only transaction will consume and free transaction and allows
     /// freeing of memory pages
     fn commit(self) -> Result<bool, DatabaseError>;
@@ -43,12 +52,84 @@ pub trait DbTx<'tx>: for<'a> DbTxGAT<'a> {
     fn drop(self);
     /// Iterate over read only values in table.
     fn cursor_read<T: Table>(&self) -> Result<<Self as DbTxGAT<'_>>::Cursor<T>, DatabaseError>;
+    /// Iterate over read only values in table, reusing a cursor released by a previous cached
+    /// cursor of this transaction if one is available.
+    ///
+    /// Cached cursors are reset before they are handed out again and never outlive the
+    /// transaction. This is useful for workloads doing many small reads across a few tables.
+    fn cursor_cached<T: Table>(&self) -> Result<<Self as DbTxGAT<'_>>::Cursor<T>, DatabaseError> {
+        self.cursor_read::<T>()
+    }
     /// Iterate over read only values in dup sorted table.
     fn cursor_dup_read<T: DupSort>(
         &self,
     ) -> Result<<Self as DbTxGAT<'_>>::DupCursor<T>, DatabaseError>;
     /// Returns number of entries in the table.
+    ///
+    /// For `DUPSORT` tables every duplicate value is counted as an entry.
     fn entries<T: Table>(&self) -> Result<usize, DatabaseError>;
+    /// Returns `true` if the table has no entries.
+    fn is_empty<T: Table>(&self) -> Result<bool, DatabaseError> {
+        Ok(self.entries::<T>()? == 0)
+    }
+    /// Returns the first key/value pair of the table, or `None` if the table is empty.
+    fn first<T: Table>(&self) -> PairResult<T> {
+        self.cursor_read::<T>()?.first()
+    }
+    /// Returns the last key/value pair of the table, or `None` if the table is empty.
+    fn last<T: Table>(&self) -> PairResult<T> {
+        self.cursor_read::<T>()?.last()
+    }
+    /// Returns the logical size of the table in bytes, the sum of the lengths of all encoded keys
+    /// and compressed values.
+    ///
+    /// Unlike the size of the table's pages, this excludes the overhead of the storage layout. The
+    /// entries are not decoded, but the whole table is walked, so this is `O(n)`.
+    fn logical_size<T: Table>(&self) -> Result<u64, DatabaseError> {
+        let mut cursor = self.cursor_read::<RawTable<T>>()?;
+        let mut size = 0u64;
+        for entry in cursor.walk(None)? {
+            let (key, value) = entry?;
+            size += (key.raw_key().len() + value.raw_value().len()) as u64;
+        }
+        Ok(size)
+    }
+    /// Returns up to `limit` entries with keys strictly greater than `after`, or from the start of
+    /// the table if `after` is `None`, together with the key to request the next page with.
+    ///
+    /// The next key is the key of the last returned entry, or `None` if there are no entries after
+    /// this page. Since pages are resumed by key rather than by offset, entries inserted or deleted
+    /// between two calls don't shift the entries of the following pages.
+    ///
+    /// Note: for `DUPSORT` tables, the remaining duplicates of the last returned key are skipped
+    /// when resuming.
+    fn page<T: Table>(
+        &self,
+        after: Option<T::Key>,
+        limit: usize,
+    ) -> Result<(Vec<TableRow<T>>, Option<T::Key>), DatabaseError> {
+        let mut cursor = self.cursor_read::<T>()?;
+        let mut next = match after.clone() {
+            Some(after) => match cursor.seek(after.clone())? {
+                Some((key, _)) if key == after => cursor.next()?,
+                entry => entry,
+            },
+            None => cursor.first()?,
+        };
+
+        let mut entries = Vec::new();
+        while entries.len() < limit {
+            let Some(entry) = next else { break };
+            entries.push(entry);
+            next = cursor.next()?;
+        }
+
+        let next_key = match next {
+            Some(_) => entries.last().map(|(key, _)| key.clone()).or(after),
+            None => None,
+        };
+        Ok((entries, next_key))
+    }
 }
 
 /// Read write transaction that allows writing to database
@@ -68,4 +149,501 @@ pub trait DbTxMut<'tx>: for<'a> DbTxMutGAT<'a> {
     fn cursor_dup_write<T: DupSort>(
         &self,
     ) -> Result<<Self as DbTxMutGAT<'_>>::DupCursorMut<T>, DatabaseError>;
+    /// Upserts the `entries`, which must be sorted by key, using a single cursor.
+    ///
+    /// Unlike appending, keys may already exist, in which case their values are overwritten.
+    /// Returns [DatabaseError::UnsortedEntries] if a key is not greater than its predecessor, in
+    /// which case all entries before it have already been written.
+    fn upsert_sorted<T: Table>(
+        &self,
+        entries: impl IntoIterator<Item = (T::Key, T::Value)>,
+    ) -> Result<(), DatabaseError> {
+        let mut cursor = self.cursor_write::<T>()?;
+        let mut previous: Option<T::Key> = None;
+        for (key, value) in entries {
+            if previous.as_ref().map_or(false, |previous| *previous >= key) {
+                return Err(DatabaseError::UnsortedEntries {
+                    table_name: T::NAME,
+                    key: key.encode().as_ref().into(),
+                })
+            }
+            cursor.upsert(key.clone(), value)?;
+            previous = Some(key);
+        }
+        Ok(())
+    }
+    /// Upserts the `entries` in any order using a single cursor.
+    ///
+    /// The entries are sorted by their encoded keys first, which allocates, so the cursor writes
+    /// them in table order. If a key occurs more than once, its last value is written. Prefer
+    /// [DbTxMut::upsert_sorted] if the entries are already sorted.
+    fn put_batch<T: Table>(&self, entries: Vec<(T::Key, T::Value)>) -> Result<(), DatabaseError> {
+        let mut entries = entries
+            .into_iter()
+            .map(|(key, value)| (key.clone().encode().as_ref().to_vec(), key, value))
+            .collect::<Vec<_>>();
+        // stable, so the last value of a duplicated key is written last
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut cursor = self.cursor_write::<T>()?;
+        for (_, key, value) in entries {
+            cursor.upsert(key, value)?;
+        }
+        Ok(())
+    }
+    /// Applies the `diff` to the table in a single cursor pass: keys mapped to `Some` value are
+    /// upserted and keys mapped to `None` are deleted.
+    ///
+    /// Like with [DbTxMut::put_batch], the keys are sorted by their encoding first, so the cursor
+    /// visits them in table order. Deleting a key that doesn't exist is a no-op. This is not meant
+    /// for `DUPSORT` tables, where only the first value of a deleted key would be removed.
+    fn apply_diff<T: Table>(
+        &self,
+        diff: HashMap<T::Key, Option<T::Value>>,
+    ) -> Result<(), DatabaseError> {
+        let mut diff = diff
+            .into_iter()
+            .map(|(key, value)| (key.clone().encode().as_ref().to_vec(), key, value))
+            .collect::<Vec<_>>();
+        diff.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        let mut cursor = self.cursor_write::<T>()?;
+        for (_, key, value) in diff {
+            match value {
+                Some(value) => cursor.upsert(key, value)?,
+                None => {
+                    if cursor.seek_exact(key)?.is_some() {
+                        cursor.delete_current()?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Deletes all entries in the key `range` and returns them in key order.
+    ///
+    /// All deleted entries are held in memory, which is costly for large ranges. If the entries
+    /// aren't needed, walk the range with a write cursor and delete them one by one instead.
+    fn take_range<T: Table>(
+        &self,
+        range: impl RangeBounds<T::Key>,
+    ) -> Result<Vec<TableRow<T>>, DatabaseError> {
+        let mut cursor = self.cursor_write::<T>()?;
+        let mut walker = cursor.walk_range(range)?;
+        let mut taken = Vec::new();
+        while let Some(entry) = walker.next().transpose()? {
+            walker.delete_current()?;
+            taken.push(entry);
+        }
+        Ok(taken)
+    }
+    /// Moves the entry at `key` from table `S` to table `D`, deleting it from `S`.
+    ///
+    /// Returns `false` if `S` has no entry at `key`, in which case nothing is written. Returns
+    /// [DatabaseError::EntryExists] if `D` already has an entry at `key`, in which case neither
+    /// table is changed.
+    fn move_entry<S: Table, D: Table<Key = S::Key, Value = S::Value>>(
+        &self,
+        key: S::Key,
+    ) -> Result<bool, DatabaseError> {
+        let mut source = self.cursor_write::<S>()?;
+        let Some((_, value)) = source.seek_exact(key.clone())? else { return Ok(false) };
+
+        let mut destination = self.cursor_write::<D>()?;
+        if destination.seek_exact(key.clone())?.is_some() {
+            return Err(DatabaseError::EntryExists {
+                table_name: D::NAME,
+                key: key.encode().as_ref().into(),
+            })
+        }
+        destination.insert(key, value)?;
+        source.delete_current()?;
+        Ok(true)
+    }
+    /// Puts the value at `key` and updates the index of the [IndexedTable] in the same
+    /// transaction.
+    ///
+    /// If the value at `key` is replaced by a value with a different index key, the stale index
+    /// entry is removed.
+    fn put_indexed<T: IndexedTable>(
+        &self,
+        key: T::Key,
+        value: T::Value,
+    ) -> Result<(), DatabaseError> {
+        let mut cursor = self.cursor_write::<T>()?;
+        let mut index = self.cursor_write::<T::Index>()?;
+        let index_key = T::index_key(&value);
+        if let Some((_, previous)) = cursor.seek_exact(key.clone())? {
+            let previous_index_key = T::index_key(&previous);
+            // only remove the stale index entry if it wasn't taken over by another key
+            if previous_index_key != index_key &&
+                index.seek_exact(previous_index_key)?.map_or(false, |(_, k)| k == key)
+            {
+                index.delete_current()?;
+            }
+        }
+        cursor.upsert(key.clone(), value)?;
+        index.upsert(index_key, key)
+    }
+    /// Deletes the value at `key` and its entry in the index of the [IndexedTable] in the same
+    /// transaction.
+    ///
+    /// Returns `false` if there is no value at `key`, in which case nothing is deleted.
+    fn delete_indexed<T: IndexedTable>(&self, key: T::Key) -> Result<bool, DatabaseError> {
+        let mut cursor = self.cursor_write::<T>()?;
+        let Some((_, value)) = cursor.seek_exact(key.clone())? else { return Ok(false) };
+        let mut index = self.cursor_write::<T::Index>()?;
+        if index.seek_exact(T::index_key(&value))?.map_or(false, |(_, k)| k == key) {
+            index.delete_current()?;
+        }
+        cursor.delete_current()?;
+        Ok(true)
+    }
+    /// Adds `delta` to the value at `key`, treating an absent value as zero, and returns the new
+    /// value.
+    ///
+    /// Returns [DatabaseError::ValueOverflow] if the addition overflows, in which case nothing is
+    /// written.
+    fn increment<T: Table<Value = U256>>(
+        &self,
+        key: T::Key,
+        delta: U256,
+    ) -> Result<U256, DatabaseError> {
+        let mut cursor = self.cursor_write::<T>()?;
+        let current = cursor.seek_exact(key.clone())?.map(|(_, value)| value).unwrap_or_default();
+        let value = current.checked_add(delta).ok_or_else(|| DatabaseError::ValueOverflow {
+            table_name: T::NAME,
+            key: key.clone().encode().as_ref().into(),
+        })?;
+        cursor.upsert(key, value)?;
+        Ok(value)
+    }
+    /// Subtracts `delta` from the value at `key`, treating an absent value as zero, and returns the
+    /// new value.
+    ///
+    /// Returns [DatabaseError::ValueUnderflow] if the subtraction underflows, in which case nothing
+    /// is written.
+    fn decrement<T: Table<Value = U256>>(
+        &self,
+        key: T::Key,
+        delta: U256,
+    ) -> Result<U256, DatabaseError> {
+        let mut cursor = self.cursor_write::<T>()?;
+        let current = cursor.seek_exact(key.clone())?.map(|(_, value)| value).unwrap_or_default();
+        let value = current.checked_sub(delta).ok_or_else(|| DatabaseError::ValueUnderflow {
+            table_name: T::NAME,
+            key: key.clone().encode().as_ref().into(),
+        })?;
+        cursor.upsert(key, value)?;
+        Ok(value)
+    }
+}
+
+/// Walks the tables `A` and `B`, which share a key type, in merge-join order.
+///
+/// Every key of either table is yielded once, in ascending order, together with the values of
+/// both tables at that key. This reads both tables sequentially instead of looking up every key of
+/// one table in the other.
+pub fn merge_join<'a, 'tx, A, B, TX>(
+    tx: &'a TX,
+) -> Result<
+    MergeJoin<'a, A, B, <TX as DbTxGAT<'a>>::Cursor<A>, <TX as DbTxGAT<'a>>::Cursor<B>>,
+    DatabaseError,
+>
+where
+    A: Table,
+    B: Table<Key = A::Key>,
+    TX: DbTx<'tx>,
+{
+    MergeJoin::new(tx.cursor_read::<A>()?, tx.cursor_read::<B>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::Database, table::Compress, test_utils::create_test_memory_db};
+    use reth_primitives::H256;
+
+    crate::table!(
+        /// Counters used to test numeric updates.
+        ( TestCounters ) u64 | U256
+    );
+
+    crate::table!(
+        /// Values joined with [TestCounters].
+        ( TestLabels ) u64 | U256
+    );
+
+    crate::table!(
+        /// Hashes indexed by [TestHashIndex].
+        ( TestHashes ) u64 | H256
+    );
+
+    crate::table!(
+        /// Index of [TestHashes].
+        ( TestHashIndex ) H256 | u64
+    );
+
+    impl IndexedTable for TestHashes {
+        type Index = TestHashIndex;
+
+        fn index_key(value: &H256) -> H256 {
+            *value
+        }
+    }
+
+    #[test]
+    fn indexed_table_put_and_delete() {
+        let db = create_test_memory_db();
+        let tx = db.tx_mut().unwrap();
+        let (a, b, c) = (H256::random(), H256::random(), H256::random());
+
+        // insert
+        tx.put_indexed::<TestHashes>(1, a).unwrap();
+        tx.put_indexed::<TestHashes>(2, b).unwrap();
+        assert_eq!(tx.get::<TestHashIndex>(a), Ok(Some(1)));
+        assert_eq!(tx.get::<TestHashIndex>(b), Ok(Some(2)));
+
+        // update with changed index key removes the stale index entry
+        tx.put_indexed::<TestHashes>(1, c).unwrap();
+        assert_eq!(tx.get::<TestHashes>(1), Ok(Some(c)));
+        assert_eq!(tx.get::<TestHashIndex>(a), Ok(None));
+        assert_eq!(tx.get::<TestHashIndex>(c), Ok(Some(1)));
+
+        // update with unchanged index key
+        tx.put_indexed::<TestHashes>(1, c).unwrap();
+        assert_eq!(tx.get::<TestHashIndex>(c), Ok(Some(1)));
+
+        // delete
+        assert_eq!(tx.delete_indexed::<TestHashes>(2), Ok(true));
+        assert_eq!(tx.get::<TestHashes>(2), Ok(None));
+        assert_eq!(tx.get::<TestHashIndex>(b), Ok(None));
+        assert_eq!(tx.delete_indexed::<TestHashes>(2), Ok(false));
+        assert_eq!(tx.get::<TestHashIndex>(c), Ok(Some(1)));
+    }
+
+    #[test]
+    fn page_through_table() {
+        let db = create_test_memory_db();
+        let tx = db.tx_mut().unwrap();
+        for key in [1u64, 3, 5, 7, 9] {
+            tx.put::<TestCounters>(key, U256::from(key)).unwrap();
+        }
+        let keys = |entries: Vec<(u64, U256)>| -> Vec<u64> {
+            entries.into_iter().map(|(key, _)| key).collect()
+        };
+
+        let (entries, next) = tx.page::<TestCounters>(None, 2).unwrap();
+        assert_eq!(keys(entries), vec![1, 3]);
+        assert_eq!(next, Some(3));
+
+        // entries inserted before and after the resume key
+        tx.put::<TestCounters>(2, U256::from(2)).unwrap();
+        tx.put::<TestCounters>(4, U256::from(4)).unwrap();
+
+        let (entries, next) = tx.page::<TestCounters>(next, 2).unwrap();
+        assert_eq!(keys(entries), vec![4, 5]);
+        assert_eq!(next, Some(5));
+
+        // the last page
+        let (entries, next) = tx.page::<TestCounters>(next, 2).unwrap();
+        assert_eq!(keys(entries), vec![7, 9]);
+        assert_eq!(next, None);
+
+        // resuming from a key that was deleted meanwhile
+        tx.delete::<TestCounters>(5, None).unwrap();
+        let (entries, next) = tx.page::<TestCounters>(Some(5), 10).unwrap();
+        assert_eq!(keys(entries), vec![7, 9]);
+        assert_eq!(next, None);
+
+        assert_eq!(tx.page::<TestCounters>(Some(9), 10), Ok((vec![], None)));
+    }
+
+    #[test]
+    fn logical_size_of_table() {
+        let db = create_test_memory_db();
+        let tx = db.tx_mut().unwrap();
+        assert_eq!(tx.logical_size::<TestCounters>(), Ok(0));
+
+        let values = [U256::ZERO, U256::from(1), U256::from(0x1234), U256::MAX];
+        let mut expected = 0;
+        for (key, value) in values.into_iter().enumerate() {
+            tx.put::<TestCounters>(key as u64, value).unwrap();
+            expected += 8 + value.compress().as_ref().len() as u64;
+        }
+        assert_eq!(tx.logical_size::<TestCounters>(), Ok(expected));
+    }
+
+    #[test]
+    fn put_batch_unsorted() {
+        let db = create_test_memory_db();
+        let tx = db.tx_mut().unwrap();
+        tx.put::<TestCounters>(4, U256::from(0)).unwrap();
+
+        let keys = [7u64, 1, 300, 4, 256, 0, 2];
+        let mut entries: Vec<_> = keys.iter().map(|key| (*key, U256::from(*key))).collect();
+        entries.push((1, U256::from(100)));
+        tx.put_batch::<TestCounters>(entries).unwrap();
+
+        let mut cursor = tx.cursor_read::<TestCounters>().unwrap();
+        let table = cursor.walk(None).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        let expected = [0u64, 1, 2, 4, 7, 256, 300]
+            .into_iter()
+            .map(|key| (key, U256::from(if key == 1 { 100 } else { key })))
+            .collect::<Vec<_>>();
+        assert_eq!(table, expected);
+    }
+
+    #[test]
+    fn apply_diff_upserts_and_deletes() {
+        let db = create_test_memory_db();
+        let tx = db.tx_mut().unwrap();
+        for key in [1u64, 3, 5, 7] {
+            tx.put::<TestCounters>(key, U256::from(key)).unwrap();
+        }
+
+        let diff = HashMap::from([
+            // insertions
+            (0, Some(U256::from(100))),
+            (4, Some(U256::from(400))),
+            (300, Some(U256::from(3000))),
+            // updates
+            (3, Some(U256::from(30))),
+            (7, Some(U256::from(70))),
+            // deletions, of a missing key too
+            (1, None),
+            (5, None),
+            (6, None),
+        ]);
+        tx.apply_diff::<TestCounters>(diff).unwrap();
+
+        let mut cursor = tx.cursor_read::<TestCounters>().unwrap();
+        let table = cursor.walk(None).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        let expected = [(0u64, 100u64), (3, 30), (4, 400), (7, 70), (300, 3000)]
+            .into_iter()
+            .map(|(key, value)| (key, U256::from(value)))
+            .collect::<Vec<_>>();
+        assert_eq!(table, expected);
+    }
+
+    #[test]
+    fn take_range_returns_deleted_entries() {
+        let db = create_test_memory_db();
+        let tx = db.tx_mut().unwrap();
+        for key in 0..10u64 {
+            tx.put::<TestCounters>(key, U256::from(key * 10)).unwrap();
+        }
+
+        let mut cursor = tx.cursor_read::<TestCounters>().unwrap();
+        let before = cursor.walk_range(3..7).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        drop(cursor);
+
+        let taken = tx.take_range::<TestCounters>(3..7).unwrap();
+        assert_eq!(taken, before);
+        assert_eq!(taken.iter().map(|(key, _)| *key).collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+
+        let mut cursor = tx.cursor_read::<TestCounters>().unwrap();
+        assert_eq!(cursor.walk_range(3..7).unwrap().next().transpose(), Ok(None));
+        let keys = cursor.walk(None).unwrap().map(|entry| entry.map(|(key, _)| key));
+        assert_eq!(keys.collect::<Result<Vec<_>, _>>(), Ok(vec![0, 1, 2, 7, 8, 9]));
+        drop(cursor);
+
+        // an empty range takes nothing
+        assert_eq!(tx.take_range::<TestCounters>(3..7), Ok(vec![]));
+    }
+
+    #[test]
+    fn increment_and_decrement() {
+        let db = create_test_memory_db();
+        let tx = db.tx_mut().unwrap();
+
+        // absent values start at zero
+        assert_eq!(tx.increment::<TestCounters>(1, U256::from(5)), Ok(U256::from(5)));
+        assert_eq!(tx.increment::<TestCounters>(1, U256::from(2)), Ok(U256::from(7)));
+        assert_eq!(tx.decrement::<TestCounters>(1, U256::from(7)), Ok(U256::ZERO));
+        assert_eq!(tx.get::<TestCounters>(1), Ok(Some(U256::ZERO)));
+
+        // overflow leaves the value untouched
+        tx.put::<TestCounters>(2, U256::MAX).unwrap();
+        assert!(matches!(
+            tx.increment::<TestCounters>(2, U256::from(1)),
+            Err(DatabaseError::ValueOverflow { table_name: "TestCounters", .. })
+        ));
+        assert_eq!(tx.get::<TestCounters>(2), Ok(Some(U256::MAX)));
+
+        // underflow, including of an absent value
+        assert!(matches!(
+            tx.decrement::<TestCounters>(1, U256::from(1)),
+            Err(DatabaseError::ValueUnderflow { table_name: "TestCounters", .. })
+        ));
+        assert!(matches!(
+            tx.decrement::<TestCounters>(3, U256::from(1)),
+            Err(DatabaseError::ValueUnderflow { .. })
+        ));
+        assert_eq!(tx.get::<TestCounters>(3), Ok(None));
+    }
+
+    #[test]
+    fn move_entry_between_tables() {
+        let db = create_test_memory_db();
+        let tx = db.tx_mut().unwrap();
+        tx.put::<TestCounters>(1, U256::from(1)).unwrap();
+        tx.put::<TestCounters>(2, U256::from(2)).unwrap();
+
+        assert_eq!(tx.move_entry::<TestCounters, TestLabels>(1), Ok(true));
+        assert_eq!(tx.get::<TestCounters>(1), Ok(None));
+        assert_eq!(tx.get::<TestLabels>(1), Ok(Some(U256::from(1))));
+
+        // and back
+        assert_eq!(tx.move_entry::<TestLabels, TestCounters>(1), Ok(true));
+        assert_eq!(tx.get::<TestCounters>(1), Ok(Some(U256::from(1))));
+        assert_eq!(tx.get::<TestLabels>(1), Ok(None));
+
+        // missing source entry
+        assert_eq!(tx.move_entry::<TestCounters, TestLabels>(3), Ok(false));
+        assert_eq!(tx.get::<TestLabels>(3), Ok(None));
+
+        // the destination must not have the key yet
+        tx.put::<TestLabels>(2, U256::from(20)).unwrap();
+        assert!(matches!(
+            tx.move_entry::<TestCounters, TestLabels>(2),
+            Err(DatabaseError::EntryExists { table_name: "TestLabels", .. })
+        ));
+        assert_eq!(tx.get::<TestCounters>(2), Ok(Some(U256::from(2))));
+        assert_eq!(tx.get::<TestLabels>(2), Ok(Some(U256::from(20))));
+    }
+
+    #[test]
+    fn merge_join_partially_overlapping_keys() {
+        let db = create_test_memory_db();
+        let tx = db.tx_mut().unwrap();
+        for key in [1u64, 2, 4, 6] {
+            tx.put::<TestCounters>(key, U256::from(key)).unwrap();
+        }
+        for key in [2u64, 3, 6, 7] {
+            tx.put::<TestLabels>(key, U256::from(key * 10)).unwrap();
+        }
+
+        let rows = merge_join::<TestCounters, TestLabels, _>(&tx)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let value = |value: u64| Some(U256::from(value));
+        assert_eq!(
+            rows,
+            vec![
+                (1, value(1), None),
+                (2, value(2), value(20)),
+                (3, None, value(30)),
+                (4, value(4), None),
+                (6, value(6), value(60)),
+                (7, None, value(70)),
+            ]
+        );
+
+        // one of the tables is empty
+        tx.clear::<TestLabels>().unwrap();
+        let rows = merge_join::<TestCounters, TestLabels, _>(&tx).unwrap().count();
+        assert_eq!(rows, 4);
+    }
 }
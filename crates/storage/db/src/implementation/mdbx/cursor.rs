@@ -1,7 +1,10 @@
 //! Cursor wrapper for libmdbx-sys.
 
 use reth_interfaces::db::DatabaseWriteOperation;
-use std::{borrow::Cow, collections::Bound, marker::PhantomData, ops::RangeBounds};
+use std::{
+    borrow::Cow, collections::Bound, marker::PhantomData, mem::ManuallyDrop, ops::RangeBounds,
+    sync::Arc,
+};
 
 use crate::{
     common::{PairResult, ValueOnlyResult},
@@ -13,7 +16,9 @@ use crate::{
     tables::utils::*,
     DatabaseError,
 };
-use reth_libmdbx::{self, Error as MDBXError, TransactionKind, WriteFlags, RO, RW};
+use reth_libmdbx::{self, ffi::DBI, Error as MDBXError, TransactionKind, WriteFlags, RO, RW};
+
+use super::tx::CursorCache;
 
 /// Read only Cursor.
 pub type CursorRO<'tx, T> = Cursor<'tx, RO, T>;
@@ -23,14 +28,39 @@ pub type CursorRW<'tx, T> = Cursor<'tx, RW, T>;
 /// Cursor wrapper to access KV items.
 #[derive(Debug)]
 pub struct Cursor<'tx, K: TransactionKind, T: Table> {
-    /// Inner `libmdbx` cursor.
-    pub inner: reth_libmdbx::Cursor<'tx, K>,
+    /// Inner `libmdbx` cursor, taken out on drop to return it to the cursor cache.
+    pub(crate) inner: ManuallyDrop<reth_libmdbx::Cursor<'tx, K>>,
     /// Table name as is inside the database.
     pub table: &'static str,
     /// Phantom data to enforce encoding/decoding.
     pub _dbi: std::marker::PhantomData<T>,
     /// Cache buffer that receives compressed values.
     pub buf: Vec<u8>,
+    /// Transaction cursor cache the inner cursor is returned to on drop, if any.
+    pub(crate) cache: Option<(DBI, Arc<CursorCache>)>,
+}
+
+impl<'tx, K: TransactionKind, T: Table> Cursor<'tx, K, T> {
+    /// Returns the inner `libmdbx` cursor.
+    pub fn inner(&self) -> &reth_libmdbx::Cursor<'tx, K> {
+        &self.inner
+    }
+
+    /// Returns the inner `libmdbx` cursor mutably.
+    pub fn inner_mut(&mut self) -> &mut reth_libmdbx::Cursor<'tx, K> {
+        &mut self.inner
+    }
+}
+
+impl<'tx, K: TransactionKind, T: Table> Drop for Cursor<'tx, K, T> {
+    fn drop(&mut self) {
+        // SAFETY: `inner` is never accessed again after this.
+        let inner = unsafe { ManuallyDrop::take(&mut self.inner) };
+        match self.cache.take() {
+            Some((dbi, cache)) => cache.release(dbi, inner),
+            None => drop(inner),
+        }
+    }
 }
 
 /// Takes `(key, value)` from the database and decodes it appropriately.
@@ -161,6 +191,39 @@ impl<'tx, K: TransactionKind, T: DupSort> DbDupCursorRO<'tx, T> for Cursor<'tx,
             .transpose()
     }
 
+    /// Returns the previous `(key, value)` pair of a duplicate `key`.
+    fn prev_dup(&mut self) -> PairResult<T> {
+        decode!(self.inner.prev_dup())
+    }
+
+    /// Returns the last `value` of a duplicate `key`, using `MDBX_LAST_DUP`.
+    fn last_dup(&mut self, key: <T as Table>::Key) -> ValueOnlyResult<T> {
+        let found = self
+            .inner
+            .set::<()>(key.encode().as_ref())
+            .map_err(|e| DatabaseError::Read(e.into()))?;
+        if found.is_none() {
+            return Ok(None)
+        }
+        self.inner
+            .last_dup()
+            .map_err(|e| DatabaseError::Read(e.into()))?
+            .map(decode_one::<T>)
+            .transpose()
+    }
+
+    /// Returns the number of duplicates of `key`, using `mdbx_cursor_count`.
+    fn dup_count(&mut self, key: <T as Table>::Key) -> Result<u64, DatabaseError> {
+        let found = self
+            .inner
+            .set::<()>(key.encode().as_ref())
+            .map_err(|e| DatabaseError::Read(e.into()))?;
+        if found.is_none() {
+            return Ok(0)
+        }
+        self.inner.count().map(|count| count as u64).map_err(|e| DatabaseError::Read(e.into()))
+    }
+
     fn seek_by_key_subkey(
         &mut self,
         key: <T as Table>::Key,
@@ -222,6 +285,31 @@ impl<'tx, K: TransactionKind, T: DupSort> DbDupCursorRO<'tx, T> for Cursor<'tx,
     }
 }
 
+impl<'tx, T: Table> Cursor<'tx, RW, T> {
+    /// Asserts that the order of the encoded `key` relative to the key at the current position of
+    /// the cursor matches the order of the decoded keys.
+    ///
+    /// This catches [Encode] implementations that don't preserve the order of the key type, which
+    /// otherwise silently corrupt the order of the table. Only checked in debug builds.
+    #[cfg(debug_assertions)]
+    fn debug_assert_key_order(&mut self, key: &[u8]) {
+        use crate::table::Decode;
+
+        let Ok(Some((current, ()))) = self.inner.get_current::<Cow<'_, [u8]>, ()>() else { return };
+        let (Ok(decoded), Ok(current_decoded)) = (T::Key::decode(key), T::Key::decode(&current))
+        else {
+            return
+        };
+        assert_eq!(
+            decoded.cmp(&current_decoded),
+            key.cmp(current.as_ref()),
+            "encoding of key {decoded:?} in table {} does not preserve its order relative to key \
+             {current_decoded:?}",
+            T::NAME,
+        );
+    }
+}
+
 impl<'tx, T: Table> DbCursorRW<'tx, T> for Cursor<'tx, RW, T> {
     /// Database operation that will update an existing row if a specified value already
     /// exists in a table, and insert a new row if the specified value doesn't already exist
@@ -232,6 +320,8 @@ impl<'tx, T: Table> DbCursorRW<'tx, T> for Cursor<'tx, RW, T> {
     /// found, before calling `upsert`.
     fn upsert(&mut self, key: T::Key, value: T::Value) -> Result<(), DatabaseError> {
         let key = key.encode();
+        #[cfg(debug_assertions)]
+        self.debug_assert_key_order(key.as_ref());
         // Default `WriteFlags` is UPSERT
         self.inner.put(key.as_ref(), compress_or_ref!(self, value), WriteFlags::UPSERT).map_err(
             |e| DatabaseError::Write {
@@ -259,6 +349,8 @@ impl<'tx, T: Table> DbCursorRW<'tx, T> for Cursor<'tx, RW, T> {
     /// will fail if the inserted key is less than the last table key
     fn append(&mut self, key: T::Key, value: T::Value) -> Result<(), DatabaseError> {
         let key = key.encode();
+        #[cfg(debug_assertions)]
+        self.debug_assert_key_order(key.as_ref());
         self.inner.put(key.as_ref(), compress_or_ref!(self, value), WriteFlags::APPEND).map_err(
             |e| DatabaseError::Write {
                 code: e.into(),
@@ -291,3 +383,39 @@ impl<'tx, T: DupSort> DbDupCursorRW<'tx, T> for Cursor<'tx, RW, T> {
         )
     }
 }
+
+/// Type-erased cursor over the raw entries of a table, see [Env::for_each_table].
+///
+/// The entries are yielded as they are stored: keys are encoded and values are compressed with the
+/// codecs of the table, which are unknown to the cursor.
+///
+/// [Env::for_each_table]: super::Env::for_each_table
+pub trait RawCursor {
+    /// Moves the cursor to the next entry of the table and returns it, starting at the first
+    /// entry. Returns `None` once all entries were read.
+    fn next_raw(&mut self) -> Result<Option<(&[u8], &[u8])>, DatabaseError>;
+}
+
+/// [RawCursor] over a table of an MDBX transaction.
+#[derive(Debug)]
+pub(crate) struct RawTableCursor<'tx, K: TransactionKind> {
+    /// Inner `libmdbx` cursor.
+    inner: reth_libmdbx::Cursor<'tx, K>,
+    /// The entry the cursor is positioned at.
+    current: Option<(Cow<'tx, [u8]>, Cow<'tx, [u8]>)>,
+}
+
+impl<'tx, K: TransactionKind> RawTableCursor<'tx, K> {
+    /// Creates a raw cursor that starts before the first entry of the table.
+    pub(crate) fn new(inner: reth_libmdbx::Cursor<'tx, K>) -> Self {
+        Self { inner, current: None }
+    }
+}
+
+impl<'tx, K: TransactionKind> RawCursor for RawTableCursor<'tx, K> {
+    fn next_raw(&mut self) -> Result<Option<(&[u8], &[u8])>, DatabaseError> {
+        // an unpositioned cursor moves to the first entry
+        self.current = self.inner.next().map_err(|e| DatabaseError::Read(e.into()))?;
+        Ok(self.current.as_ref().map(|(key, value)| (key.as_ref(), value.as_ref())))
+    }
+}
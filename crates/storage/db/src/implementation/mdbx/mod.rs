@@ -1,17 +1,33 @@
 //! Module that interacts with MDBX.
 
 use crate::{
+    cursor::{DbCursorRO, DbCursorRW},
     database::{Database, DatabaseGAT},
-    tables::{TableType, Tables},
+    table::{CompressionCodec, Decode, Decompress, Table},
+    tables::{RawKey, RawTable, RawValue, RecompressProgress, TableDef, TableType, Tables},
+    transaction::{DbTx, DbTxMut},
     utils::default_page_size,
     DatabaseError,
 };
+use cursor::{RawCursor, RawTableCursor};
 use reth_interfaces::db::LogLevel;
 use reth_libmdbx::{
     DatabaseFlags, Environment, EnvironmentFlags, EnvironmentKind, Geometry, Mode, PageSize,
     SyncMode, RO, RW,
 };
-use std::{ops::Deref, path::Path};
+use reth_primitives::H256;
+use std::{
+    ops::Deref,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+use tracing::{debug, warn};
 use tx::Tx;
 
 pub mod cursor;
@@ -20,6 +36,22 @@ pub mod tx;
 const GIGABYTE: usize = 1024 * 1024 * 1024;
 const TERABYTE: usize = GIGABYTE * 1024;
 
+/// Number of auxiliary tables that can be created next to [Tables], see
+/// [Env::create_tables_with].
+const MAX_AUXILIARY_TABLES: usize = 32;
+
+/// The name of the MDBX data file inside of the database directory.
+const DATA_FILE_NAME: &str = "mdbx.dat";
+
+/// The smallest page size supported by MDBX (`MDBX_MIN_PAGESIZE`).
+const MIN_PAGE_SIZE: usize = 256;
+
+/// The largest page size supported by MDBX (`MDBX_MAX_PAGESIZE`).
+const MAX_PAGE_SIZE: usize = 0x10000;
+
+/// Number of entries that are re-encoded per write transaction by [Env::recompress_table].
+const RECOMPRESS_BATCH_SIZE: usize = 10_000;
+
 /// MDBX allows up to 32767 readers (`MDBX_READERS_LIMIT`), but we limit it to slightly below that
 const DEFAULT_MAX_READERS: u64 = 32_000;
 
@@ -32,11 +64,227 @@ pub enum EnvKind {
     RW,
 }
 
+/// Observes the outcome of the write transactions of a database environment.
+///
+/// Registered via [DbOpts::with_observer]. Useful to build change data capture or cache
+/// invalidation on top of the database.
+pub trait TxObserver: std::fmt::Debug + Send + Sync {
+    /// Called after a write transaction was successfully committed, with the id of the
+    /// transaction.
+    fn on_commit(&self, txn_id: u64);
+
+    /// Called after a write transaction was aborted, either explicitly, by dropping it or because
+    /// its commit failed.
+    fn on_abort(&self);
+}
+
+/// Options for opening a database environment.
+#[derive(Debug, Clone, Default)]
+pub struct DbOpts {
+    /// The log level of MDBX.
+    pub log_level: Option<LogLevel>,
+    /// The page size of a newly created database, in bytes. Defaults to the page size of the OS.
+    ///
+    /// The page size is fixed once the database is created, so this is ignored when opening an
+    /// existing database.
+    pub page_size: Option<usize>,
+    /// The observer notified about committed and aborted write transactions.
+    pub observer: Option<Arc<dyn TxObserver>>,
+    /// The genesis hash of the chain that an existing database must match.
+    ///
+    /// This is only checked by [init_db_with_opts](crate::init_db_with_opts).
+    pub expected_genesis: Option<H256>,
+}
+
+impl DbOpts {
+    /// Sets the log level of MDBX.
+    pub fn with_log_level(mut self, log_level: Option<LogLevel>) -> Self {
+        self.log_level = log_level;
+        self
+    }
+
+    /// Sets the page size of a newly created database, in bytes.
+    ///
+    /// Must be a power of two between 256 and 65536 bytes. Larger pages reduce the number of
+    /// overflow pages needed for large values.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Sets the observer that is notified about committed and aborted write transactions.
+    pub fn with_observer(mut self, observer: Arc<dyn TxObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Sets the genesis hash of the chain that the genesis header of an existing database must
+    /// match.
+    pub fn with_expected_genesis(mut self, genesis_hash: H256) -> Self {
+        self.expected_genesis = Some(genesis_hash);
+        self
+    }
+}
+
+/// Size of the database data file before and after a compaction, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionStats {
+    /// Size of the data file before the compaction.
+    pub size_before: u64,
+    /// Size of the compacted data file.
+    pub size_after: u64,
+}
+
+impl CompactionStats {
+    /// Returns the number of bytes the compaction returned to the file system.
+    pub fn reclaimed(&self) -> u64 {
+        self.size_before.saturating_sub(self.size_after)
+    }
+}
+
+/// Outcome of [Env::compact_in_place], in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionReport {
+    /// Size of the data file before the compaction.
+    pub bytes_before: u64,
+    /// Size of the compacted data file.
+    pub bytes_after: u64,
+    /// Number of bytes the compaction returned to the file system.
+    pub reclaimed: u64,
+}
+
+/// A compacted copy of the database, see [Env::create_savepoint].
+///
+/// The copy is removed when the savepoint is dropped.
+#[cfg(any(test, feature = "test-utils"))]
+#[derive(Debug)]
+pub struct Savepoint {
+    /// Id of the last committed transaction when the savepoint was created.
+    txn_id: u64,
+    /// Directory of the copy.
+    dir: tempfile::TempDir,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl Savepoint {
+    /// Returns the id of the last transaction that was committed when the savepoint was created.
+    pub fn txn_id(&self) -> u64 {
+        self.txn_id
+    }
+}
+
+/// Pages of the database that are on the freelist, see [Env::freelist_info].
+///
+/// Free pages are reused by later writes, but are only returned to the file system by a
+/// compaction, see [Env::close_with_compaction].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FreelistInfo {
+    /// Number of pages on the freelist.
+    pub free_pages: u64,
+    /// Number of pages allocated in the data file, including free pages.
+    pub total_pages: u64,
+    /// Size of a single page, in bytes.
+    pub page_size: u32,
+}
+
+impl FreelistInfo {
+    /// Returns the size of all pages on the freelist, in bytes.
+    ///
+    /// This is roughly the space a compaction would recover.
+    pub fn free_bytes(&self) -> u64 {
+        self.free_pages * self.page_size as u64
+    }
+
+    /// Returns the size of all allocated pages, in bytes.
+    pub fn total_bytes(&self) -> u64 {
+        self.total_pages * self.page_size as u64
+    }
+}
+
+/// Configuration and state of the MDBX environment, see [Env::env_info].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvInfo {
+    /// Size of the memory map, in bytes.
+    pub map_size: u64,
+    /// Id of the last committed transaction.
+    pub last_txn_id: u64,
+    /// Number of the last used page.
+    pub last_page_number: u64,
+    /// Maximum number of reader slots.
+    pub max_readers: u64,
+    /// Number of reader slots in use.
+    pub num_readers: u64,
+    /// The active geometry of the data file.
+    pub geometry: EnvGeometry,
+}
+
+/// The active geometry of the data file of the environment, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvGeometry {
+    /// Lower limit of the data file size.
+    pub min_size: u64,
+    /// Upper limit of the data file size.
+    pub max_size: u64,
+    /// Current size of the data file.
+    pub current_size: u64,
+    /// Threshold of unused space at the end of the data file above which it is shrunk.
+    pub shrink_threshold: u64,
+    /// Step the data file grows by.
+    pub growth_step: u64,
+}
+
+/// Handle to the reader maintenance task of a database, see [Env::spawn_reader_maintenance].
+///
+/// The task is stopped when the handle is dropped.
+#[derive(Debug)]
+pub struct MaintenanceHandle {
+    /// Dropping the sender stops the task.
+    stop: Option<mpsc::Sender<()>>,
+    /// The thread running the task.
+    thread: Option<JoinHandle<()>>,
+    /// Number of completed maintenance passes.
+    passes: Arc<AtomicU64>,
+    /// Number of passes that were skipped because the database was busy or returned an error.
+    skipped: Arc<AtomicU64>,
+}
+
+impl MaintenanceHandle {
+    /// Returns the number of completed maintenance passes.
+    pub fn passes(&self) -> u64 {
+        self.passes.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of maintenance passes that were skipped.
+    pub fn skipped(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// Stops the task and waits for a running pass to finish.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MaintenanceHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
 /// Wrapper for the libmdbx environment.
 #[derive(Debug)]
 pub struct Env<E: EnvironmentKind> {
     /// Libmdbx-sys environment.
     pub inner: Environment<E>,
+    /// Options the environment was opened with.
+    opts: DbOpts,
 }
 
 impl<'a, E: EnvironmentKind> DatabaseGAT<'a> for Env<E> {
@@ -54,7 +302,8 @@ impl<E: EnvironmentKind> Database for Env<E> {
     fn tx_mut(&self) -> Result<<Self as DatabaseGAT<'_>>::TXMut, DatabaseError> {
         Ok(Tx::new(
             self.inner.begin_rw_txn().map_err(|e| DatabaseError::InitTransaction(e.into()))?,
-        ))
+        )
+        .with_observer(self.opts.observer.clone()))
     }
 }
 
@@ -67,13 +316,41 @@ impl<E: EnvironmentKind> Env<E> {
         kind: EnvKind,
         log_level: Option<LogLevel>,
     ) -> Result<Env<E>, DatabaseError> {
+        Self::open_with_opts(path, kind, DbOpts::default().with_log_level(log_level))
+    }
+
+    /// Opens the database at the specified path with the given [DbOpts], creating it if it does
+    /// not exist yet.
+    ///
+    /// Returns [DatabaseError::InvalidPageSize] if the page size is not supported by MDBX.
+    pub fn open_with_opts(
+        path: &Path,
+        kind: EnvKind,
+        opts: DbOpts,
+    ) -> Result<Env<E>, DatabaseError> {
+        Self::open_with_flags(path, kind, opts, false)
+    }
+
+    /// Opens the database like [Env::open_with_opts]. If `exclusive` is set, the database is
+    /// locked for the lifetime of the environment, so no other process can open it.
+    fn open_with_flags(
+        path: &Path,
+        kind: EnvKind,
+        opts: DbOpts,
+        exclusive: bool,
+    ) -> Result<Env<E>, DatabaseError> {
+        let page_size = opts.page_size.unwrap_or_else(default_page_size);
+        if !page_size.is_power_of_two() || !(MIN_PAGE_SIZE..=MAX_PAGE_SIZE).contains(&page_size) {
+            return Err(DatabaseError::InvalidPageSize(page_size))
+        }
+
         let mode = match kind {
             EnvKind::RO => Mode::ReadOnly,
             EnvKind::RW => Mode::ReadWrite { sync_mode: SyncMode::Durable },
         };
 
         let mut inner_env = Environment::new();
-        inner_env.set_max_dbs(Tables::ALL.len());
+        inner_env.set_max_dbs(Tables::ALL.len() + MAX_AUXILIARY_TABLES);
         inner_env.set_geometry(Geometry {
             // Maximum database size of 4 terabytes
             size: Some(0..(4 * TERABYTE)),
@@ -81,7 +358,7 @@ impl<E: EnvironmentKind> Env<E> {
             growth_step: Some(4 * GIGABYTE as isize),
             // The database never shrinks
             shrink_threshold: None,
-            page_size: Some(PageSize::Set(default_page_size())),
+            page_size: Some(PageSize::Set(page_size)),
         });
         inner_env.set_flags(EnvironmentFlags {
             mode,
@@ -89,137 +366,1424 @@ impl<E: EnvironmentKind> Env<E> {
             // worsens it for random access (which is our access pattern outside of sync)
             no_rdahead: true,
             coalesce: true,
+            exclusive,
             ..Default::default()
         });
         // configure more readers
         inner_env.set_max_readers(DEFAULT_MAX_READERS);
 
-        if let Some(log_level) = log_level {
-            // Levels higher than [LogLevel::Notice] require libmdbx built with `MDBX_DEBUG` option.
-            let is_log_level_available = if cfg!(debug_assertions) {
-                true
-            } else {
-                matches!(
-                    log_level,
-                    LogLevel::Fatal | LogLevel::Error | LogLevel::Warn | LogLevel::Notice
-                )
-            };
-            if is_log_level_available {
-                inner_env.set_log_level(match log_level {
-                    LogLevel::Fatal => 0,
-                    LogLevel::Error => 1,
-                    LogLevel::Warn => 2,
-                    LogLevel::Notice => 3,
-                    LogLevel::Verbose => 4,
-                    LogLevel::Debug => 5,
-                    LogLevel::Trace => 6,
-                    LogLevel::Extra => 7,
-                });
-            } else {
-                return Err(DatabaseError::LogLevelUnavailable(log_level))
-            }
+        if let Some(log_level) = opts.log_level {
+            // Levels higher than [LogLevel::Notice] require libmdbx built with `MDBX_DEBUG` option.
+            let is_log_level_available = if cfg!(debug_assertions) {
+                true
+            } else {
+                matches!(
+                    log_level,
+                    LogLevel::Fatal | LogLevel::Error | LogLevel::Warn | LogLevel::Notice
+                )
+            };
+            if is_log_level_available {
+                inner_env.set_log_level(match log_level {
+                    LogLevel::Fatal => 0,
+                    LogLevel::Error => 1,
+                    LogLevel::Warn => 2,
+                    LogLevel::Notice => 3,
+                    LogLevel::Verbose => 4,
+                    LogLevel::Debug => 5,
+                    LogLevel::Trace => 6,
+                    LogLevel::Extra => 7,
+                });
+            } else {
+                return Err(DatabaseError::LogLevelUnavailable(log_level))
+            }
+        }
+
+        let env = Env {
+            inner: inner_env.open(path).map_err(|e| DatabaseError::FailedToOpen(e.into()))?,
+            opts,
+        };
+
+        if let Some(requested) = env.opts.page_size {
+            let actual = env.inner.stat().map_err(|e| DatabaseError::Stats(e.into()))?.page_size();
+            if actual as usize != requested {
+                warn!(
+                    target: "db::mdbx",
+                    requested,
+                    actual,
+                    "Ignoring page size of existing database"
+                );
+            }
+        }
+
+        Ok(env)
+    }
+
+    /// Creates all the defined tables, if necessary.
+    pub fn create_tables(&self) -> Result<(), DatabaseError> {
+        self.create_tables_with(&[])
+    }
+
+    /// Creates all the defined tables and the given auxiliary `tables`, if necessary.
+    ///
+    /// At most 32 auxiliary tables are supported.
+    pub fn create_tables_with(&self, tables: &[TableDef]) -> Result<(), DatabaseError> {
+        let tx = self.inner.begin_rw_txn().map_err(|e| DatabaseError::InitTransaction(e.into()))?;
+
+        let defaults = Tables::ALL.iter().map(|table| (table.name(), table.table_type()));
+        let auxiliary = tables.iter().map(|table| (table.name, table.table_type));
+        for (name, table_type) in defaults.chain(auxiliary) {
+            let flags = match table_type {
+                TableType::Table => DatabaseFlags::default(),
+                TableType::DupSort => DatabaseFlags::DUP_SORT,
+            };
+
+            tx.create_db(Some(name), flags).map_err(|e| DatabaseError::TableCreation(e.into()))?;
+        }
+
+        tx.commit().map_err(|e| DatabaseError::Commit(e.into()))?;
+
+        Ok(())
+    }
+
+    /// Closes the database after writing a compacted copy of it, which omits all free pages.
+    ///
+    /// If `dest` is set, the compacted copy is written to that directory and the database itself
+    /// is left untouched. Otherwise, the database is compacted in place like
+    /// [compact_in_place](Self::compact_in_place) does, but not reopened.
+    ///
+    /// This can take a long time on large databases, so it is never done automatically.
+    pub fn close_with_compaction(self, dest: Option<&Path>) -> eyre::Result<CompactionStats> {
+        let path = self.inner.path()?;
+        let data_file = path.join(DATA_FILE_NAME);
+        let size_before = std::fs::metadata(&data_file)?.len();
+
+        let compacted_file = if let Some(dest) = dest {
+            std::fs::create_dir_all(dest)?;
+            let dest_file = dest.join(DATA_FILE_NAME);
+            self.inner.copy(&dest_file, true)?;
+            drop(self);
+            dest_file
+        } else {
+            let (path, opts) = (path.to_path_buf(), self.opts.clone());
+            drop(self);
+            Self::compact_exclusively(&path, opts)?;
+            data_file
+        };
+
+        let size_after = std::fs::metadata(compacted_file)?.len();
+        Ok(CompactionStats { size_before, size_after })
+    }
+
+    /// Compacts the database in place and reopens it for writing, returning how much space was
+    /// reclaimed.
+    ///
+    /// The environment is closed and the database is reopened exclusively, which fails if another
+    /// process has it open. While the exclusive lock is held, a compacted copy is written to a
+    /// temporary file next to the data file and atomically renamed over it, so no other process
+    /// can open the database until the swap is done. Taking the environment by value guarantees
+    /// that no transactions of it are open. On any error, the original data file is left in place.
+    ///
+    /// The database is reopened with the [DbOpts] it was originally opened with.
+    pub fn compact_in_place(self) -> Result<(Self, CompactionReport), DatabaseError> {
+        let path = self.inner.path().map_err(compaction_error)?.to_path_buf();
+        let opts = self.opts.clone();
+        drop(self);
+
+        let data_file = path.join(DATA_FILE_NAME);
+        let bytes_before = std::fs::metadata(&data_file).map_err(compaction_error)?.len();
+        Self::compact_exclusively(&path, opts.clone())?;
+
+        let bytes_after = std::fs::metadata(&data_file).map_err(compaction_error)?.len();
+        let report = CompactionReport {
+            bytes_before,
+            bytes_after,
+            reclaimed: bytes_before.saturating_sub(bytes_after),
+        };
+        let env = Self::open_with_opts(&path, EnvKind::RW, opts)?;
+        debug!(target: "db::mdbx", reclaimed = report.reclaimed, "Compacted database");
+        Ok((env, report))
+    }
+
+    /// Opens the closed database at `path` exclusively and replaces its data file with a
+    /// compacted copy, before the lock is released.
+    fn compact_exclusively(path: &Path, opts: DbOpts) -> Result<(), DatabaseError> {
+        let data_file = path.join(DATA_FILE_NAME);
+        let tmp_file = path.join(format!("{DATA_FILE_NAME}.compact"));
+
+        let exclusive = Self::open_with_flags(path, EnvKind::RW, opts, true)?;
+        // remove leftovers of a previously interrupted compaction
+        if tmp_file.exists() {
+            std::fs::remove_file(&tmp_file).map_err(compaction_error)?;
+        }
+        let swapped = exclusive
+            .inner
+            .copy(&tmp_file, true)
+            .map_err(compaction_error)
+            .and_then(|_| std::fs::rename(&tmp_file, &data_file).map_err(compaction_error));
+        // the lock is only released once the compacted copy replaced the data file
+        drop(exclusive);
+        if swapped.is_err() {
+            let _ = std::fs::remove_file(&tmp_file);
+        }
+        swapped
+    }
+
+    /// Writes a compacted backup of the database to the directory `dest` while it stays open.
+    ///
+    /// `progress` is called with the number of bytes written so far and the estimated size of
+    /// the backup after every chunk. If it returns [`ControlFlow::Break`](std::ops::ControlFlow),
+    /// the backup is cancelled and the partially written data file is removed. Returns `false` if
+    /// the backup was cancelled.
+    ///
+    /// The backup reads the database in a read-only transaction, so cancelling it never affects
+    /// the database itself.
+    #[cfg(unix)]
+    pub fn backup_to<F>(&self, dest: &Path, mut progress: F) -> eyre::Result<bool>
+    where
+        F: FnMut(u64, u64) -> std::ops::ControlFlow<()>,
+    {
+        let info = self.freelist_info()?;
+        let total = info.total_bytes() - info.free_bytes();
+
+        std::fs::create_dir_all(dest)?;
+        let completed =
+            self.inner.copy_with_progress(&dest.join(DATA_FILE_NAME), true, |copied| {
+                progress(copied, total.max(copied))
+            })?;
+        Ok(completed)
+    }
+
+    /// Calls `f` with the name and a [RawCursor] of every table in [Tables], in order.
+    ///
+    /// The cursors read the raw entries in a single read-only transaction, so all tables are
+    /// visited at the same committed state. Keys and values are passed on encoded and compressed
+    /// as they are stored, which allows exporting or comparing whole databases without knowing the
+    /// types of the tables.
+    pub fn for_each_table(
+        &self,
+        mut f: impl FnMut(&str, &mut dyn RawCursor),
+    ) -> Result<(), DatabaseError> {
+        let tx = self.inner.begin_ro_txn().map_err(|e| DatabaseError::InitTransaction(e.into()))?;
+        for table in Tables::ALL {
+            let db =
+                tx.open_db(Some(table.name())).map_err(|e| DatabaseError::InitCursor(e.into()))?;
+            let cursor = tx.cursor(&db).map_err(|e| DatabaseError::InitCursor(e.into()))?;
+            f(table.name(), &mut RawTableCursor::new(cursor));
+        }
+        Ok(())
+    }
+
+    /// Captures the committed state of the database in a [Savepoint], which can be restored with
+    /// [Env::restore_savepoint].
+    ///
+    /// MDBX can't roll back committed transactions, so this writes a compacted copy of the whole
+    /// database to a temporary directory. This is expensive and only meant for tests.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn create_savepoint(&self) -> eyre::Result<Savepoint> {
+        let dir = tempfile::tempdir()?;
+        // the copy is taken in a read-only transaction, so it matches the last committed txn
+        let txn_id = self.inner.info()?.last_txnid() as u64;
+        self.inner.copy(&dir.path().join(DATA_FILE_NAME), true)?;
+        Ok(Savepoint { txn_id, dir })
+    }
+
+    /// Restores the state of the database that was captured by [Env::create_savepoint].
+    ///
+    /// All entries of the [Tables] are replaced by the entries of the savepoint in a single write
+    /// transaction, by copying them back from the savepoint. Auxiliary tables are left untouched.
+    /// This is expensive and only meant for tests.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn restore_savepoint(&self, savepoint: &Savepoint) -> eyre::Result<()> {
+        use reth_libmdbx::{NoWriteMap, WriteFlags};
+        use std::borrow::Cow;
+
+        let source = Env::<NoWriteMap>::open(savepoint.dir.path(), EnvKind::RO, None)?;
+        let source_tx = source.inner.begin_ro_txn()?;
+
+        let tx = self.inner.begin_rw_txn()?;
+        for table in Tables::ALL {
+            let db = tx.open_db(Some(table.name()))?;
+            tx.clear_db(db.dbi())?;
+
+            let source_db = source_tx.open_db(Some(table.name()))?;
+            let mut cursor = source_tx.cursor(&source_db)?;
+            for entry in cursor.iter_start::<Cow<'_, [u8]>, Cow<'_, [u8]>>() {
+                let (key, value) = entry?;
+                tx.put(db.dbi(), key, value, WriteFlags::empty())?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Returns the number of pages on the freelist, which a compaction would reclaim.
+    ///
+    /// The freelist is read in a read-only transaction, so pages that are freed by a concurrent
+    /// write transaction are not included until it is committed.
+    pub fn freelist_info(&self) -> Result<FreelistInfo, DatabaseError> {
+        let stat = self.inner.stat().map_err(|e| DatabaseError::Stats(e.into()))?;
+        let info = self.inner.info().map_err(|e| DatabaseError::Stats(e.into()))?;
+        let free_pages = self.inner.freelist().map_err(|e| DatabaseError::Stats(e.into()))?;
+
+        Ok(FreelistInfo {
+            free_pages: free_pages as u64,
+            // page numbers are zero based
+            total_pages: info.last_pgno() as u64 + 1,
+            page_size: stat.page_size(),
+        })
+    }
+
+    /// Returns the live configuration of the environment, as reported by `mdbx_env_info_ex`.
+    pub fn env_info(&self) -> Result<EnvInfo, DatabaseError> {
+        let info = self.inner.info().map_err(|e| DatabaseError::Stats(e.into()))?;
+        let geometry = info.geometry();
+
+        Ok(EnvInfo {
+            map_size: info.map_size() as u64,
+            last_txn_id: info.last_txnid() as u64,
+            last_page_number: info.last_pgno() as u64,
+            max_readers: info.max_readers() as u64,
+            num_readers: info.num_readers() as u64,
+            geometry: EnvGeometry {
+                min_size: geometry.min(),
+                max_size: geometry.max(),
+                current_size: geometry.current(),
+                shrink_threshold: geometry.shrink(),
+                growth_step: geometry.grow(),
+            },
+        })
+    }
+
+    /// Spawns a thread that maintains the reader slots of the database and flushes it to disk every
+    /// `interval`.
+    ///
+    /// A pass clears the reader slots of dead processes, so the pages retained by their snapshots
+    /// can be reused by the next write transaction, and flushes the data buffers to disk. It never
+    /// waits for write transactions: if the database is busy the pass is skipped.
+    ///
+    /// This doesn't run a GC pass or compact the database, MDBX only reclaims freed pages in write
+    /// transactions. Use [Env::compact_in_place] to shrink the data file.
+    ///
+    /// The task runs until the returned [MaintenanceHandle] is stopped or dropped.
+    pub fn spawn_reader_maintenance(self: &Arc<Self>, interval: Duration) -> MaintenanceHandle {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let passes = Arc::new(AtomicU64::new(0));
+        let skipped = Arc::new(AtomicU64::new(0));
+
+        let env = Arc::clone(self);
+        let (task_passes, task_skipped) = (Arc::clone(&passes), Arc::clone(&skipped));
+        let thread = std::thread::Builder::new()
+            .name("db-reader-maintenance".to_string())
+            .spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    match env.reader_maintenance_pass() {
+                        Ok(()) => task_passes.fetch_add(1, Ordering::Relaxed),
+                        Err(_) => task_skipped.fetch_add(1, Ordering::Relaxed),
+                    };
+                }
+            })
+            .expect("failed to spawn db maintenance thread");
+
+        MaintenanceHandle { stop: Some(stop), thread: Some(thread), passes, skipped }
+    }
+
+    /// Runs a single reader maintenance pass, see [Env::spawn_reader_maintenance].
+    fn reader_maintenance_pass(&self) -> Result<(), reth_libmdbx::Error> {
+        self.inner.reader_check()?;
+        // read-only environments can't be synced
+        if self.inner.is_read_write() {
+            self.inner.try_sync(false)?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if a table with the given name exists in the database.
+    ///
+    /// This opens the table in a read-only transaction without creating it if it's missing.
+    pub fn has_table(&self, name: &str) -> Result<bool, DatabaseError> {
+        let tx = self.inner.begin_ro_txn().map_err(|e| DatabaseError::InitTransaction(e.into()))?;
+
+        match tx.open_db(Some(name)) {
+            Ok(_) => Ok(true),
+            Err(reth_libmdbx::Error::NotFound) => Ok(false),
+            Err(e) => Err(DatabaseError::Read(e.into())),
+        }
+    }
+
+    /// Loads the `entries` into the empty table `T` in a single write transaction.
+    ///
+    /// Entries are appended with `MDBX_APPEND`, which avoids searching for the insert position of
+    /// every entry, and the transaction is committed once all entries are written. Hence the
+    /// entries must be sorted by key, and for `DUPSORT` tables every key must only appear once.
+    ///
+    /// Returns [DatabaseError::TableNotEmpty] if the table already contains entries. If the
+    /// entries are not sorted, an error is returned and nothing is written.
+    ///
+    /// Returns the number of loaded entries.
+    pub fn bulk_load<T: Table>(
+        &self,
+        entries: impl IntoIterator<Item = (T::Key, T::Value)>,
+    ) -> Result<u64, DatabaseError> {
+        let tx = self.tx_mut()?;
+        let mut cursor = tx.cursor_write::<T>()?;
+        if cursor.first()?.is_some() {
+            return Err(DatabaseError::TableNotEmpty { table_name: T::NAME })
+        }
+
+        let mut count = 0;
+        for (key, value) in entries {
+            cursor.append(key, value)?;
+            count += 1;
+        }
+        drop(cursor);
+        tx.commit()?;
+
+        Ok(count)
+    }
+
+    /// Re-encodes all values of table `T` from the `source_codec` to the `new_codec`, returning
+    /// the number of entries processed by this call.
+    ///
+    /// Every value is decompressed with the `source_codec` and written back if the `new_codec`
+    /// encodes it differently. The table is processed in batches that are committed in separate
+    /// write transactions, so readers are never blocked for long.
+    ///
+    /// The key to continue at is committed to the auxiliary [RecompressProgress] table together
+    /// with every batch, so an interrupted pass resumes after the last committed batch when it is
+    /// run again. The progress is removed once the whole table was processed.
+    ///
+    /// Returns [DatabaseError::DupSortUnsupported] for `DUPSORT` tables, where re-encoding a value
+    /// would add a duplicate instead of replacing it.
+    pub fn recompress_table<T: Table>(
+        &self,
+        source_codec: impl CompressionCodec<T::Value>,
+        new_codec: impl CompressionCodec<T::Value>,
+    ) -> Result<u64, DatabaseError> {
+        let tx = self.tx()?;
+        let db =
+            tx.inner.open_db(Some(T::NAME)).map_err(|e| DatabaseError::InitCursor(e.into()))?;
+        let flags = tx.inner.db_flags(&db).map_err(|e| DatabaseError::Read(e.into()))?;
+        if flags.contains(DatabaseFlags::DUP_SORT) {
+            return Err(DatabaseError::DupSortUnsupported { table_name: T::NAME })
+        }
+        drop(tx);
+        self.create_tables_with(&[TableDef::table(RecompressProgress::NAME)])?;
+
+        let progress_key = T::NAME.to_string();
+        let mut processed = 0;
+        let mut buf = Vec::new();
+        loop {
+            let tx = self.tx_mut()?;
+            let mut cursor = tx.cursor_write::<RawTable<T>>()?;
+            let mut entry = match tx.get::<RecompressProgress>(progress_key.clone())? {
+                Some(key) => cursor.seek(RawKey::decode(key)?)?,
+                None => cursor.first()?,
+            };
+
+            let mut batch = 0;
+            let mut next_key = None;
+            while let Some((key, value)) = entry {
+                if batch == RECOMPRESS_BATCH_SIZE {
+                    next_key = Some(key);
+                    break
+                }
+
+                buf.clear();
+                new_codec.compress_to_buf(source_codec.decompress(value.raw_value())?, &mut buf);
+                if &buf != value.raw_value() {
+                    cursor.upsert(key, RawValue::decompress(&buf)?)?;
+                }
+                batch += 1;
+                entry = cursor.next()?;
+            }
+            drop(cursor);
+
+            match &next_key {
+                Some(key) => {
+                    tx.put::<RecompressProgress>(progress_key.clone(), key.raw_key().clone())?
+                }
+                None => {
+                    tx.delete::<RecompressProgress>(progress_key.clone(), None)?;
+                }
+            }
+            tx.commit()?;
+
+            processed += batch as u64;
+            debug!(target: "db::mdbx", table = T::NAME, processed, "Recompressed table entries");
+            if next_key.is_none() {
+                return Ok(processed)
+            }
+        }
+    }
+
+    /// Exports all entries of table `T` to `writer` as newline-delimited JSON.
+    ///
+    /// Each line is a JSON object with a `key` and a `value` field. Entries are streamed from a
+    /// read-only transaction, so the table is never buffered in memory.
+    ///
+    /// Returns the number of exported entries.
+    #[cfg(feature = "json-export")]
+    pub fn export_table_ndjson<T: Table, W: std::io::Write>(
+        &self,
+        mut writer: W,
+    ) -> eyre::Result<u64> {
+        let tx = self.tx()?;
+        let mut cursor = tx.cursor_read::<T>()?;
+
+        let mut count = 0;
+        for entry in cursor.walk(None)? {
+            let (key, value) = entry?;
+            serde_json::to_writer(&mut writer, &NdjsonEntry { key: &key, value: &value })?;
+            writer.write_all(b"\n")?;
+            count += 1;
+        }
+        writer.flush()?;
+
+        Ok(count)
+    }
+}
+
+/// A single line of a table exported with [Env::export_table_ndjson].
+#[cfg(feature = "json-export")]
+#[derive(serde::Serialize)]
+struct NdjsonEntry<'a, K, V> {
+    key: &'a K,
+    value: &'a V,
+}
+
+/// Converts an error that occurred while compacting the database.
+fn compaction_error(err: impl std::fmt::Display) -> DatabaseError {
+    DatabaseError::Compaction(err.to_string())
+}
+
+impl<E: EnvironmentKind> Deref for Env<E> {
+    type Target = Environment<E>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        abstraction::table::{Compress, Decompress, DefaultCodec, Encode, Table},
+        cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO, DbDupCursorRW, ReverseWalker, Walker},
+        database::Database,
+        models::{AccountBeforeTx, ShardedKey},
+        tables::{
+            AccountHistory, CanonicalHeaders, Headers, PlainAccountState, PlainStorageState, RawKey,
+        },
+        test_utils::*,
+        transaction::{DbTx, DbTxMut},
+        AccountChangeSet, DatabaseError,
+    };
+    use reth_interfaces::db::DatabaseWriteOperation;
+    use reth_libmdbx::{NoWriteMap, WriteMap};
+    use reth_primitives::{Account, Address, Header, IntegerList, StorageEntry, H160, H256, U256};
+    use std::{ops::ControlFlow, path::Path, str::FromStr, sync::Arc};
+    use tempfile::TempDir;
+
+    /// Create database for testing
+    fn create_test_db<E: EnvironmentKind>(kind: EnvKind) -> Arc<Env<E>> {
+        Arc::new(create_test_db_with_path(
+            kind,
+            &tempfile::TempDir::new().expect(ERROR_TEMPDIR).into_path(),
+        ))
+    }
+
+    /// Create database for testing with specified path
+    fn create_test_db_with_path<E: EnvironmentKind>(kind: EnvKind, path: &Path) -> Env<E> {
+        let env = Env::<E>::open(path, kind, None).expect(ERROR_DB_CREATION);
+        env.create_tables().expect(ERROR_TABLE_CREATION);
+        env
+    }
+
+    const ERROR_DB_CREATION: &str = "Not able to create the mdbx file.";
+    const ERROR_PUT: &str = "Not able to insert value into table.";
+    const ERROR_APPEND: &str = "Not able to append the value to the table.";
+    const ERROR_UPSERT: &str = "Not able to upsert the value to the table.";
+    const ERROR_GET: &str = "Not able to get value from table.";
+    const ERROR_DEL: &str = "Not able to delete value from table.";
+    const ERROR_COMMIT: &str = "Not able to commit transaction.";
+    const ERROR_RETURN_VALUE: &str = "Mismatching result.";
+    const ERROR_INIT_TX: &str = "Failed to create a MDBX transaction.";
+    const ERROR_ETH_ADDRESS: &str = "Invalid address.";
+
+    #[test]
+    fn db_creation() {
+        create_test_db::<NoWriteMap>(EnvKind::RW);
+    }
+
+    #[test]
+    fn db_create_auxiliary_tables() {
+        crate::table!(
+            /// Auxiliary table used to test custom table sets.
+            ( AuxiliaryCounters ) u64 | H256
+        );
+
+        let path = TempDir::new().expect(ERROR_TEMPDIR).into_path();
+        let tables =
+            [TableDef::table(AuxiliaryCounters::NAME), TableDef::dup_sort("AuxiliaryDupSort")];
+        let env = crate::init_db_with_tables(&path, None, &tables).expect(ERROR_DB_CREATION);
+
+        for table in Tables::ALL {
+            assert!(env.has_table(table.name()).expect(ERROR_GET));
+        }
+        assert!(env.has_table(AuxiliaryCounters::NAME).expect(ERROR_GET));
+        assert!(env.has_table("AuxiliaryDupSort").expect(ERROR_GET));
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        tx.put::<AuxiliaryCounters>(1, H256::from_low_u64_be(1)).expect(ERROR_PUT);
+        tx.put::<CanonicalHeaders>(1, H256::from_low_u64_be(2)).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+
+        // creating the tables again is a no-op
+        drop(env);
+        let env = crate::init_db_with_tables(&path, None, &tables).expect(ERROR_DB_CREATION);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let value = tx.get::<AuxiliaryCounters>(1).expect(ERROR_GET);
+        assert_eq!(value, Some(H256::from_low_u64_be(1)));
+        let value = tx.get::<CanonicalHeaders>(1).expect(ERROR_GET);
+        assert_eq!(value, Some(H256::from_low_u64_be(2)));
+        let mut cursor = tx.cursor_read::<AuxiliaryCounters>().expect(ERROR_INIT_TX);
+        assert_eq!(cursor.first().expect(ERROR_GET), Some((1, H256::from_low_u64_be(1))));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "does not preserve its order")]
+    fn db_cursor_append_unordered_encoding() {
+        /// Key whose encoding doesn't preserve the numeric order.
+        #[derive(
+            Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+        )]
+        pub struct LittleEndianKey(u64);
+
+        impl Encode for LittleEndianKey {
+            type Encoded = [u8; 8];
+
+            fn encode(self) -> Self::Encoded {
+                self.0.to_le_bytes()
+            }
+        }
+
+        impl crate::table::Decode for LittleEndianKey {
+            fn decode<B: AsRef<[u8]>>(value: B) -> Result<Self, DatabaseError> {
+                let bytes = value.as_ref().try_into().map_err(|_| DatabaseError::DecodeError)?;
+                Ok(Self(u64::from_le_bytes(bytes)))
+            }
+        }
+
+        crate::table!(
+            /// Auxiliary table keyed by [LittleEndianKey].
+            ( LittleEndianCounters ) LittleEndianKey | H256
+        );
+
+        let path = TempDir::new().expect(ERROR_TEMPDIR).into_path();
+        let tables = [TableDef::table(LittleEndianCounters::NAME)];
+        let env = crate::init_db_with_tables(&path, None, &tables).expect(ERROR_DB_CREATION);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_write::<LittleEndianCounters>().expect(ERROR_INIT_TX);
+        cursor.append(LittleEndianKey(1), H256::zero()).expect(ERROR_APPEND);
+        // 256 sorts after 1 numerically, but before it once encoded as little endian
+        let _ = cursor.append(LittleEndianKey(256), H256::zero());
+    }
+
+    #[test]
+    fn db_has_table() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        for table in Tables::ALL {
+            assert!(env.has_table(table.name()).expect(ERROR_GET));
+        }
+        assert!(!env.has_table("UnknownTable").expect(ERROR_GET));
+    }
+
+    #[test]
+    fn db_close_with_compaction() {
+        let path = TempDir::new().expect(ERROR_TEMPDIR).into_path();
+        let env = create_test_db_with_path::<WriteMap>(EnvKind::RW, &path);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in 0..20_000u64 {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in 0..19_000u64 {
+            tx.delete::<CanonicalHeaders>(key, None).expect(ERROR_DEL);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        // compact into a separate directory, leaving the database untouched
+        let dest = TempDir::new().expect(ERROR_TEMPDIR).into_path();
+        let stats = env.close_with_compaction(Some(&dest)).unwrap();
+        assert!(stats.size_after < stats.size_before);
+
+        let env = Env::<WriteMap>::open(&dest, EnvKind::RO, None).expect(ERROR_DB_CREATION);
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(1_000));
+        drop(tx);
+        drop(env);
+
+        // compact in place
+        let env = Env::<WriteMap>::open(&path, EnvKind::RW, None).expect(ERROR_DB_CREATION);
+        let stats = env.close_with_compaction(None).unwrap();
+        assert!(stats.size_after < stats.size_before);
+        let size = std::fs::metadata(path.join(DATA_FILE_NAME)).unwrap().len();
+        assert_eq!(size, stats.size_after);
+
+        let env = Env::<WriteMap>::open(&path, EnvKind::RO, None).expect(ERROR_DB_CREATION);
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        assert_eq!(
+            tx.first::<CanonicalHeaders>(),
+            Ok(Some((19_000, H256::from_low_u64_be(19_000))))
+        );
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(1_000));
+    }
+
+    #[test]
+    fn db_compact_in_place() {
+        let path = TempDir::new().expect(ERROR_TEMPDIR).into_path();
+        let opts = DbOpts::default().with_page_size(8192);
+        let env = Env::<NoWriteMap>::open_with_opts(&path, EnvKind::RW, opts).unwrap();
+        env.create_tables().expect(ERROR_TABLE_CREATION);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in 0..20_000u64 {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in 1_000..20_000u64 {
+            tx.delete::<CanonicalHeaders>(key, None).expect(ERROR_DEL);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let (env, report) = env.compact_in_place().unwrap();
+        assert!(report.reclaimed > 0);
+        assert_eq!(report.reclaimed, report.bytes_before - report.bytes_after);
+        let size = std::fs::metadata(path.join(DATA_FILE_NAME)).unwrap().len();
+        assert_eq!(size, report.bytes_after);
+        assert!(!path.join(format!("{DATA_FILE_NAME}.compact")).exists());
+        // the database is reopened with the original options
+        assert_eq!(env.opts.page_size, Some(8192));
+
+        // the reopened database is writable and kept all entries
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(1_000));
+        tx.put::<CanonicalHeaders>(20_000, H256::zero()).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+    }
+
+    #[test]
+    fn db_manual_put_get() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        let value = Header::default();
+        let key = 1u64;
+
+        // PUT
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        tx.put::<Headers>(key, value.clone()).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+
+        // GET
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let result = tx.get::<Headers>(key).expect(ERROR_GET);
+        assert!(result.expect(ERROR_RETURN_VALUE) == value);
+        tx.commit().expect(ERROR_COMMIT);
+    }
+
+    #[test]
+    fn db_get_raw() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        let value = Header { number: 1, ..Default::default() };
+        let key = 1u64;
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        tx.put::<Headers>(key, value.clone()).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let raw = tx.get_raw::<Headers>(key).expect(ERROR_GET).expect(ERROR_RETURN_VALUE);
+        assert_eq!(raw.as_ref(), value.clone().compress().as_ref());
+        assert_eq!(Header::decompress(raw).expect(ERROR_RETURN_VALUE), value);
+        assert_eq!(tx.get_raw::<Headers>(2).expect(ERROR_GET), None);
+        tx.commit().expect(ERROR_COMMIT);
+    }
+
+    #[test]
+    fn db_tx_first_last() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        // Empty table
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        assert_eq!(tx.first::<CanonicalHeaders>(), Ok(None));
+        assert_eq!(tx.last::<CanonicalHeaders>(), Ok(None));
+        tx.commit().expect(ERROR_COMMIT);
+
+        // Populated table
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in [3u64, 1, 2] {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        assert_eq!(tx.first::<CanonicalHeaders>(), Ok(Some((1, H256::from_low_u64_be(1)))));
+        assert_eq!(tx.last::<CanonicalHeaders>(), Ok(Some((3, H256::from_low_u64_be(3)))));
+        tx.commit().expect(ERROR_COMMIT);
+    }
+
+    #[test]
+    fn db_cursor_cached_reuse() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in [1u64, 2, 3] {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        {
+            let mut cursor = tx.cursor_cached::<CanonicalHeaders>().unwrap();
+            assert_eq!(cursor.last(), Ok(Some((3, H256::from_low_u64_be(3)))));
+        }
+        assert_eq!(tx.cursor_cache.cursors.lock().len(), 1);
+
+        // the pooled cursor is reused and starts unpositioned
+        let mut cursor = tx.cursor_cached::<CanonicalHeaders>().unwrap();
+        assert!(tx.cursor_cache.cursors.lock().is_empty());
+        assert_eq!(cursor.current(), Ok(None));
+        assert_eq!(cursor.next(), Ok(Some((1, H256::from_low_u64_be(1)))));
+
+        // a second cached cursor on the same table is opened fresh
+        let mut other = tx.cursor_cached::<CanonicalHeaders>().unwrap();
+        assert_eq!(other.seek_exact(2), Ok(Some((2, H256::from_low_u64_be(2)))));
+        drop(cursor);
+        drop(other);
+        assert_eq!(tx.cursor_cache.cursors.lock().len(), 2);
+
+        tx.commit().expect(ERROR_COMMIT);
+    }
+
+    #[test]
+    fn db_cursor_current_after_seek() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in [1u64, 3, 5] {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+
+        // cursor is not positioned yet
+        assert_eq!(cursor.current(), Ok(None));
+
+        // seek lands on the next greater key and `current` does not advance the cursor
+        assert_eq!(cursor.seek(2), Ok(Some((3, H256::from_low_u64_be(3)))));
+        assert_eq!(cursor.current(), Ok(Some((3, H256::from_low_u64_be(3)))));
+        assert_eq!(cursor.current(), Ok(Some((3, H256::from_low_u64_be(3)))));
+        assert_eq!(cursor.next(), Ok(Some((5, H256::from_low_u64_be(5)))));
+    }
+
+    #[test]
+    fn db_cursor_seek_floor() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        // Empty table
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        assert_eq!(tx.cursor_read::<CanonicalHeaders>().unwrap().seek_floor(1), Ok(None));
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in [3u64, 5, 7] {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+
+        // all keys exceed the target
+        assert_eq!(cursor.seek_floor(2), Ok(None));
+        // exact match
+        assert_eq!(cursor.seek_floor(5), Ok(Some((5, H256::from_low_u64_be(5)))));
+        // between keys
+        assert_eq!(cursor.seek_floor(6), Ok(Some((5, H256::from_low_u64_be(5)))));
+        assert_eq!(cursor.next(), Ok(Some((7, H256::from_low_u64_be(7)))));
+        // beyond the last key
+        assert_eq!(cursor.seek_floor(10), Ok(Some((7, H256::from_low_u64_be(7)))));
+    }
+
+    #[test]
+    fn db_cursor_seek_nth() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        // Empty table
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        assert_eq!(tx.cursor_read::<CanonicalHeaders>().unwrap().seek_nth(0), Ok(None));
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in [3u64, 5, 7, 9] {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+
+        assert_eq!(cursor.seek_nth(0), Ok(Some((3, H256::from_low_u64_be(3)))));
+        assert_eq!(cursor.seek_nth(2), Ok(Some((7, H256::from_low_u64_be(7)))));
+        // the cursor is positioned at the entry
+        assert_eq!(cursor.next(), Ok(Some((9, H256::from_low_u64_be(9)))));
+        // seeking restarts from the first entry
+        assert_eq!(cursor.seek_nth(1), Ok(Some((5, H256::from_low_u64_be(5)))));
+        assert_eq!(cursor.seek_nth(3), Ok(Some((9, H256::from_low_u64_be(9)))));
+        // out of range
+        assert_eq!(cursor.seek_nth(4), Ok(None));
+        assert_eq!(cursor.seek_nth(u64::MAX), Ok(None));
+    }
+
+    #[test]
+    fn db_cursor_walk_limited() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in 0..10u64 {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+
+        // stops at the limit while more entries remain
+        let mut walker = cursor.walk_limited(Some(2), 3).unwrap();
+        let keys = walker.by_ref().map(|entry| entry.unwrap().0).collect::<Vec<_>>();
+        assert_eq!(keys, vec![2, 3, 4]);
+        assert!(walker.has_more());
+
+        // limit exactly covers the remaining entries
+        let mut walker = cursor.walk_limited(Some(7), 3).unwrap();
+        assert_eq!(walker.by_ref().count(), 3);
+        assert!(!walker.has_more());
+
+        // table ends before the limit is reached
+        let mut walker = cursor.walk_limited(None, 20).unwrap();
+        assert_eq!(walker.by_ref().count(), 10);
+        assert!(!walker.has_more());
+    }
+
+    #[cfg(feature = "json-export")]
+    #[test]
+    fn db_export_table_ndjson() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in [1u64, 2, 3] {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let mut out = Vec::new();
+        assert_eq!(env.export_table_ndjson::<CanonicalHeaders, _>(&mut out).unwrap(), 3);
+
+        let entries = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+                (
+                    serde_json::from_value::<u64>(entry["key"].clone()).unwrap(),
+                    serde_json::from_value::<H256>(entry["value"].clone()).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(entries, [1u64, 2, 3].map(|key| (key, H256::from_low_u64_be(key))).to_vec());
+    }
+
+    #[test]
+    fn db_upsert_sorted() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        tx.put::<CanonicalHeaders>(2, H256::zero()).expect(ERROR_PUT);
+        tx.upsert_sorted::<CanonicalHeaders>(
+            [1u64, 2, 3].map(|key| (key, H256::from_low_u64_be(key))),
+        )
+        .unwrap();
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let entries = tx
+            .cursor_read::<CanonicalHeaders>()
+            .unwrap()
+            .walk(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(entries, [1u64, 2, 3].map(|key| (key, H256::from_low_u64_be(key))).to_vec());
+        tx.commit().expect(ERROR_COMMIT);
+
+        // out of order input errors at the first unsorted key
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        let res = tx.upsert_sorted::<CanonicalHeaders>(
+            [5u64, 4, 6].map(|key| (key, H256::from_low_u64_be(key))),
+        );
+        assert_eq!(
+            res,
+            Err(DatabaseError::UnsortedEntries {
+                table_name: CanonicalHeaders::NAME,
+                key: 4u64.encode().as_ref().into()
+            })
+        );
+    }
+
+    #[test]
+    fn db_bulk_load() {
+        let path = TempDir::new().expect(ERROR_TEMPDIR).into_path();
+        let env = create_test_db_with_path::<WriteMap>(EnvKind::RW, &path);
+
+        let entries = (0..1_000u64).map(|key| (key, H256::from_low_u64_be(key)));
+        assert_eq!(env.bulk_load::<CanonicalHeaders>(entries.clone()), Ok(1_000));
+
+        // unsorted entries error without writing anything
+        let unsorted = [2u64, 1].map(|key| (Address::from_low_u64_be(key), Account::default()));
+        assert!(env.bulk_load::<PlainAccountState>(unsorted).is_err());
+
+        drop(env);
+        let env = create_test_db_with_path::<WriteMap>(EnvKind::RW, &path);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let loaded = tx
+            .cursor_read::<CanonicalHeaders>()
+            .unwrap()
+            .walk(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(loaded, entries.clone().collect::<Vec<_>>());
+        assert_eq!(tx.entries::<PlainAccountState>(), Ok(0));
+        tx.commit().expect(ERROR_COMMIT);
+
+        // the table must be empty
+        assert_eq!(
+            env.bulk_load::<CanonicalHeaders>(entries),
+            Err(DatabaseError::TableNotEmpty { table_name: CanonicalHeaders::NAME })
+        );
+    }
+
+    #[test]
+    fn db_long_lived_read_tx() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+        env.update(|tx| tx.put::<CanonicalHeaders>(1, H256::from_low_u64_be(1)))
+            .unwrap()
+            .expect(ERROR_PUT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        assert_eq!(tx.get::<CanonicalHeaders>(1), Ok(Some(H256::from_low_u64_be(1))));
+
+        // writes committed while the transaction is open are not visible to it
+        env.update(|tx| tx.put::<CanonicalHeaders>(2, H256::from_low_u64_be(2)))
+            .unwrap()
+            .expect(ERROR_PUT);
+        assert_eq!(tx.get::<CanonicalHeaders>(2), Ok(None));
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(1));
+
+        drop(tx);
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        assert_eq!(tx.get::<CanonicalHeaders>(2), Ok(Some(H256::from_low_u64_be(2))));
+    }
+
+    #[test]
+    fn db_reader_maintenance() {
+        let env = create_test_db::<WriteMap>(EnvKind::RW);
+        let maintenance = env.spawn_reader_maintenance(Duration::from_millis(1));
+
+        // churn the database while the maintenance task is running
+        for round in 0..10u64 {
+            let tx = env.tx_mut().expect(ERROR_INIT_TX);
+            for key in 0..1_000u64 {
+                tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key + round))
+                    .expect(ERROR_PUT);
+            }
+            for key in 0..500u64 {
+                tx.delete::<CanonicalHeaders>(key, None).expect(ERROR_DEL);
+            }
+            tx.commit().expect(ERROR_COMMIT);
+        }
+
+        while maintenance.passes() == 0 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        maintenance.stop();
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let entries = tx
+            .cursor_read::<CanonicalHeaders>()
+            .unwrap()
+            .walk(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let expected = (500..1_000u64).map(|key| (key, H256::from_low_u64_be(key + 9)));
+        assert_eq!(entries, expected.collect::<Vec<_>>());
+
+        // the environment is no longer shared with the task
+        assert_eq!(Arc::strong_count(&env), 1);
+    }
+
+    #[test]
+    fn db_freelist_info() {
+        let env = create_test_db::<WriteMap>(EnvKind::RW);
+
+        let entries = (0..100_000u64).map(|key| (key, H256::from_low_u64_be(key)));
+        env.bulk_load::<CanonicalHeaders>(entries).unwrap();
+
+        let baseline = env.freelist_info().unwrap();
+        assert!(baseline.free_pages < baseline.total_pages);
+        assert_eq!(baseline.total_bytes(), baseline.total_pages * baseline.page_size as u64);
+
+        // delete most of the table
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in 0..90_000u64 {
+            tx.delete::<CanonicalHeaders>(key, None).unwrap();
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let info = env.freelist_info().unwrap();
+        assert!(info.free_pages > baseline.free_pages);
+        assert!(info.free_bytes() > baseline.free_bytes());
+        // deleting entries never shrinks the data file
+        assert!(info.total_pages >= baseline.total_pages);
+    }
+
+    #[test]
+    fn db_env_info() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        let info = env.env_info().unwrap();
+        assert_eq!(info.map_size, 4 * TERABYTE as u64);
+        assert_eq!(info.geometry.max_size, 4 * TERABYTE as u64);
+        assert!(info.geometry.current_size <= info.geometry.max_size);
+        assert!(info.max_readers >= DEFAULT_MAX_READERS);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        tx.put::<CanonicalHeaders>(1, H256::zero()).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+
+        let after_commit = env.env_info().unwrap();
+        assert!(after_commit.last_txn_id > info.last_txn_id);
+        assert_eq!(after_commit.map_size, info.map_size);
+    }
+
+    #[test]
+    fn db_for_each_table() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+        let address = Address::from_low_u64_be(1);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for block in 0..3 {
+            tx.put::<CanonicalHeaders>(block, H256::from_low_u64_be(block)).expect(ERROR_PUT);
+        }
+        tx.put::<PlainAccountState>(address, Account::default()).expect(ERROR_PUT);
+        for slot in 0..4 {
+            let entry = StorageEntry { key: H256::from_low_u64_be(slot), value: U256::from(slot) };
+            tx.put::<PlainStorageState>(address, entry).expect(ERROR_PUT);
         }
+        tx.commit().expect(ERROR_COMMIT);
 
-        let env =
-            Env { inner: inner_env.open(path).map_err(|e| DatabaseError::FailedToOpen(e.into()))? };
+        let mut total = 0;
+        let mut visited = 0;
+        env.for_each_table(|name, cursor| {
+            visited += 1;
+            let mut entries = 0;
+            while let Some((key, _)) = cursor.next_raw().unwrap() {
+                if name == CanonicalHeaders::NAME {
+                    assert_eq!(key, (entries as u64).encode().as_ref());
+                }
+                entries += 1;
+            }
+            total += entries;
+        })
+        .unwrap();
 
-        Ok(env)
+        assert_eq!(visited, Tables::ALL.len());
+        // every duplicate value is yielded as its own entry
+        assert_eq!(total, 3 + 1 + 4);
     }
 
-    /// Creates all the defined tables, if necessary.
-    pub fn create_tables(&self) -> Result<(), DatabaseError> {
-        let tx = self.inner.begin_rw_txn().map_err(|e| DatabaseError::InitTransaction(e.into()))?;
+    #[test]
+    fn db_savepoint() {
+        let env = create_test_db::<WriteMap>(EnvKind::RW);
+        let address = Address::from_low_u64_be(1);
+        let slot =
+            |key: u64| StorageEntry { key: H256::from_low_u64_be(key), value: U256::from(1) };
 
-        for table in Tables::ALL {
-            let flags = match table.table_type() {
-                TableType::Table => DatabaseFlags::default(),
-                TableType::DupSort => DatabaseFlags::DUP_SORT,
-            };
+        // write A
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        tx.put::<CanonicalHeaders>(1, H256::from_low_u64_be(1)).expect(ERROR_PUT);
+        tx.put::<PlainStorageState>(address, slot(1)).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
 
-            tx.create_db(Some(table.name()), flags)
-                .map_err(|e| DatabaseError::TableCreation(e.into()))?;
-        }
+        let savepoint = env.create_savepoint().unwrap();
+        assert_eq!(savepoint.txn_id(), env.info().unwrap().last_txnid() as u64);
 
-        tx.commit().map_err(|e| DatabaseError::Commit(e.into()))?;
+        // write B
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        tx.put::<CanonicalHeaders>(2, H256::from_low_u64_be(2)).expect(ERROR_PUT);
+        tx.put::<PlainStorageState>(address, slot(2)).expect(ERROR_PUT);
+        tx.delete::<CanonicalHeaders>(1, None).expect(ERROR_DEL);
+        tx.commit().expect(ERROR_COMMIT);
 
-        Ok(())
+        env.restore_savepoint(&savepoint).unwrap();
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let headers = tx
+            .cursor_read::<CanonicalHeaders>()
+            .unwrap()
+            .walk(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(headers, vec![(1, H256::from_low_u64_be(1))]);
+        let mut cursor = tx.cursor_dup_read::<PlainStorageState>().unwrap();
+        assert_eq!(cursor.dup_values(address), Ok(vec![slot(1)]));
     }
-}
 
-impl<E: EnvironmentKind> Deref for Env<E> {
-    type Target = Environment<E>;
+    /// Codec that stores the bytes of a hash in reverse order.
+    #[derive(Debug)]
+    struct ReversedCodec;
 
-    fn deref(&self) -> &Self::Target {
-        &self.inner
+    impl CompressionCodec<H256> for ReversedCodec {
+        fn compress_to_buf(&self, value: H256, buf: &mut Vec<u8>) {
+            buf.extend(value.as_bytes().iter().rev())
+        }
+
+        fn decompress(&self, value: &[u8]) -> Result<H256, DatabaseError> {
+            let mut bytes = value.to_vec();
+            bytes.reverse();
+            H256::decompress(bytes)
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        abstraction::table::{Encode, Table},
-        cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO, DbDupCursorRW, ReverseWalker, Walker},
-        database::Database,
-        models::{AccountBeforeTx, ShardedKey},
-        tables::{AccountHistory, CanonicalHeaders, Headers, PlainAccountState, PlainStorageState},
-        test_utils::*,
-        transaction::{DbTx, DbTxMut},
-        AccountChangeSet, DatabaseError,
-    };
-    use reth_interfaces::db::DatabaseWriteOperation;
-    use reth_libmdbx::{NoWriteMap, WriteMap};
-    use reth_primitives::{Account, Address, Header, IntegerList, StorageEntry, H160, H256, U256};
-    use std::{path::Path, str::FromStr, sync::Arc};
-    use tempfile::TempDir;
+    #[test]
+    fn db_recompress_table() {
+        let env = create_test_db::<WriteMap>(EnvKind::RW);
+        // spans multiple batches
+        let entries =
+            (0..25_000u64).map(|key| (key, H256::from_low_u64_be(key))).collect::<Vec<_>>();
+        env.bulk_load::<CanonicalHeaders>(entries.clone()).unwrap();
+
+        let read_back = || {
+            let tx = env.tx().expect(ERROR_INIT_TX);
+            let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+            cursor.walk(None).unwrap().collect::<Result<Vec<_>, _>>().unwrap()
+        };
+        let progress = || {
+            let tx = env.tx().expect(ERROR_INIT_TX);
+            tx.get::<RecompressProgress>(CanonicalHeaders::NAME.to_string()).unwrap()
+        };
 
-    /// Create database for testing
-    fn create_test_db<E: EnvironmentKind>(kind: EnvKind) -> Arc<Env<E>> {
-        Arc::new(create_test_db_with_path(
-            kind,
-            &tempfile::TempDir::new().expect(ERROR_TEMPDIR).into_path(),
-        ))
+        // switch to the reversed encoding
+        assert_eq!(
+            env.recompress_table::<CanonicalHeaders>(DefaultCodec, ReversedCodec),
+            Ok(25_000)
+        );
+        assert_eq!(progress(), None);
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_read::<RawTable<CanonicalHeaders>>().unwrap();
+        for ((key, value), entry) in entries.iter().zip(cursor.walk(None).unwrap()) {
+            let (raw_key, raw_value) = entry.unwrap();
+            assert_eq!(raw_key.key(), Ok(*key));
+            let mut reversed = value.0;
+            reversed.reverse();
+            assert_eq!(raw_value.raw_value(), &reversed.to_vec());
+        }
+        drop(cursor);
+        drop(tx);
+
+        // an interrupted pass back to the default encoding resumes at the committed progress
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        let next_key = RawKey::new(20_000u64).raw_key().clone();
+        tx.put::<RecompressProgress>(CanonicalHeaders::NAME.to_string(), next_key).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(
+            env.recompress_table::<CanonicalHeaders>(ReversedCodec, DefaultCodec),
+            Ok(5_000)
+        );
+        assert_eq!(progress(), None);
+        let headers = read_back();
+        assert_eq!(headers[20_000..], entries[20_000..]);
+        // the entries before the progress were skipped and still use the reversed encoding
+        let mut reversed = entries[1].1;
+        reversed.0.reverse();
+        assert_eq!(headers[1], (1, reversed));
+
+        assert_eq!(env.recompress_table::<Headers>(DefaultCodec, DefaultCodec), Ok(0));
+        assert_eq!(
+            env.recompress_table::<PlainStorageState>(DefaultCodec, DefaultCodec),
+            Err(DatabaseError::DupSortUnsupported { table_name: PlainStorageState::NAME })
+        );
     }
 
-    /// Create database for testing with specified path
-    fn create_test_db_with_path<E: EnvironmentKind>(kind: EnvKind, path: &Path) -> Env<E> {
-        let env = Env::<E>::open(path, kind, None).expect(ERROR_DB_CREATION);
-        env.create_tables().expect(ERROR_TABLE_CREATION);
-        env
+    #[test]
+    fn db_backup_to() {
+        let env = create_test_db::<WriteMap>(EnvKind::RW);
+        let entries = (0..100_000u64).map(|key| (key, H256::from_low_u64_be(key)));
+        env.bulk_load::<CanonicalHeaders>(entries).unwrap();
+
+        // cancel after the first chunk
+        let dest = TempDir::new().expect(ERROR_TEMPDIR).into_path();
+        let mut calls = 0;
+        let completed = env
+            .backup_to(&dest, |copied, total| {
+                calls += 1;
+                assert!(copied <= total);
+                ControlFlow::Break(())
+            })
+            .unwrap();
+        assert!(!completed);
+        assert_eq!(calls, 1);
+        assert!(!dest.join(DATA_FILE_NAME).exists());
+
+        // the source is intact and still writable
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(100_000));
+        tx.put::<CanonicalHeaders>(100_000, H256::zero()).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+
+        let mut last = 0;
+        let completed = env
+            .backup_to(&dest, |copied, _| {
+                assert!(copied > last);
+                last = copied;
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert!(completed);
+        assert_eq!(std::fs::metadata(dest.join(DATA_FILE_NAME)).unwrap().len(), last);
+
+        let backup = Env::<WriteMap>::open(&dest, EnvKind::RO, None).expect(ERROR_DB_CREATION);
+        let tx = backup.tx().expect(ERROR_INIT_TX);
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(100_001));
     }
 
-    const ERROR_DB_CREATION: &str = "Not able to create the mdbx file.";
-    const ERROR_PUT: &str = "Not able to insert value into table.";
-    const ERROR_APPEND: &str = "Not able to append the value to the table.";
-    const ERROR_UPSERT: &str = "Not able to upsert the value to the table.";
-    const ERROR_GET: &str = "Not able to get value from table.";
-    const ERROR_COMMIT: &str = "Not able to commit transaction.";
-    const ERROR_RETURN_VALUE: &str = "Mismatching result.";
-    const ERROR_INIT_TX: &str = "Failed to create a MDBX transaction.";
-    const ERROR_ETH_ADDRESS: &str = "Invalid address.";
+    #[test]
+    fn db_tx_observer() {
+        #[derive(Debug, Default)]
+        struct Recorder {
+            commits: parking_lot::Mutex<Vec<u64>>,
+            aborts: AtomicU64,
+        }
+
+        impl TxObserver for Recorder {
+            fn on_commit(&self, txn_id: u64) {
+                self.commits.lock().push(txn_id);
+            }
+
+            fn on_abort(&self) {
+                self.aborts.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let recorder = Arc::new(Recorder::default());
+        let path = TempDir::new().expect(ERROR_TEMPDIR).into_path();
+        let opts = DbOpts::default().with_observer(recorder.clone());
+        let env = Env::<WriteMap>::open_with_opts(&path, EnvKind::RW, opts).unwrap();
+        env.create_tables().expect(ERROR_TABLE_CREATION);
+        let committed = recorder.commits.lock().len();
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        let txn_id = tx.id();
+        tx.put::<CanonicalHeaders>(1, H256::zero()).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+        assert_eq!(recorder.commits.lock()[committed..], [txn_id]);
+        assert_eq!(recorder.aborts.load(Ordering::Relaxed), 0);
+
+        // rolled back explicitly and by dropping
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        tx.put::<CanonicalHeaders>(2, H256::zero()).expect(ERROR_PUT);
+        DbTx::drop(tx);
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        tx.put::<CanonicalHeaders>(3, H256::zero()).expect(ERROR_PUT);
+        drop(tx);
+        assert_eq!(recorder.commits.lock().len(), committed + 1);
+        assert_eq!(recorder.aborts.load(Ordering::Relaxed), 2);
+
+        // read transactions are not observed
+        env.tx().expect(ERROR_INIT_TX).commit().expect(ERROR_COMMIT);
+        assert_eq!(recorder.commits.lock().len(), committed + 1);
+    }
 
     #[test]
-    fn db_creation() {
-        create_test_db::<NoWriteMap>(EnvKind::RW);
+    fn db_page_size() {
+        let path = TempDir::new().expect(ERROR_TEMPDIR).into_path();
+
+        let opts = DbOpts::default().with_page_size(16384);
+        let env = Env::<WriteMap>::open_with_opts(&path, EnvKind::RW, opts).unwrap();
+        env.create_tables().expect(ERROR_TABLE_CREATION);
+        assert_eq!(env.freelist_info().unwrap().page_size, 16384);
+        drop(env);
+
+        // the page size of an existing database can't be changed
+        let opts = DbOpts::default().with_page_size(4096);
+        let env = Env::<WriteMap>::open_with_opts(&path, EnvKind::RW, opts).unwrap();
+        assert_eq!(env.freelist_info().unwrap().page_size, 16384);
+        drop(env);
+
+        for page_size in [3000, 128, 1 << 17] {
+            let path = TempDir::new().expect(ERROR_TEMPDIR).into_path();
+            let opts = DbOpts::default().with_page_size(page_size);
+            assert_eq!(
+                Env::<WriteMap>::open_with_opts(&path, EnvKind::RW, opts).err(),
+                Some(DatabaseError::InvalidPageSize(page_size))
+            );
+        }
     }
 
     #[test]
-    fn db_manual_put_get() {
+    fn db_entries_and_is_empty() {
         let env = create_test_db::<NoWriteMap>(EnvKind::RW);
 
-        let value = Header::default();
-        let key = 1u64;
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(0));
+        assert_eq!(tx.is_empty::<CanonicalHeaders>(), Ok(true));
+        tx.commit().expect(ERROR_COMMIT);
 
-        // PUT
         let tx = env.tx_mut().expect(ERROR_INIT_TX);
-        tx.put::<Headers>(key, value.clone()).expect(ERROR_PUT);
+        for key in 0..3u64 {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).expect(ERROR_PUT);
+        }
+        // three duplicates of the first key and a single value of the second
+        let (key1, key2) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        for (key, subkey) in [(key1, 1u64), (key1, 2), (key1, 3), (key2, 1)] {
+            let entry = StorageEntry { key: H256::from_low_u64_be(subkey), value: U256::from(1) };
+            tx.put::<PlainStorageState>(key, entry).expect(ERROR_PUT);
+        }
         tx.commit().expect(ERROR_COMMIT);
 
-        // GET
         let tx = env.tx().expect(ERROR_INIT_TX);
-        let result = tx.get::<Headers>(key).expect(ERROR_GET);
-        assert!(result.expect(ERROR_RETURN_VALUE) == value);
-        tx.commit().expect(ERROR_COMMIT);
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(3));
+        assert_eq!(tx.is_empty::<CanonicalHeaders>(), Ok(false));
+        assert_eq!(tx.entries::<PlainStorageState>(), Ok(4));
+        assert_eq!(tx.is_empty::<PlainStorageState>(), Ok(false));
+        assert_eq!(tx.is_empty::<PlainAccountState>(), Ok(true));
     }
 
     #[test]
@@ -247,6 +1811,86 @@ mod tests {
         assert_eq!(first.1, value, "First next should be put value");
     }
 
+    #[test]
+    fn db_cursor_walk_filter_values() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+        let address = Address::random();
+        let key = |block| ShardedKey::new(address, block);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        tx.put::<AccountHistory>(key(1), vec![1u64].into()).expect(ERROR_PUT);
+        tx.put::<AccountHistory>(key(3), vec![2u64, 3].into()).expect(ERROR_PUT);
+        tx.put::<AccountHistory>(key(5), vec![5u64].into()).expect(ERROR_PUT);
+        tx.put::<AccountHistory>(key(7), vec![6u64, 7].into()).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_read::<AccountHistory>().unwrap();
+        let keys = cursor
+            .walk(None)
+            .unwrap()
+            .filter_values(|list| list.len() > 1)
+            .map(|entry| entry.map(|(key, _)| key.highest_block_number))
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(keys, Ok(vec![3, 7]));
+        drop(cursor);
+        tx.commit().expect(ERROR_COMMIT);
+
+        // an entry that fails to decode is surfaced instead of being filtered out
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        let garbage = RawValue::<IntegerList>::decompress([0xffu8]).unwrap();
+        tx.put::<RawTable<AccountHistory>>(RawKey::new(key(4)), garbage).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_read::<AccountHistory>().unwrap();
+        let mut walker = cursor.walk(None).unwrap().filter_values(|list| list.len() > 1);
+        let mut next_key = || walker.next().map(|entry| entry.map(|(key, _)| key));
+        assert_eq!(next_key(), Some(Ok(key(3))));
+        assert_eq!(next_key(), Some(Err(DatabaseError::DecodeError)));
+    }
+
+    #[test]
+    fn db_cursor_walk_group_by() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+        let accounts = [1, 2, 3].map(Address::from_low_u64_be);
+        let shards = [vec![10u64, 20, 30], vec![5], vec![7, u64::MAX]];
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for (address, blocks) in accounts.iter().zip(&shards) {
+            for block in blocks {
+                let key = ShardedKey::new(*address, *block);
+                tx.put::<AccountHistory>(key, vec![*block].into()).expect(ERROR_PUT);
+            }
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_read::<AccountHistory>().unwrap();
+        let groups = cursor
+            .walk(None)
+            .unwrap()
+            .group_by(|key| key.key)
+            .map(|group| {
+                group.map(|(address, entries)| {
+                    let blocks = entries.into_iter().map(|(key, _)| key.highest_block_number);
+                    (address, blocks.collect::<Vec<_>>())
+                })
+            })
+            .collect::<Result<Vec<_>, _>>();
+        let expected = accounts.into_iter().zip(shards).collect::<Vec<_>>();
+        assert_eq!(groups, Ok(expected));
+
+        // grouping starts at the position of the walker
+        let groups = cursor
+            .walk(Some(ShardedKey::new(accounts[0], 20)))
+            .unwrap()
+            .group_by(|key| key.key)
+            .map(|group| group.map(|(address, entries)| (address, entries.len())))
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(groups, Ok(vec![(accounts[0], 2), (accounts[1], 1), (accounts[2], 2)]));
+    }
+
     #[test]
     fn db_cursor_walk_range() {
         let db: Arc<Env<WriteMap>> = create_test_db(EnvKind::RW);
@@ -566,6 +2210,62 @@ mod tests {
         assert!(dup_cursor.insert(key, entry2).is_err());
     }
 
+    #[test]
+    fn db_cursor_delete_current_while_walking() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for key in 0..10u64 {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        // delete every other entry
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_write::<CanonicalHeaders>().unwrap();
+        let mut walker = cursor.walk(None).unwrap();
+        while let Some((key, _)) = walker.next().transpose().unwrap() {
+            if key % 2 == 0 {
+                walker.delete_current().expect(ERROR_DEL);
+            }
+        }
+        drop(cursor);
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+        let keys = cursor.walk(None).unwrap().map(|res| res.unwrap().0).collect::<Vec<_>>();
+        assert_eq!(keys, vec![1, 3, 5, 7, 9]);
+    }
+
+    #[test]
+    fn db_cursor_delete_current_duplicates() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+        let key1 = H160([0x11; 20]);
+        let key2 = H160([0x22; 20]);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for i in 0..3u64 {
+            let entry = StorageEntry { key: H256::from_low_u64_be(i), value: U256::from(i) };
+            tx.put::<PlainStorageState>(key1, entry).expect(ERROR_PUT);
+            tx.put::<PlainStorageState>(key2, entry).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_dup_write::<PlainStorageState>().unwrap();
+        assert!(cursor.seek_exact(key1).unwrap().is_some());
+        cursor.delete_current_duplicates().expect(ERROR_DEL);
+        drop(cursor);
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_dup_read::<PlainStorageState>().unwrap();
+        assert_eq!(cursor.seek_exact(key1), Ok(None));
+        let keys = cursor.walk(None).unwrap().map(|res| res.unwrap().0).collect::<Vec<_>>();
+        assert_eq!(keys, vec![key2; 3]);
+    }
+
     #[test]
     fn db_cursor_delete_current_non_existent() {
         let db: Arc<Env<WriteMap>> = create_test_db(EnvKind::RW);
@@ -813,6 +2513,100 @@ mod tests {
         assert!(result == Some(value))
     }
 
+    #[test]
+    fn db_dup_values() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+        let key = Address::from_low_u64_be(1);
+        let next_key = Address::from_low_u64_be(2);
+        let absent = Address::from_low_u64_be(3);
+
+        let values = (0..5u64)
+            .rev()
+            .map(|slot| StorageEntry { key: H256::from_low_u64_be(slot), value: U256::from(slot) })
+            .collect::<Vec<_>>();
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for value in &values {
+            tx.put::<PlainStorageState>(key, *value).expect(ERROR_PUT);
+        }
+        tx.put::<PlainStorageState>(next_key, StorageEntry::default()).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_dup_read::<PlainStorageState>().unwrap();
+        // sorted by subkey, without the values of the next key
+        let expected = values.into_iter().rev().collect::<Vec<_>>();
+        assert_eq!(cursor.dup_values(key), Ok(expected));
+        assert_eq!(cursor.dup_values(absent), Ok(vec![]));
+        assert_eq!(cursor.dup_values(next_key), Ok(vec![StorageEntry::default()]));
+    }
+
+    #[test]
+    fn db_dup_count() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+        let key = Address::from_low_u64_be(1);
+        let single = Address::from_low_u64_be(2);
+        let absent = Address::from_low_u64_be(3);
+
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for slot in 0..5 {
+            let entry = StorageEntry { key: H256::from_low_u64_be(slot), value: U256::from(slot) };
+            tx.put::<PlainStorageState>(key, entry).expect(ERROR_PUT);
+        }
+        let single_value = StorageEntry { key: H256::from_low_u64_be(7), value: U256::from(7) };
+        tx.put::<PlainStorageState>(single, single_value).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_dup_read::<PlainStorageState>().unwrap();
+        assert_eq!(cursor.dup_count(key), Ok(5));
+        assert_eq!(cursor.dup_count(single), Ok(1));
+        assert_eq!(cursor.dup_count(absent), Ok(0));
+
+        // the cursor is left at the first duplicate of the key
+        assert_eq!(cursor.dup_count(key), Ok(5));
+        let first = StorageEntry { key: H256::from_low_u64_be(0), value: U256::ZERO };
+        assert_eq!(cursor.current(), Ok(Some((key, first))));
+    }
+
+    #[test]
+    fn db_last_dup() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+        let key = Address::from_low_u64_be(1);
+        let single = Address::from_low_u64_be(2);
+        let absent = Address::from_low_u64_be(3);
+
+        let values = [3u64, 0, 4, 1, 2]
+            .into_iter()
+            .map(|slot| StorageEntry { key: H256::from_low_u64_be(slot), value: U256::from(slot) })
+            .collect::<Vec<_>>();
+        let single_value = StorageEntry { key: H256::from_low_u64_be(7), value: U256::from(7) };
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for value in &values {
+            tx.put::<PlainStorageState>(key, *value).expect(ERROR_PUT);
+        }
+        tx.put::<PlainStorageState>(single, single_value).expect(ERROR_PUT);
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_dup_read::<PlainStorageState>().unwrap();
+        let max = values.iter().max_by_key(|entry| entry.key).copied();
+        assert_eq!(cursor.last_dup(key), Ok(max));
+        assert_eq!(cursor.last_dup(single), Ok(Some(single_value)));
+        assert_eq!(cursor.last_dup(absent), Ok(None));
+
+        // duplicates of the key in descending subkey order
+        let walked =
+            cursor.walk_dup_back(key).unwrap().collect::<Result<Vec<_>, _>>().expect(ERROR_GET);
+        let mut expected = values.into_iter().map(|value| (key, value)).collect::<Vec<_>>();
+        expected.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.key));
+        assert_eq!(walked, expected);
+
+        let walked =
+            cursor.walk_dup_back(single).unwrap().collect::<Result<Vec<_>, _>>().expect(ERROR_GET);
+        assert_eq!(walked, vec![(single, single_value)]);
+        assert_eq!(cursor.walk_dup_back(absent).unwrap().count(), 0);
+    }
+
     #[test]
     fn db_dup_sort() {
         let env = create_test_db::<NoWriteMap>(EnvKind::RW);
@@ -903,6 +2697,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn db_walk_all_dup() {
+        let env = create_test_db::<NoWriteMap>(EnvKind::RW);
+        let key1 = Address::from_low_u64_be(1);
+        let key2 = Address::from_low_u64_be(2);
+        let key3 = Address::from_low_u64_be(3);
+        let entry = |subkey: u64| StorageEntry {
+            key: H256::from_low_u64_be(subkey),
+            value: U256::from(subkey),
+        };
+
+        // insert the duplicates out of order, key2 has a single value
+        let tx = env.tx_mut().expect(ERROR_INIT_TX);
+        for (key, subkey) in [(key3, 5), (key1, 2), (key2, 3), (key1, 0), (key3, 4), (key1, 1)] {
+            tx.put::<PlainStorageState>(key, entry(subkey)).expect(ERROR_PUT);
+        }
+        tx.commit().expect(ERROR_COMMIT);
+
+        let tx = env.tx().expect(ERROR_INIT_TX);
+        let mut cursor = tx.cursor_dup_read::<PlainStorageState>().unwrap();
+
+        let all = cursor.walk_all_dup(None).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        let expected = [(key1, 0), (key1, 1), (key1, 2), (key2, 3), (key3, 4), (key3, 5)]
+            .into_iter()
+            .map(|(key, subkey)| (key, entry(subkey)))
+            .collect::<Vec<_>>();
+        assert_eq!(all, expected);
+
+        let from_key2 =
+            cursor.walk_all_dup(Some(key2)).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(from_key2, expected[3..]);
+
+        // starts at the next key if the key does not exist
+        let from_missing = cursor
+            .walk_all_dup(Some(Address::from_low_u64_be(4)))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert!(from_missing.is_empty());
+    }
+
     #[test]
     fn dup_value_with_same_subkey() {
         let env = create_test_db::<NoWriteMap>(EnvKind::RW);
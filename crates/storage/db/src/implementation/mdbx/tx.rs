@@ -1,34 +1,129 @@
 //! Transaction wrapper for libmdbx-sys.
 
-use super::cursor::Cursor;
+use super::{cursor::Cursor, TxObserver};
 use crate::{
     table::{Compress, DupSort, Encode, Table, TableImporter},
     tables::{utils::decode_one, Tables, NUM_TABLES},
     transaction::{DbTx, DbTxGAT, DbTxMut, DbTxMutGAT},
     DatabaseError,
 };
-use parking_lot::RwLock;
+use bytes::Bytes;
+use parking_lot::{Mutex, RwLock};
 use reth_interfaces::db::DatabaseWriteOperation;
-use reth_libmdbx::{ffi::DBI, EnvironmentKind, Transaction, TransactionKind, WriteFlags, RW};
+use reth_libmdbx::{
+    ffi::{self, DBI},
+    EnvironmentKind, Transaction, TransactionKind, WriteFlags, RW,
+};
 use reth_metrics::metrics::histogram;
-use std::{marker::PhantomData, str::FromStr, sync::Arc, time::Instant};
+use std::{marker::PhantomData, mem::ManuallyDrop, str::FromStr, sync::Arc, time::Instant};
 
 /// Wrapper for the libmdbx transaction.
 #[derive(Debug)]
 pub struct Tx<'a, K: TransactionKind, E: EnvironmentKind> {
+    /// Cursors released by [DbTx::cursor_cached] handles, ready to be reused.
+    ///
+    /// Declared first so pooled cursors are closed before the transaction is dropped.
+    pub(crate) cursor_cache: Arc<CursorCache>,
     /// Libmdbx-sys transaction.
     pub inner: Transaction<'a, K, E>,
     /// Database table handle cache
     pub db_handles: Arc<RwLock<[Option<DBI>; NUM_TABLES]>>,
+    /// Notifies the observer of the environment about the outcome of a write transaction.
+    ///
+    /// Declared after the transaction so an abort is only reported once the transaction is
+    /// dropped.
+    observer: Option<ObserverHandle>,
+}
+
+/// Reports the outcome of a write transaction to a [TxObserver].
+///
+/// The transaction is reported as aborted when the handle is dropped without being committed.
+#[derive(Debug)]
+struct ObserverHandle(Option<Arc<dyn TxObserver>>);
+
+impl ObserverHandle {
+    /// Reports the transaction with the given id as committed.
+    fn committed(mut self, txn_id: u64) {
+        if let Some(observer) = self.0.take() {
+            observer.on_commit(txn_id)
+        }
+    }
+}
+
+impl Drop for ObserverHandle {
+    fn drop(&mut self) {
+        if let Some(observer) = self.0.take() {
+            observer.on_abort()
+        }
+    }
+}
+
+/// Pool of cursors that were released back to their transaction.
+///
+/// Cursors are stored as raw handles and are renewed before they are handed out again, which
+/// resets their position. Any cursors left in the pool are closed when the pool is dropped.
+#[derive(Debug, Default)]
+pub struct CursorCache {
+    pub(crate) cursors: Mutex<Vec<(DBI, RawCursor)>>,
 }
 
+impl CursorCache {
+    /// Takes a released cursor for the given table, if any.
+    fn take(&self, dbi: DBI) -> Option<*mut ffi::MDBX_cursor> {
+        let mut cursors = self.cursors.lock();
+        let idx = cursors.iter().position(|(cursor_dbi, _)| *cursor_dbi == dbi)?;
+        Some(cursors.swap_remove(idx).1 .0)
+    }
+
+    /// Returns a cursor to the pool.
+    pub(crate) fn release<K: TransactionKind>(
+        &self,
+        dbi: DBI,
+        cursor: reth_libmdbx::Cursor<'_, K>,
+    ) {
+        self.cursors.lock().push((dbi, RawCursor(cursor.into_raw())));
+    }
+
+    /// Closes all pooled cursors.
+    fn clear(&self) {
+        for (_, cursor) in self.cursors.lock().drain(..) {
+            unsafe { ffi::mdbx_cursor_close(cursor.0) }
+        }
+    }
+}
+
+impl Drop for CursorCache {
+    fn drop(&mut self) {
+        self.clear()
+    }
+}
+
+/// Raw handle of a pooled cursor.
+#[derive(Debug)]
+pub(crate) struct RawCursor(*mut ffi::MDBX_cursor);
+
+// SAFETY: pooled cursors are not in use and are only handed out to one cursor at a time.
+unsafe impl Send for RawCursor {}
+unsafe impl Sync for RawCursor {}
+
 impl<'env, K: TransactionKind, E: EnvironmentKind> Tx<'env, K, E> {
     /// Creates new `Tx` object with a `RO` or `RW` transaction.
     pub fn new<'a>(inner: Transaction<'a, K, E>) -> Self
     where
         'a: 'env,
     {
-        Self { inner, db_handles: Default::default() }
+        Self {
+            cursor_cache: Default::default(),
+            inner,
+            db_handles: Default::default(),
+            observer: None,
+        }
+    }
+
+    /// Sets the observer that is notified about the outcome of this transaction.
+    pub(crate) fn with_observer(mut self, observer: Option<Arc<dyn TxObserver>>) -> Self {
+        self.observer = observer.map(|observer| ObserverHandle(Some(observer)));
+        self
     }
 
     /// Gets this transaction ID.
@@ -40,7 +135,14 @@ impl<'env, K: TransactionKind, E: EnvironmentKind> Tx<'env, K, E> {
     pub fn get_dbi<T: Table>(&self) -> Result<DBI, DatabaseError> {
         let mut handles = self.db_handles.write();
 
-        let table = Tables::from_str(T::NAME).expect("Requested table should be part of `Tables`.");
+        let Ok(table) = Tables::from_str(T::NAME) else {
+            // handles of auxiliary tables are not cached
+            return self
+                .inner
+                .open_db(Some(T::NAME))
+                .map(|db| db.dbi())
+                .map_err(|e| DatabaseError::InitCursor(e.into()))
+        };
 
         let dbi_handle = handles.get_mut(table as usize).expect("should exist");
         if dbi_handle.is_none() {
@@ -58,13 +160,42 @@ impl<'env, K: TransactionKind, E: EnvironmentKind> Tx<'env, K, E> {
     /// Create db Cursor
     pub fn new_cursor<T: Table>(&self) -> Result<Cursor<'env, K, T>, DatabaseError> {
         Ok(Cursor {
-            inner: self
-                .inner
-                .cursor_with_dbi(self.get_dbi::<T>()?)
-                .map_err(|e| DatabaseError::InitCursor(e.into()))?,
+            inner: ManuallyDrop::new(
+                self.inner
+                    .cursor_with_dbi(self.get_dbi::<T>()?)
+                    .map_err(|e| DatabaseError::InitCursor(e.into()))?,
+            ),
             table: T::NAME,
             _dbi: PhantomData,
             buf: vec![],
+            cache: None,
+        })
+    }
+
+    /// Create db Cursor that reuses a pooled cursor if one is available and returns it to the
+    /// pool on drop.
+    pub fn new_cached_cursor<T: Table>(&self) -> Result<Cursor<'env, K, T>, DatabaseError> {
+        let dbi = self.get_dbi::<T>()?;
+        let inner = match self.cursor_cache.take(dbi) {
+            // SAFETY: the cursor was released by a cursor of this transaction and is not
+            // referenced anywhere else.
+            Some(raw) => match unsafe { reth_libmdbx::Cursor::from_raw(&self.inner, raw) } {
+                Ok(cursor) => cursor,
+                Err(e) => {
+                    unsafe { ffi::mdbx_cursor_close(raw) };
+                    return Err(DatabaseError::InitCursor(e.into()))
+                }
+            },
+            None => {
+                self.inner.cursor_with_dbi(dbi).map_err(|e| DatabaseError::InitCursor(e.into()))?
+            }
+        };
+        Ok(Cursor {
+            inner: ManuallyDrop::new(inner),
+            table: T::NAME,
+            _dbi: PhantomData,
+            buf: vec![],
+            cache: Some((dbi, self.cursor_cache.clone())),
         })
     }
 }
@@ -90,14 +221,30 @@ impl<'tx, K: TransactionKind, E: EnvironmentKind> DbTx<'tx> for Tx<'tx, K, E> {
             .transpose()
     }
 
+    fn get_raw<T: Table>(&self, key: T::Key) -> Result<Option<Bytes>, DatabaseError> {
+        Ok(self
+            .inner
+            .get::<Vec<u8>>(self.get_dbi::<T>()?, key.encode().as_ref())
+            .map_err(|e| DatabaseError::Read(e.into()))?
+            .map(Bytes::from))
+    }
+
     fn commit(self) -> Result<bool, DatabaseError> {
+        // pooled cursors must not outlive the transaction
+        self.cursor_cache.clear();
+        let txn_id = self.observer.as_ref().map(|_| self.inner.id());
         let start = Instant::now();
         let result = self.inner.commit().map_err(|e| DatabaseError::Commit(e.into()));
         histogram!("tx.commit", start.elapsed());
+        // a failed commit is reported as abort when the handle is dropped
+        if let (Some(observer), Some(txn_id), Ok(_)) = (self.observer, txn_id, &result) {
+            observer.committed(txn_id);
+        }
         result
     }
 
     fn drop(self) {
+        self.cursor_cache.clear();
         drop(self.inner)
     }
 
@@ -106,6 +253,10 @@ impl<'tx, K: TransactionKind, E: EnvironmentKind> DbTx<'tx> for Tx<'tx, K, E> {
         self.new_cursor()
     }
 
+    fn cursor_cached<T: Table>(&self) -> Result<<Self as DbTxGAT<'_>>::Cursor<T>, DatabaseError> {
+        self.new_cached_cursor()
+    }
+
     /// Iterate over read only values in database.
     fn cursor_dup_read<T: DupSort>(
         &self,
@@ -0,0 +1,1140 @@
+//! In-memory database backed by [BTreeMap]s, for tests and as the write layer of an overlay.
+
+use crate::{
+    common::{PairResult, ValueOnlyResult},
+    cursor::{
+        DbCursorRO, DbCursorRW, DbDupCursorRO, DbDupCursorRW, DupWalker, RangeWalker,
+        ReverseWalker, Walker,
+    },
+    database::{Database, DatabaseGAT},
+    table::{Compress, DupSort, Encode, Table, TableImporter, TableRow},
+    tables::{
+        utils::{decode_one, decoder},
+        TableDef, TableType, Tables,
+    },
+    transaction::{DbTx, DbTxGAT, DbTxMut, DbTxMutGAT},
+    DatabaseError, DatabaseWriteOperation,
+};
+use bytes::Bytes;
+use parking_lot::{Condvar, Mutex, RwLock};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+    marker::PhantomData,
+    ops::{Bound, RangeBounds},
+    sync::Arc,
+};
+
+/// `MDBX_KEYEXIST` error code, returned when inserting a key that already exists.
+const KEY_EXIST: i32 = -30799;
+/// `MDBX_NOTFOUND` error code, returned when a required entry does not exist.
+const NOT_FOUND: i32 = -30798;
+/// `MDBX_EKEYMISMATCH` error code, returned when appending out of order.
+const KEY_MISMATCH: i32 = -30418;
+
+/// Encoded `(key, value)` entry of a table.
+type RawEntry = (Vec<u8>, Vec<u8>);
+
+/// Result of looking up an encoded entry.
+type EntryResult = Result<Option<RawEntry>, DatabaseError>;
+
+/// Written keys of a single table.
+///
+/// Every key maps to its sorted set of values, which holds exactly one value unless the table is
+/// a [DupSort] table. A key without values was deleted, which hides its values in the
+/// [BaseLayer].
+type TableEntries = BTreeMap<Vec<u8>, BTreeSet<Vec<u8>>>;
+
+/// Entries written to a single table.
+#[derive(Debug, Clone, Default)]
+struct MemoryTable {
+    /// Whether the table was cleared, which hides all of its entries in the [BaseLayer].
+    cleared: bool,
+    /// Keys written since the table was created or cleared.
+    entries: TableEntries,
+}
+
+/// Written entries of all tables, by table name.
+///
+/// Tables are shared between the database and its transactions, a transaction only copies a table
+/// once it writes to it.
+type MemoryTables = BTreeMap<&'static str, Arc<MemoryTable>>;
+
+/// Types of all tables, by table name.
+type TableTypes = BTreeMap<&'static str, TableType>;
+
+/// A [Database] that keeps all tables in memory.
+///
+/// Keys and duplicate values are stored encoded and compared bytewise, which matches the default
+/// MDBX comparators, so cursors observe the same ordering as they do on an MDBX environment.
+///
+/// Transactions operate on a snapshot of the database that is taken when they are opened. The
+/// snapshot shares the tables with the database, and a table is only copied once the transaction
+/// writes to it. Write transactions replace the database contents with their snapshot on commit,
+/// so like on MDBX there is at most one write transaction at a time: opening a write transaction
+/// blocks until the previous one is committed or dropped.
+///
+/// Like on MDBX, whether a table holds duplicate values is determined by the type it was created
+/// with: the [Tables] are created with the database, auxiliary tables with
+/// [MemoryDatabase::create_tables_with].
+///
+/// If the database has a [BaseLayer], it only holds the changes made on top of it: every
+/// transaction reads from a snapshot of the base layer, and only the keys that were written are
+/// kept in memory.
+#[derive(Debug, Clone)]
+pub struct MemoryDatabase {
+    tables: Arc<RwLock<MemoryTables>>,
+    /// Types of the created tables.
+    table_types: Arc<RwLock<TableTypes>>,
+    /// Entries beneath the written tables.
+    base: Option<Arc<dyn BaseLayer>>,
+    /// Serializes the write transactions.
+    writer: Arc<WriterLock>,
+}
+
+impl MemoryDatabase {
+    /// Creates an empty in-memory database.
+    pub fn new() -> Self {
+        let table_types = Tables::ALL.iter().map(|table| (table.name(), table.table_type()));
+        Self {
+            tables: Default::default(),
+            table_types: Arc::new(RwLock::new(table_types.collect())),
+            base: None,
+            writer: Default::default(),
+        }
+    }
+
+    /// Creates an in-memory database that applies all writes on top of the given base layer.
+    pub(crate) fn with_base(base: Arc<dyn BaseLayer>) -> Self {
+        Self { base: Some(base), ..Self::new() }
+    }
+
+    /// Creates the given auxiliary `tables` next to the [Tables], like `Env::create_tables_with`
+    /// does on MDBX.
+    ///
+    /// Tables that were already created keep their type.
+    pub fn create_tables_with(&self, tables: &[TableDef]) {
+        let mut table_types = self.table_types.write();
+        for table in tables {
+            table_types.entry(table.name).or_insert(table.table_type);
+        }
+    }
+
+    /// Opens a transaction on a snapshot of the database.
+    fn begin(&self, writable: bool) -> Result<MemoryTx<'_>, DatabaseError> {
+        // the snapshot of a write transaction is taken after the previous one is committed
+        let writer = writable.then(|| self.writer.acquire());
+        let base = self.base.as_ref().map(|base| base.begin()).transpose()?;
+        // only the table handles are cloned, the entries are shared until they are written to
+        let tables = Mutex::new(self.tables.read().clone());
+        Ok(MemoryTx { db: &self.tables, table_types: &self.table_types, base, tables, writer })
+    }
+}
+
+/// Lock that is held by the write transaction of a [MemoryDatabase].
+#[derive(Debug, Default)]
+struct WriterLock {
+    /// Whether a write transaction is open.
+    locked: Mutex<bool>,
+    /// Notified when the write transaction is committed or dropped.
+    released: Condvar,
+}
+
+impl WriterLock {
+    /// Blocks until no other write transaction is open and takes the lock.
+    fn acquire(&self) -> WriterGuard<'_> {
+        let mut locked = self.locked.lock();
+        while *locked {
+            self.released.wait(&mut locked);
+        }
+        *locked = true;
+        WriterGuard(self)
+    }
+}
+
+/// Releases the [WriterLock] when dropped.
+#[derive(Debug)]
+struct WriterGuard<'a>(&'a WriterLock);
+
+impl Drop for WriterGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.locked.lock() = false;
+        self.0.released.notify_one();
+    }
+}
+
+impl Default for MemoryDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Committed entries beneath the tables of a [MemoryDatabase].
+pub(crate) trait BaseLayer: Debug + Send + Sync {
+    /// Opens a read transaction, which observes a consistent snapshot of the entries.
+    fn begin(&self) -> Result<Box<dyn BaseTx + '_>, DatabaseError>;
+}
+
+/// Read transaction of a [BaseLayer].
+///
+/// Tables are addressed by name, and keys and values are encoded. Tables that don't exist are
+/// empty.
+pub(crate) trait BaseTx: Debug + Send + Sync {
+    /// Returns `true` if the table is a [DupSort] table.
+    fn is_dupsort(&self, table: &'static str) -> Result<bool, DatabaseError>;
+
+    /// Returns all values stored under `key`.
+    fn values(&self, table: &'static str, key: &[u8]) -> Result<BTreeSet<Vec<u8>>, DatabaseError>;
+
+    /// Returns the smallest key within the lower bound `from`.
+    fn next_key(
+        &self,
+        table: &'static str,
+        from: Bound<&[u8]>,
+    ) -> Result<Option<Vec<u8>>, DatabaseError>;
+
+    /// Returns the largest key within the upper bound `to`.
+    fn prev_key(
+        &self,
+        table: &'static str,
+        to: Bound<&[u8]>,
+    ) -> Result<Option<Vec<u8>>, DatabaseError>;
+
+    /// Returns the number of entries of the table.
+    fn entries(&self, table: &'static str) -> Result<usize, DatabaseError>;
+}
+
+impl<'a> DatabaseGAT<'a> for MemoryDatabase {
+    type TX = MemoryTx<'a>;
+    type TXMut = MemoryTx<'a>;
+}
+
+impl Database for MemoryDatabase {
+    fn tx(&self) -> Result<<Self as DatabaseGAT<'_>>::TX, DatabaseError> {
+        self.begin(false)
+    }
+
+    fn tx_mut(&self) -> Result<<Self as DatabaseGAT<'_>>::TXMut, DatabaseError> {
+        self.begin(true)
+    }
+}
+
+/// Transaction of a [MemoryDatabase].
+#[derive(Debug)]
+pub struct MemoryTx<'db> {
+    /// Contents of the database, replaced on commit of a write transaction.
+    db: &'db RwLock<MemoryTables>,
+    /// Types of the tables of the database.
+    table_types: &'db RwLock<TableTypes>,
+    /// Snapshot of the base layer of the database, if it has one.
+    base: Option<Box<dyn BaseTx + 'db>>,
+    /// Snapshot of the database this transaction reads from and writes to.
+    tables: Mutex<MemoryTables>,
+    /// Writer lock of a write transaction, whose snapshot is written back to the database on
+    /// commit.
+    writer: Option<WriterGuard<'db>>,
+}
+
+impl<'db> MemoryTx<'db> {
+    /// Returns `true` if table `T` was created as a [DupSort] table.
+    fn is_dupsort<T: Table>(&self) -> Result<bool, DatabaseError> {
+        if let Some(table_type) = self.table_types.read().get(T::NAME) {
+            return Ok(*table_type == TableType::DupSort)
+        }
+        self.base.as_ref().map_or(Ok(false), |base| base.is_dupsort(T::NAME))
+    }
+
+    /// Creates a cursor over table `T`.
+    fn new_cursor<T: Table>(&self) -> Result<MemoryCursor<'_, T>, DatabaseError> {
+        Ok(MemoryCursor {
+            tables: &self.tables,
+            base: self.base.as_deref(),
+            dupsort: self.is_dupsort::<T>()?,
+            position: None,
+            _table: PhantomData,
+        })
+    }
+
+    /// Returns the first value stored under `key` in table `T`.
+    fn first_value<T: Table>(&self, key: T::Key) -> Result<Option<Vec<u8>>, DatabaseError> {
+        let tables = self.tables.lock();
+        let values = TableView::new(T::NAME, &tables, self.base.as_deref()).values(key.encode())?;
+        Ok(values.and_then(|values| values.first().cloned()))
+    }
+}
+
+impl<'a> DbTxGAT<'a> for MemoryTx<'_> {
+    type Cursor<T: Table> = MemoryCursor<'a, T>;
+    type DupCursor<T: DupSort> = MemoryCursor<'a, T>;
+}
+
+impl<'a> DbTxMutGAT<'a> for MemoryTx<'_> {
+    type CursorMut<T: Table> = MemoryCursor<'a, T>;
+    type DupCursorMut<T: DupSort> = MemoryCursor<'a, T>;
+}
+
+impl<'a> TableImporter<'a> for MemoryTx<'_> {}
+
+impl<'tx> DbTx<'tx> for MemoryTx<'tx> {
+    fn get<T: Table>(&self, key: T::Key) -> Result<Option<T::Value>, DatabaseError> {
+        self.first_value::<T>(key)?.map(|value| decode_one::<T>(Cow::Owned(value))).transpose()
+    }
+
+    fn get_raw<T: Table>(&self, key: T::Key) -> Result<Option<Bytes>, DatabaseError> {
+        Ok(self.first_value::<T>(key)?.map(Bytes::from))
+    }
+
+    fn commit(self) -> Result<bool, DatabaseError> {
+        if self.writer.is_some() {
+            *self.db.write() = self.tables.into_inner();
+        }
+        Ok(true)
+    }
+
+    fn drop(self) {}
+
+    fn cursor_read<T: Table>(&self) -> Result<<Self as DbTxGAT<'_>>::Cursor<T>, DatabaseError> {
+        self.new_cursor()
+    }
+
+    fn cursor_dup_read<T: DupSort>(
+        &self,
+    ) -> Result<<Self as DbTxGAT<'_>>::DupCursor<T>, DatabaseError> {
+        self.new_cursor()
+    }
+
+    fn entries<T: Table>(&self) -> Result<usize, DatabaseError> {
+        let tables = self.tables.lock();
+        TableView::new(T::NAME, &tables, self.base.as_deref()).entries()
+    }
+}
+
+impl DbTxMut<'_> for MemoryTx<'_> {
+    fn put<T: Table>(&self, key: T::Key, value: T::Value) -> Result<(), DatabaseError> {
+        let dupsort = self.is_dupsort::<T>()?;
+        let entry = (key.encode().as_ref().to_vec(), compress_value(value));
+        let mut tables = self.tables.lock();
+        TableWriter::new(T::NAME, &mut tables, self.base.as_deref()).insert(entry, dupsort)
+    }
+
+    fn delete<T: Table>(
+        &self,
+        key: T::Key,
+        value: Option<T::Value>,
+    ) -> Result<bool, DatabaseError> {
+        let key = key.encode();
+        let value = value.map(compress_value);
+        let mut tables = self.tables.lock();
+        if TableView::new(T::NAME, &tables, self.base.as_deref()).values(key.as_ref())?.is_none() {
+            return Ok(false)
+        }
+        TableWriter::new(T::NAME, &mut tables, self.base.as_deref())
+            .remove(key.as_ref(), value.as_deref())
+    }
+
+    fn clear<T: Table>(&self) -> Result<(), DatabaseError> {
+        let table = MemoryTable { cleared: true, entries: TableEntries::new() };
+        self.tables.lock().insert(T::NAME, Arc::new(table));
+        Ok(())
+    }
+
+    fn cursor_write<T: Table>(
+        &self,
+    ) -> Result<<Self as DbTxMutGAT<'_>>::CursorMut<T>, DatabaseError> {
+        self.new_cursor()
+    }
+
+    fn cursor_dup_write<T: DupSort>(
+        &self,
+    ) -> Result<<Self as DbTxMutGAT<'_>>::DupCursorMut<T>, DatabaseError> {
+        self.new_cursor()
+    }
+}
+
+/// Read access to a table of a transaction, which merges the written keys with the keys of the
+/// [BaseLayer].
+struct TableView<'a> {
+    /// Name of the table.
+    table: &'static str,
+    /// Keys written to the table.
+    written: Option<&'a TableEntries>,
+    /// Snapshot of the base layer, unless there is none or the table was cleared.
+    base: Option<&'a dyn BaseTx>,
+}
+
+impl<'a> TableView<'a> {
+    /// Creates a view of the table with the given name.
+    fn new(table: &'static str, tables: &'a MemoryTables, base: Option<&'a dyn BaseTx>) -> Self {
+        let written = tables.get(table);
+        let base = base.filter(|_| !written.map_or(false, |written| written.cleared));
+        Self { table, written: written.map(|written| &written.entries), base }
+    }
+
+    /// Returns the values stored under `key`, or `None` if there are none.
+    fn values(
+        &self,
+        key: impl AsRef<[u8]>,
+    ) -> Result<Option<Cow<'a, BTreeSet<Vec<u8>>>>, DatabaseError> {
+        let values = match (self.written.and_then(|written| written.get(key.as_ref())), self.base) {
+            (Some(values), _) => Cow::Borrowed(values),
+            (None, Some(base)) => Cow::Owned(base.values(self.table, key.as_ref())?),
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(values).filter(|values| !values.is_empty()))
+    }
+
+    /// Returns `true` if `key` was written, in which case its values in the base layer are hidden.
+    fn is_written(&self, key: &[u8]) -> bool {
+        self.written.map_or(false, |written| written.contains_key(key))
+    }
+
+    /// Returns the smallest key with values within the lower bound `from`.
+    fn next_key(&self, from: Bound<&[u8]>) -> Result<Option<Vec<u8>>, DatabaseError> {
+        let written = self
+            .written
+            .into_iter()
+            .flat_map(|written| written.range::<[u8], _>((from, Bound::Unbounded)))
+            .find(|(_, values)| !values.is_empty())
+            .map(|(key, _)| key);
+        let Some(base) = self.base else { return Ok(written.cloned()) };
+
+        let mut from = owned_bound(from);
+        loop {
+            match base.next_key(self.table, borrowed_bound(&from))? {
+                // keys that were written are found among the written keys, or were deleted
+                Some(key) if self.is_written(&key) => from = Bound::Excluded(key),
+                Some(key) if written.map_or(true, |written| key < *written) => return Ok(Some(key)),
+                _ => return Ok(written.cloned()),
+            }
+        }
+    }
+
+    /// Returns the largest key with values within the upper bound `to`.
+    fn prev_key(&self, to: Bound<&[u8]>) -> Result<Option<Vec<u8>>, DatabaseError> {
+        let written = self
+            .written
+            .into_iter()
+            .flat_map(|written| written.range::<[u8], _>((Bound::Unbounded, to)))
+            .rev()
+            .find(|(_, values)| !values.is_empty())
+            .map(|(key, _)| key);
+        let Some(base) = self.base else { return Ok(written.cloned()) };
+
+        let mut to = owned_bound(to);
+        loop {
+            match base.prev_key(self.table, borrowed_bound(&to))? {
+                Some(key) if self.is_written(&key) => to = Bound::Excluded(key),
+                Some(key) if written.map_or(true, |written| key > *written) => return Ok(Some(key)),
+                _ => return Ok(written.cloned()),
+            }
+        }
+    }
+
+    /// Returns the number of entries of the table.
+    fn entries(&self) -> Result<usize, DatabaseError> {
+        let mut entries = match self.base {
+            Some(base) => base.entries(self.table)?,
+            None => 0,
+        };
+        for (key, values) in self.written.into_iter().flatten() {
+            if let Some(base) = self.base {
+                entries -= base.values(self.table, key)?.len();
+            }
+            entries += values.len();
+        }
+        Ok(entries)
+    }
+
+    /// Returns the first entry of `key`.
+    fn first_of(&self, key: Option<Vec<u8>>) -> EntryResult {
+        let Some(key) = key else { return Ok(None) };
+        let value = self.values(&key)?.and_then(|values| values.first().cloned());
+        Ok(value.map(|value| (key, value)))
+    }
+
+    /// Returns the last entry of `key`.
+    fn last_of(&self, key: Option<Vec<u8>>) -> EntryResult {
+        let Some(key) = key else { return Ok(None) };
+        let value = self.values(&key)?.and_then(|values| values.last().cloned());
+        Ok(value.map(|value| (key, value)))
+    }
+
+    /// Returns the first entry of the table.
+    fn first_entry(&self) -> EntryResult {
+        self.first_of(self.next_key(Bound::Unbounded)?)
+    }
+
+    /// Returns the last entry of the table.
+    fn last_entry(&self) -> EntryResult {
+        self.last_of(self.prev_key(Bound::Unbounded)?)
+    }
+
+    /// Returns the first entry whose key is greater than or equal to `key`.
+    fn seek_entry(&self, key: &[u8]) -> EntryResult {
+        self.first_of(self.next_key(Bound::Included(key))?)
+    }
+
+    /// Returns the first entry whose key is greater than `key`.
+    fn next_key_entry(&self, key: &[u8]) -> EntryResult {
+        self.first_of(self.next_key(Bound::Excluded(key))?)
+    }
+
+    /// Returns the first entry of `key` whose value is greater than or equal to `subkey`.
+    fn seek_dup_entry(&self, key: &[u8], subkey: &[u8]) -> EntryResult {
+        let Some(values) = self.values(key)? else { return Ok(None) };
+        let value = values.range::<[u8], _>((Bound::Included(subkey), Bound::Unbounded)).next();
+        Ok(value.map(|value| (key.to_vec(), value.clone())))
+    }
+
+    /// Returns the entry the cursor at `position` is on.
+    ///
+    /// If the entry at `position` was deleted, the cursor is on the entry that followed it.
+    fn current_entry(&self, position: &RawEntry) -> EntryResult {
+        let (key, value) = position;
+        if self.values(key)?.map_or(false, |values| values.contains(value)) {
+            Ok(Some(position.clone()))
+        } else {
+            self.next_entry(position)
+        }
+    }
+
+    /// Returns the entry following `position` among the values of its key.
+    fn next_dup_entry(&self, (key, value): &RawEntry) -> EntryResult {
+        let Some(values) = self.values(key)? else { return Ok(None) };
+        let next =
+            values.range::<[u8], _>((Bound::Excluded(value.as_slice()), Bound::Unbounded)).next();
+        Ok(next.map(|next| (key.clone(), next.clone())))
+    }
+
+    /// Returns the entry following `position`.
+    fn next_entry(&self, position: &RawEntry) -> EntryResult {
+        match self.next_dup_entry(position)? {
+            Some(entry) => Ok(Some(entry)),
+            None => self.next_key_entry(&position.0),
+        }
+    }
+
+    /// Returns the entry preceding `position` among the values of its key.
+    fn prev_dup_entry(&self, (key, value): &RawEntry) -> EntryResult {
+        let Some(values) = self.values(key)? else { return Ok(None) };
+        let prev = values
+            .range::<[u8], _>((Bound::Unbounded, Bound::Excluded(value.as_slice())))
+            .next_back();
+        Ok(prev.map(|prev| (key.clone(), prev.clone())))
+    }
+
+    /// Returns the entry preceding `position`.
+    fn prev_entry(&self, position: &RawEntry) -> EntryResult {
+        match self.prev_dup_entry(position)? {
+            Some(entry) => Ok(Some(entry)),
+            None => self.last_of(self.prev_key(Bound::Excluded(&position.0))?),
+        }
+    }
+}
+
+/// Write access to a table of a transaction.
+struct TableWriter<'a> {
+    /// Name of the table.
+    table: &'static str,
+    /// Keys written to the table, copied from the database if they are shared.
+    written: &'a mut TableEntries,
+    /// Snapshot of the base layer, unless there is none or the table was cleared.
+    base: Option<&'a dyn BaseTx>,
+}
+
+impl<'a> TableWriter<'a> {
+    /// Creates a writer of the table with the given name.
+    fn new(
+        table: &'static str,
+        tables: &'a mut MemoryTables,
+        base: Option<&'a dyn BaseTx>,
+    ) -> Self {
+        let written = Arc::make_mut(tables.entry(table).or_default());
+        let base = base.filter(|_| !written.cleared);
+        Self { table, written: &mut written.entries, base }
+    }
+
+    /// Returns the values of `key` for writing, starting from its values in the base layer if it
+    /// wasn't written yet.
+    fn values_mut(&mut self, key: &[u8]) -> Result<&mut BTreeSet<Vec<u8>>, DatabaseError> {
+        if !self.written.contains_key(key) {
+            let values = match self.base {
+                Some(base) => base.values(self.table, key)?,
+                None => BTreeSet::new(),
+            };
+            self.written.insert(key.to_vec(), values);
+        }
+        Ok(self.written.get_mut(key).expect("inserted above"))
+    }
+
+    /// Inserts an entry, replacing the value of its key unless the table is a [DupSort] table.
+    fn insert(&mut self, (key, value): RawEntry, dupsort: bool) -> Result<(), DatabaseError> {
+        let values = self.values_mut(&key)?;
+        if !dupsort {
+            values.clear();
+        }
+        values.insert(value);
+        Ok(())
+    }
+
+    /// Removes the given value of `key`, or all of its values, returning `true` if any value was
+    /// removed.
+    fn remove(&mut self, key: &[u8], value: Option<&[u8]>) -> Result<bool, DatabaseError> {
+        let values = self.values_mut(key)?;
+        let removed = match value {
+            Some(value) => values.remove(value),
+            None => {
+                let removed = !values.is_empty();
+                values.clear();
+                removed
+            }
+        };
+        // without a base layer there is nothing to hide, so deleted keys are dropped
+        if values.is_empty() && self.base.is_none() {
+            self.written.remove(key);
+        }
+        Ok(removed)
+    }
+}
+
+/// Cursor over table `T` of a [MemoryTx].
+///
+/// The cursor remembers the entry it is positioned at rather than a reference into the table, so
+/// it stays valid while the table is modified. Once the entry under the cursor is deleted, moving
+/// the cursor continues from where that entry used to be.
+#[derive(Debug)]
+pub struct MemoryCursor<'tx, T: Table> {
+    /// Tables of the transaction.
+    tables: &'tx Mutex<MemoryTables>,
+    /// Snapshot of the base layer of the transaction.
+    base: Option<&'tx dyn BaseTx>,
+    /// Whether the table was created as a [DupSort] table.
+    dupsort: bool,
+    /// Entry the cursor is positioned at.
+    position: Option<RawEntry>,
+    /// Phantom data to enforce encoding/decoding.
+    _table: PhantomData<T>,
+}
+
+impl<'tx, T: Table> MemoryCursor<'tx, T> {
+    /// Looks up an entry of the table and positions the cursor at it if it exists.
+    fn lookup(
+        &mut self,
+        f: impl FnOnce(&TableView<'_>, Option<&RawEntry>) -> EntryResult,
+    ) -> EntryResult {
+        let entry = {
+            let tables = self.tables.lock();
+            f(&TableView::new(T::NAME, &tables, self.base), self.position.as_ref())?
+        };
+        if entry.is_some() {
+            self.position = entry.clone();
+        }
+        Ok(entry)
+    }
+
+    /// Looks up an entry with [Self::lookup] and decodes it.
+    fn lookup_pair(
+        &mut self,
+        f: impl FnOnce(&TableView<'_>, Option<&RawEntry>) -> EntryResult,
+    ) -> PairResult<T> {
+        self.lookup(f)?.as_ref().map(decode_entry::<T>).transpose()
+    }
+
+    /// Looks up an entry with [Self::lookup] and decodes its value.
+    fn lookup_value(
+        &mut self,
+        f: impl FnOnce(&TableView<'_>, Option<&RawEntry>) -> EntryResult,
+    ) -> ValueOnlyResult<T> {
+        self.lookup(f)?.map(|(_, value)| decode_one::<T>(Cow::Owned(value))).transpose()
+    }
+
+    /// Writes an entry and positions the cursor at it.
+    ///
+    /// The entry is only written if `check` doesn't return an error code for it.
+    fn write(
+        &mut self,
+        key: T::Key,
+        value: T::Value,
+        operation: DatabaseWriteOperation,
+        check: impl FnOnce(&TableView<'_>, &RawEntry) -> Result<Option<i32>, DatabaseError>,
+    ) -> Result<(), DatabaseError> {
+        let entry = (key.encode().as_ref().to_vec(), compress_value(value));
+        let mut tables = self.tables.lock();
+        if let Some(code) = check(&TableView::new(T::NAME, &tables, self.base), &entry)? {
+            return Err(DatabaseError::Write {
+                code,
+                operation,
+                table_name: T::NAME,
+                key: Box::from(entry.0.as_ref()),
+            })
+        }
+
+        TableWriter::new(T::NAME, &mut tables, self.base).insert(entry.clone(), self.dupsort)?;
+        self.position = Some(entry);
+        Ok(())
+    }
+
+    /// Returns the entry the cursor is on, failing if there is none.
+    fn current_raw(&self, tables: &MemoryTables) -> Result<RawEntry, DatabaseError> {
+        let Some(position) = self.position.as_ref() else {
+            return Err(DatabaseError::Delete(NOT_FOUND))
+        };
+        TableView::new(T::NAME, tables, self.base)
+            .current_entry(position)?
+            .ok_or(DatabaseError::Delete(NOT_FOUND))
+    }
+}
+
+impl<'tx, T: Table> DbCursorRO<'tx, T> for MemoryCursor<'tx, T> {
+    fn first(&mut self) -> PairResult<T> {
+        self.lookup_pair(|table, _| table.first_entry())
+    }
+
+    fn seek_exact(&mut self, key: T::Key) -> PairResult<T> {
+        let key = key.encode();
+        self.lookup_pair(|table, _| table.first_of(Some(key.as_ref().to_vec())))
+    }
+
+    fn seek(&mut self, key: T::Key) -> PairResult<T> {
+        let key = key.encode();
+        self.lookup_pair(|table, _| table.seek_entry(key.as_ref()))
+    }
+
+    fn next(&mut self) -> PairResult<T> {
+        self.lookup_pair(|table, position| match position {
+            Some(position) => table.next_entry(position),
+            None => table.first_entry(),
+        })
+    }
+
+    fn prev(&mut self) -> PairResult<T> {
+        self.lookup_pair(|table, position| match position {
+            Some(position) => table.prev_entry(position),
+            None => table.last_entry(),
+        })
+    }
+
+    fn last(&mut self) -> PairResult<T> {
+        self.lookup_pair(|table, _| table.last_entry())
+    }
+
+    fn current(&mut self) -> PairResult<T> {
+        let Some(position) = self.position.as_ref() else { return Ok(None) };
+        let tables = self.tables.lock();
+        let entry = TableView::new(T::NAME, &tables, self.base).current_entry(position)?;
+        entry.as_ref().map(decode_entry::<T>).transpose()
+    }
+
+    fn walk<'cursor>(
+        &'cursor mut self,
+        start_key: Option<T::Key>,
+    ) -> Result<Walker<'cursor, 'tx, T, Self>, DatabaseError>
+    where
+        Self: Sized,
+    {
+        let start = match start_key {
+            Some(start_key) => self.seek(start_key),
+            None => self.first(),
+        }
+        .transpose();
+
+        Ok(Walker::new(self, start))
+    }
+
+    fn walk_range<'cursor>(
+        &'cursor mut self,
+        range: impl RangeBounds<T::Key>,
+    ) -> Result<RangeWalker<'cursor, 'tx, T, Self>, DatabaseError>
+    where
+        Self: Sized,
+    {
+        let start = match range.start_bound().cloned() {
+            Bound::Included(key) => self.seek(key),
+            Bound::Excluded(_key) => {
+                unreachable!("Rust doesn't allow for Bound::Excluded in starting bounds");
+            }
+            Bound::Unbounded => self.first(),
+        }
+        .transpose();
+
+        Ok(RangeWalker::new(self, start, range.end_bound().cloned()))
+    }
+
+    fn walk_back<'cursor>(
+        &'cursor mut self,
+        start_key: Option<T::Key>,
+    ) -> Result<ReverseWalker<'cursor, 'tx, T, Self>, DatabaseError>
+    where
+        Self: Sized,
+    {
+        let start = match start_key {
+            Some(start_key) => self.seek(start_key),
+            None => self.last(),
+        }
+        .transpose();
+
+        Ok(ReverseWalker::new(self, start))
+    }
+}
+
+impl<'tx, T: DupSort> DbDupCursorRO<'tx, T> for MemoryCursor<'tx, T> {
+    fn next_dup(&mut self) -> PairResult<T> {
+        self.lookup_pair(|table, position| match position {
+            Some(position) => table.next_dup_entry(position),
+            None => Ok(None),
+        })
+    }
+
+    fn next_no_dup(&mut self) -> PairResult<T> {
+        self.lookup_pair(|table, position| match position {
+            Some((key, _)) => table.next_key_entry(key),
+            None => table.first_entry(),
+        })
+    }
+
+    fn next_dup_val(&mut self) -> ValueOnlyResult<T> {
+        self.lookup_value(|table, position| match position {
+            Some(position) => table.next_dup_entry(position),
+            None => Ok(None),
+        })
+    }
+
+    fn prev_dup(&mut self) -> PairResult<T> {
+        self.lookup_pair(|table, position| match position {
+            Some(position) => table.prev_dup_entry(position),
+            None => Ok(None),
+        })
+    }
+
+    fn last_dup(&mut self, key: T::Key) -> ValueOnlyResult<T> {
+        let key = key.encode();
+        self.lookup_value(|table, _| table.last_of(Some(key.as_ref().to_vec())))
+    }
+
+    fn dup_count(&mut self, key: T::Key) -> Result<u64, DatabaseError> {
+        let key = key.encode();
+        let mut count = 0;
+        self.lookup(|table, _| {
+            let Some(values) = table.values(key.as_ref())? else { return Ok(None) };
+            count = values.len() as u64;
+            Ok(values.first().map(|value| (key.as_ref().to_vec(), value.clone())))
+        })?;
+        Ok(count)
+    }
+
+    fn seek_by_key_subkey(&mut self, key: T::Key, subkey: T::SubKey) -> ValueOnlyResult<T> {
+        let (key, subkey) = (key.encode(), subkey.encode());
+        self.lookup_value(|table, _| table.seek_dup_entry(key.as_ref(), subkey.as_ref()))
+    }
+
+    fn walk_dup<'cursor>(
+        &'cursor mut self,
+        key: Option<T::Key>,
+        subkey: Option<T::SubKey>,
+    ) -> Result<DupWalker<'cursor, 'tx, T, Self>, DatabaseError> {
+        let start = match (key, subkey) {
+            (Some(key), Some(subkey)) => {
+                let (key, subkey) = (key.encode(), subkey.encode());
+                self.lookup_pair(|table, _| table.seek_dup_entry(key.as_ref(), subkey.as_ref()))
+                    .transpose()
+            }
+            (Some(key), None) => self.seek_exact(key).transpose(),
+            (None, Some(subkey)) => {
+                if let Some((key, _)) = self.first()? {
+                    let (key, subkey) = (key.encode(), subkey.encode());
+                    self.lookup_pair(|table, _| table.seek_dup_entry(key.as_ref(), subkey.as_ref()))
+                        .transpose()
+                } else {
+                    Some(Err(DatabaseError::Read(NOT_FOUND)))
+                }
+            }
+            (None, None) => self.first().transpose(),
+        };
+
+        Ok(DupWalker::<'cursor, 'tx, T, Self> { cursor: self, start, _tx_phantom: PhantomData {} })
+    }
+}
+
+impl<'tx, T: Table> DbCursorRW<'tx, T> for MemoryCursor<'tx, T> {
+    fn upsert(&mut self, key: T::Key, value: T::Value) -> Result<(), DatabaseError> {
+        self.write(key, value, DatabaseWriteOperation::CursorUpsert, |_, _| Ok(None))
+    }
+
+    fn insert(&mut self, key: T::Key, value: T::Value) -> Result<(), DatabaseError> {
+        self.write(key, value, DatabaseWriteOperation::CursorInsert, |table, (key, _)| {
+            Ok(table.values(key)?.map(|_| KEY_EXIST))
+        })
+    }
+
+    fn append(&mut self, key: T::Key, value: T::Value) -> Result<(), DatabaseError> {
+        let dupsort = self.dupsort;
+        self.write(key, value, DatabaseWriteOperation::CursorAppend, |table, entry| {
+            Ok(match table.last_entry()? {
+                Some(last) if last.0 == entry.0 && dupsort && last.1 < entry.1 => None,
+                Some(last) if last.0 >= entry.0 => Some(KEY_MISMATCH),
+                _ => None,
+            })
+        })
+    }
+
+    fn delete_current(&mut self) -> Result<(), DatabaseError> {
+        let mut tables = self.tables.lock();
+        let (key, value) = self.current_raw(&tables)?;
+
+        TableWriter::new(T::NAME, &mut tables, self.base).remove(&key, Some(&value))?;
+        self.position = Some((key, value));
+        Ok(())
+    }
+}
+
+impl<'tx, T: DupSort> DbDupCursorRW<'tx, T> for MemoryCursor<'tx, T> {
+    fn delete_current_duplicates(&mut self) -> Result<(), DatabaseError> {
+        let mut tables = self.tables.lock();
+        let (key, value) = self.current_raw(&tables)?;
+        let last = TableView::new(T::NAME, &tables, self.base).last_of(Some(key.clone()))?;
+
+        TableWriter::new(T::NAME, &mut tables, self.base).remove(&key, None)?;
+        self.position = Some(last.unwrap_or((key, value)));
+        Ok(())
+    }
+
+    fn append_dup(&mut self, key: T::Key, value: T::Value) -> Result<(), DatabaseError> {
+        self.write(key, value, DatabaseWriteOperation::CursorAppendDup, |table, (key, value)| {
+            let last = table.values(key)?.and_then(|values| values.last().cloned());
+            Ok(last.filter(|last| last >= value).map(|_| KEY_MISMATCH))
+        })
+    }
+}
+
+/// Compresses a value the same way it is written to MDBX.
+fn compress_value<V: Compress>(value: V) -> Vec<u8> {
+    match value.uncompressable_ref() {
+        Some(value) => value.to_vec(),
+        None => value.compress().as_ref().to_vec(),
+    }
+}
+
+/// Decodes an encoded entry of table `T`.
+fn decode_entry<T: Table>((key, value): &RawEntry) -> Result<TableRow<T>, DatabaseError> {
+    decoder::<T>((Cow::Borrowed(key.as_slice()), Cow::Borrowed(value.as_slice())))
+}
+
+/// Converts a borrowed key bound into an owned one.
+fn owned_bound(bound: Bound<&[u8]>) -> Bound<Vec<u8>> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.to_vec()),
+        Bound::Excluded(key) => Bound::Excluded(key.to_vec()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// Borrows an owned key bound.
+fn borrowed_bound(bound: &Bound<Vec<u8>>) -> Bound<&[u8]> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key),
+        Bound::Excluded(key) => Bound::Excluded(key),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        init_db, init_db_with_tables, open_db_overlay,
+        tables::{CanonicalHeaders, PlainStorageState},
+        test_utils::create_test_rw_db,
+    };
+    use reth_primitives::{Address, StorageEntry, H256, U256};
+
+    type RowResult<T> = Result<TableRow<T>, DatabaseError>;
+
+    /// Runs the same scenario on a database, asserting the MDBX cursor and transaction semantics.
+    fn assert_database_semantics<DB: Database>(db: &DB) {
+        let header = |key: u64| (key, H256::from_low_u64_be(key));
+
+        // plain table, written out of order
+        let tx = db.tx_mut().unwrap();
+        for key in [5u64, 1, 3, 9] {
+            tx.put::<CanonicalHeaders>(key, H256::from_low_u64_be(key)).unwrap();
+        }
+        tx.commit().unwrap();
+
+        let tx = db.tx().unwrap();
+        let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+        let keys = |walker: &mut dyn Iterator<Item = RowResult<CanonicalHeaders>>| {
+            walker.map(|entry| entry.unwrap().0).collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&mut cursor.walk(None).unwrap()), vec![1, 3, 5, 9]);
+        assert_eq!(keys(&mut cursor.walk_range(3..9).unwrap()), vec![3, 5]);
+        assert_eq!(keys(&mut cursor.walk_back(Some(5)).unwrap()), vec![5, 3, 1]);
+        assert_eq!(cursor.seek(4), Ok(Some(header(5))));
+        assert_eq!(cursor.seek_exact(4), Ok(None));
+        assert_eq!(cursor.last(), Ok(Some(header(9))));
+        assert_eq!(cursor.prev(), Ok(Some(header(5))));
+        assert_eq!(tx.get::<CanonicalHeaders>(3), Ok(Some(H256::from_low_u64_be(3))));
+        drop(cursor);
+        tx.commit().unwrap();
+
+        // write cursor
+        let tx = db.tx_mut().unwrap();
+        let mut cursor = tx.cursor_write::<CanonicalHeaders>().unwrap();
+        assert!(cursor.insert(3, H256::zero()).is_err());
+        assert!(cursor.append(7, H256::zero()).is_err());
+        cursor.append(10, H256::from_low_u64_be(10)).unwrap();
+
+        // deleting while walking does not skip the following entry
+        let mut walker = cursor.walk(None).unwrap();
+        let mut walked = Vec::new();
+        while let Some(entry) = walker.next() {
+            let key = entry.unwrap().0;
+            if key == 3 {
+                walker.delete_current().unwrap();
+            }
+            walked.push(key);
+        }
+        assert_eq!(walked, vec![1, 3, 5, 9, 10]);
+        drop(cursor);
+        tx.commit().unwrap();
+
+        let tx = db.tx().unwrap();
+        let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+        assert_eq!(keys(&mut cursor.walk(None).unwrap()), vec![1, 5, 9, 10]);
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(4));
+        drop(cursor);
+        tx.commit().unwrap();
+
+        // dupsort table, duplicates written out of order
+        let (first, second) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        let entry =
+            |key: u64| StorageEntry { key: H256::from_low_u64_be(key), value: U256::from(key) };
+        let tx = db.tx_mut().unwrap();
+        for (address, key) in [(second, 1u64), (first, 3), (first, 1), (first, 2)] {
+            tx.put::<PlainStorageState>(address, entry(key)).unwrap();
+        }
+        tx.commit().unwrap();
+
+        let tx = db.tx_mut().unwrap();
+        let mut cursor = tx.cursor_dup_write::<PlainStorageState>().unwrap();
+        let subkeys = |walker: &mut dyn Iterator<Item = RowResult<PlainStorageState>>| {
+            walker.map(|entry| entry.unwrap().1.key.to_low_u64_be()).collect::<Vec<_>>()
+        };
+        assert_eq!(subkeys(&mut cursor.walk_dup(Some(first), None).unwrap()), vec![1, 2, 3]);
+        assert_eq!(cursor.seek_by_key_subkey(first, H256::from_low_u64_be(2)), Ok(Some(entry(2))));
+        assert_eq!(cursor.seek_by_key_subkey(first, H256::from_low_u64_be(4)), Ok(None));
+        assert_eq!(cursor.seek_exact(first), Ok(Some((first, entry(1)))));
+        assert_eq!(cursor.next_dup_val(), Ok(Some(entry(2))));
+        assert_eq!(cursor.next_no_dup(), Ok(Some((second, entry(1)))));
+        assert_eq!(cursor.next_dup(), Ok(None));
+        assert_eq!(tx.entries::<PlainStorageState>(), Ok(4));
+
+        assert_eq!(tx.delete::<PlainStorageState>(first, Some(entry(2))), Ok(true));
+        assert_eq!(subkeys(&mut cursor.walk_dup(Some(first), None).unwrap()), vec![1, 3]);
+        assert!(cursor.append_dup(first, entry(2)).is_err());
+
+        cursor.seek_exact(first).unwrap();
+        cursor.delete_current_duplicates().unwrap();
+        assert_eq!(cursor.first(), Ok(Some((second, entry(1)))));
+        assert_eq!(tx.entries::<PlainStorageState>(), Ok(1));
+        drop(cursor);
+        tx.commit().unwrap();
+    }
+
+    #[test]
+    fn memory_database_matches_mdbx() {
+        assert_database_semantics(&create_test_rw_db());
+        assert_database_semantics(&MemoryDatabase::new());
+    }
+
+    #[test]
+    fn memory_database_auxiliary_dupsort() {
+        crate::table!(
+            /// Auxiliary table used to test auxiliary `DUPSORT` tables.
+            ( AuxiliaryStorage ) Address | StorageEntry
+        );
+
+        impl DupSort for AuxiliaryStorage {
+            type SubKey = H256;
+        }
+
+        fn put_duplicates<DB: Database>(db: &DB) -> Vec<StorageEntry> {
+            let address = Address::from_low_u64_be(1);
+            let tx = db.tx_mut().unwrap();
+            for key in [2u64, 1] {
+                let entry =
+                    StorageEntry { key: H256::from_low_u64_be(key), value: U256::from(key) };
+                tx.put::<AuxiliaryStorage>(address, entry).unwrap();
+            }
+            tx.commit().unwrap();
+
+            let tx = db.tx().unwrap();
+            let mut cursor = tx.cursor_dup_read::<AuxiliaryStorage>().unwrap();
+            let walker = cursor.walk_dup(Some(address), None).unwrap();
+            walker.map(|entry| entry.unwrap().1).collect()
+        }
+
+        let tables = [TableDef::dup_sort(AuxiliaryStorage::NAME)];
+        let path = tempfile::TempDir::new().unwrap();
+        let mdbx = init_db_with_tables(path.path(), None, &tables).unwrap();
+        let memory = MemoryDatabase::new();
+        memory.create_tables_with(&tables);
+
+        let values = put_duplicates(&memory);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values, put_duplicates(&mdbx));
+    }
+
+    #[test]
+    fn overlay_matches_mdbx() {
+        let path = tempfile::TempDir::new().unwrap();
+        init_db(path.path(), None).unwrap();
+        assert_database_semantics(&open_db_overlay(path.path(), None).unwrap());
+    }
+
+    #[test]
+    fn memory_tx_isolation() {
+        let db = MemoryDatabase::new();
+
+        let tx = db.tx_mut().unwrap();
+        tx.put::<CanonicalHeaders>(1, H256::zero()).unwrap();
+        // uncommitted writes are not visible to other transactions
+        assert_eq!(db.tx().unwrap().get::<CanonicalHeaders>(1), Ok(None));
+        tx.commit().unwrap();
+        assert_eq!(db.tx().unwrap().get::<CanonicalHeaders>(1), Ok(Some(H256::zero())));
+
+        // writes of a dropped transaction are discarded
+        let tx = db.tx_mut().unwrap();
+        tx.clear::<CanonicalHeaders>().unwrap();
+        tx.drop();
+        assert_eq!(db.tx().unwrap().entries::<CanonicalHeaders>(), Ok(1));
+    }
+
+    #[test]
+    fn memory_concurrent_write_txs() {
+        let db = MemoryDatabase::new();
+
+        // every transaction reads the last committed key and writes the next one, a lost write
+        // would leave a gap
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let tx = db.tx_mut().unwrap();
+                    let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+                    let next = cursor.last().unwrap().map_or(0, |(key, _)| key + 1);
+                    drop(cursor);
+                    std::thread::yield_now();
+                    tx.put::<CanonicalHeaders>(next, H256::zero()).unwrap();
+                    tx.commit().unwrap();
+                });
+            }
+        });
+
+        let tx = db.tx().unwrap();
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(8));
+        assert_eq!(
+            tx.cursor_read::<CanonicalHeaders>().unwrap().last(),
+            Ok(Some((7, H256::zero())))
+        );
+    }
+}
@@ -1,2 +1,5 @@
 #[cfg(feature = "mdbx")]
 pub(crate) mod mdbx;
+pub(crate) mod memory;
+#[cfg(feature = "mdbx")]
+pub(crate) mod overlay;
@@ -0,0 +1,293 @@
+//! Copy-on-write view of a read-only MDBX environment.
+
+use crate::{
+    database::{Database, DatabaseGAT},
+    implementation::{
+        mdbx::Env,
+        memory::{BaseLayer, BaseTx, MemoryDatabase, MemoryTx},
+    },
+    DatabaseEnvRO, DatabaseError,
+};
+use parking_lot::Mutex;
+use reth_libmdbx::{ffi::DBI, Cursor, DatabaseFlags, EnvironmentKind, Transaction, RO};
+use std::{
+    collections::{BTreeSet, HashMap},
+    ops::Bound,
+    sync::Arc,
+};
+
+/// A [Database] that reads an MDBX environment opened read-only and applies all writes to an
+/// in-memory layer, see [open_db_overlay](crate::open_db_overlay).
+///
+/// The environment is never written to. Every transaction reads from its own read-only transaction
+/// of the environment, and the in-memory layer only holds the keys that were written, with deleted
+/// keys hiding the entries beneath them. Reads see the committed writes of the overlay, and fall
+/// through to the environment for all keys that weren't written. All writes are discarded once the
+/// overlay is dropped.
+#[derive(Debug, Clone)]
+pub struct OverlayEnv {
+    /// The in-memory layer on top of the environment.
+    inner: MemoryDatabase,
+}
+
+impl OverlayEnv {
+    /// Creates an overlay over the given environment.
+    pub fn new(env: DatabaseEnvRO) -> Self {
+        Self { inner: MemoryDatabase::with_base(Arc::new(env)) }
+    }
+}
+
+impl<'a> DatabaseGAT<'a> for OverlayEnv {
+    type TX = MemoryTx<'a>;
+    type TXMut = MemoryTx<'a>;
+}
+
+impl Database for OverlayEnv {
+    fn tx(&self) -> Result<<Self as DatabaseGAT<'_>>::TX, DatabaseError> {
+        self.inner.tx()
+    }
+
+    fn tx_mut(&self) -> Result<<Self as DatabaseGAT<'_>>::TXMut, DatabaseError> {
+        self.inner.tx_mut()
+    }
+}
+
+impl<E: EnvironmentKind> BaseLayer for Env<E> {
+    fn begin(&self) -> Result<Box<dyn BaseTx + '_>, DatabaseError> {
+        let txn =
+            self.inner.begin_ro_txn().map_err(|e| DatabaseError::InitTransaction(e.into()))?;
+        Ok(Box::new(EnvSnapshot { txn, tables: Default::default() }))
+    }
+}
+
+/// Read-only transaction of the environment beneath an [OverlayEnv].
+#[derive(Debug)]
+struct EnvSnapshot<'env, E: EnvironmentKind> {
+    txn: Transaction<'env, RO, E>,
+    /// Handles of the opened tables and whether they are `DUPSORT` tables, or `None` for tables
+    /// that don't exist in the environment.
+    tables: Mutex<HashMap<&'static str, Option<(DBI, bool)>>>,
+}
+
+impl<'env, E: EnvironmentKind> EnvSnapshot<'env, E> {
+    /// Returns the handle of the table and whether it is a `DUPSORT` table, or `None` if the table
+    /// doesn't exist.
+    fn table(&self, table: &'static str) -> Result<Option<(DBI, bool)>, DatabaseError> {
+        if let Some(handle) = self.tables.lock().get(table) {
+            return Ok(*handle)
+        }
+
+        let handle = match self.txn.open_db(Some(table)) {
+            Ok(db) => {
+                let flags = self.txn.db_flags(&db).map_err(|e| DatabaseError::Read(e.into()))?;
+                Some((db.dbi(), flags.contains(DatabaseFlags::DUP_SORT)))
+            }
+            Err(reth_libmdbx::Error::NotFound) => None,
+            Err(e) => return Err(DatabaseError::InitCursor(e.into())),
+        };
+        self.tables.lock().insert(table, handle);
+        Ok(handle)
+    }
+
+    /// Opens a cursor over the table, or returns `None` if the table doesn't exist.
+    fn cursor(&self, table: &'static str) -> Result<Option<(Cursor<'_, RO>, bool)>, DatabaseError> {
+        let Some((dbi, dupsort)) = self.table(table)? else { return Ok(None) };
+        let cursor =
+            self.txn.cursor_with_dbi(dbi).map_err(|e| DatabaseError::InitCursor(e.into()))?;
+        Ok(Some((cursor, dupsort)))
+    }
+}
+
+impl<'env, E: EnvironmentKind> BaseTx for EnvSnapshot<'env, E> {
+    fn is_dupsort(&self, table: &'static str) -> Result<bool, DatabaseError> {
+        Ok(self.table(table)?.map_or(false, |(_, dupsort)| dupsort))
+    }
+
+    fn values(&self, table: &'static str, key: &[u8]) -> Result<BTreeSet<Vec<u8>>, DatabaseError> {
+        let mut values = BTreeSet::new();
+        let Some((mut cursor, dupsort)) = self.cursor(table)? else { return Ok(values) };
+
+        let mut value = cursor.set::<Vec<u8>>(key).map_err(|e| DatabaseError::Read(e.into()))?;
+        while let Some(found) = value {
+            values.insert(found);
+            if !dupsort {
+                break
+            }
+            value = cursor
+                .next_dup::<(), Vec<u8>>()
+                .map_err(|e| DatabaseError::Read(e.into()))?
+                .map(|(_, value)| value);
+        }
+        Ok(values)
+    }
+
+    fn next_key(
+        &self,
+        table: &'static str,
+        from: Bound<&[u8]>,
+    ) -> Result<Option<Vec<u8>>, DatabaseError> {
+        let Some((mut cursor, _)) = self.cursor(table)? else { return Ok(None) };
+
+        let entry = match from {
+            Bound::Unbounded => cursor.first::<Vec<u8>, ()>(),
+            Bound::Included(key) => cursor.set_range(key),
+            Bound::Excluded(key) => match cursor.set_range::<Vec<u8>, ()>(key) {
+                Ok(Some((found, _))) if found == key => cursor.next_nodup(),
+                entry => entry,
+            },
+        }
+        .map_err(|e| DatabaseError::Read(e.into()))?;
+        Ok(entry.map(|(key, _)| key))
+    }
+
+    fn prev_key(
+        &self,
+        table: &'static str,
+        to: Bound<&[u8]>,
+    ) -> Result<Option<Vec<u8>>, DatabaseError> {
+        let Some((mut cursor, _)) = self.cursor(table)? else { return Ok(None) };
+
+        let entry = match to {
+            Bound::Unbounded => cursor.last::<Vec<u8>, ()>(),
+            Bound::Included(key) | Bound::Excluded(key) => {
+                match cursor.set_range::<Vec<u8>, ()>(key) {
+                    Ok(Some((found, _))) if found == key && matches!(to, Bound::Included(_)) => {
+                        Ok(Some((found, ())))
+                    }
+                    Ok(Some(_)) => cursor.prev_nodup(),
+                    // all keys are smaller than `key`
+                    Ok(None) => cursor.last(),
+                    Err(err) => Err(err),
+                }
+            }
+        }
+        .map_err(|e| DatabaseError::Read(e.into()))?;
+        Ok(entry.map(|(key, _)| key))
+    }
+
+    fn entries(&self, table: &'static str) -> Result<usize, DatabaseError> {
+        let Some((dbi, _)) = self.table(table)? else { return Ok(0) };
+        let stat = self.txn.db_stat_with_dbi(dbi).map_err(|e| DatabaseError::Stats(e.into()))?;
+        Ok(stat.entries())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO},
+        database::Database,
+        init_db, open_db_overlay, open_db_read_only,
+        tables::{CanonicalHeaders, PlainStorageState},
+        transaction::{DbTx, DbTxMut},
+    };
+    use reth_primitives::{Address, StorageEntry, H256, U256};
+
+    #[test]
+    fn overlay_writes_stay_in_memory() {
+        let path = tempfile::TempDir::new().unwrap();
+        let address = Address::from_low_u64_be(1);
+        let entry =
+            |key: u64| StorageEntry { key: H256::from_low_u64_be(key), value: U256::from(key) };
+        let header = |block: u64| (block, H256::from_low_u64_be(block));
+
+        {
+            let db = init_db(path.path(), None).unwrap();
+            let tx = db.tx_mut().unwrap();
+            for block in 1..=3 {
+                tx.put::<CanonicalHeaders>(block, H256::from_low_u64_be(block)).unwrap();
+            }
+            tx.put::<PlainStorageState>(address, entry(1)).unwrap();
+            tx.commit().unwrap();
+        }
+
+        let overlay = open_db_overlay(path.path(), None).unwrap();
+        let tx = overlay.tx_mut().unwrap();
+        // reads fall through to the environment
+        assert_eq!(tx.get::<CanonicalHeaders>(2), Ok(Some(H256::from_low_u64_be(2))));
+        tx.put::<CanonicalHeaders>(2, H256::zero()).unwrap();
+        tx.put::<CanonicalHeaders>(4, H256::from_low_u64_be(4)).unwrap();
+        tx.delete::<CanonicalHeaders>(1, None).unwrap();
+        tx.put::<PlainStorageState>(address, entry(2)).unwrap();
+        tx.commit().unwrap();
+
+        // reads see the writes of the overlay
+        let tx = overlay.tx().unwrap();
+        let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+        let entries = cursor.walk(None).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(entries, vec![(2, H256::zero()), header(3), header(4)]);
+        let mut cursor = tx.cursor_dup_read::<PlainStorageState>().unwrap();
+        let values = cursor.walk_dup(Some(address), None).unwrap().collect::<Result<Vec<_>, _>>();
+        assert_eq!(values.unwrap(), vec![(address, entry(1)), (address, entry(2))]);
+        drop(cursor);
+        tx.commit().unwrap();
+        drop(overlay);
+
+        // the environment is untouched
+        let db = open_db_read_only(path.path(), None).unwrap();
+        let tx = db.tx().unwrap();
+        let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+        let entries = cursor.walk(None).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(entries, vec![header(1), header(2), header(3)]);
+        assert_eq!(tx.entries::<PlainStorageState>(), Ok(1));
+    }
+
+    #[test]
+    fn overlay_merges_writes_with_environment() {
+        let path = tempfile::TempDir::new().unwrap();
+        let address = Address::from_low_u64_be(1);
+        let entry =
+            |key: u64| StorageEntry { key: H256::from_low_u64_be(key), value: U256::from(key) };
+        let header = |block: u64| (block, H256::from_low_u64_be(block));
+
+        {
+            let db = init_db(path.path(), None).unwrap();
+            let tx = db.tx_mut().unwrap();
+            for block in [1, 3, 5, 7] {
+                tx.put::<CanonicalHeaders>(block, H256::from_low_u64_be(block)).unwrap();
+            }
+            for key in [1, 3] {
+                tx.put::<PlainStorageState>(address, entry(key)).unwrap();
+            }
+            tx.commit().unwrap();
+        }
+
+        let overlay = open_db_overlay(path.path(), None).unwrap();
+        let tx = overlay.tx_mut().unwrap();
+        tx.delete::<CanonicalHeaders>(3, None).unwrap();
+        tx.delete::<CanonicalHeaders>(5, None).unwrap();
+        tx.put::<CanonicalHeaders>(4, H256::from_low_u64_be(4)).unwrap();
+        tx.delete::<PlainStorageState>(address, Some(entry(1))).unwrap();
+        tx.put::<PlainStorageState>(address, entry(2)).unwrap();
+        tx.commit().unwrap();
+
+        let tx = overlay.tx_mut().unwrap();
+        let mut cursor = tx.cursor_write::<CanonicalHeaders>().unwrap();
+        let walked = cursor.walk(None).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(walked, vec![header(1), header(4), header(7)]);
+        let walked = cursor.walk_back(None).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(walked, vec![header(7), header(4), header(1)]);
+        // deleted keys are skipped in both directions
+        assert_eq!(cursor.seek(2), Ok(Some(header(4))));
+        assert_eq!(cursor.seek(5), Ok(Some(header(7))));
+        assert_eq!(cursor.prev(), Ok(Some(header(4))));
+        assert_eq!(cursor.seek_exact(3), Ok(None));
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(3));
+        assert!(cursor.insert(7, H256::zero()).is_err());
+        cursor.insert(3, H256::zero()).unwrap();
+        assert_eq!(tx.get::<CanonicalHeaders>(3), Ok(Some(H256::zero())));
+
+        let mut cursor = tx.cursor_dup_read::<PlainStorageState>().unwrap();
+        let values = cursor.walk_dup(Some(address), None).unwrap().collect::<Result<Vec<_>, _>>();
+        assert_eq!(values.unwrap(), vec![(address, entry(2)), (address, entry(3))]);
+        assert_eq!(tx.entries::<PlainStorageState>(), Ok(2));
+
+        // clearing a table hides all entries of the environment
+        tx.clear::<CanonicalHeaders>().unwrap();
+        tx.put::<CanonicalHeaders>(2, H256::from_low_u64_be(2)).unwrap();
+        let mut cursor = tx.cursor_read::<CanonicalHeaders>().unwrap();
+        let walked = cursor.walk(None).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(walked, vec![header(2)]);
+        assert_eq!(tx.entries::<CanonicalHeaders>(), Ok(1));
+    }
+}
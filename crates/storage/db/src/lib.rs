@@ -83,13 +83,20 @@ pub mod mdbx {
     pub use reth_libmdbx::*;
 }
 
+/// In-memory database, which is also the write layer of [OverlayEnv].
+pub mod memory {
+    pub use crate::implementation::memory::{MemoryCursor, MemoryDatabase, MemoryTx};
+}
+
 pub use abstraction::*;
+#[cfg(feature = "mdbx")]
+pub use implementation::overlay::OverlayEnv;
 pub use reth_interfaces::db::{DatabaseError, DatabaseWriteOperation};
 pub use tables::*;
 pub use utils::is_database_empty;
 
 #[cfg(feature = "mdbx")]
-use mdbx::{Env, EnvKind, NoWriteMap, WriteMap};
+use mdbx::{DbOpts, Env, EnvKind, NoWriteMap, WriteMap};
 
 #[cfg(feature = "mdbx")]
 /// Alias type for the database environment in use. Read/Write mode.
@@ -106,9 +113,36 @@ use std::path::Path;
 /// Opens up an existing database or creates a new one at the specified path. Creates tables if
 /// necessary. Read/Write mode.
 pub fn init_db<P: AsRef<Path>>(path: P, log_level: Option<LogLevel>) -> eyre::Result<DatabaseEnv> {
+    init_db_with_tables(path, log_level, &[])
+}
+
+/// Opens up an existing database or creates a new one at the specified path, like [init_db].
+///
+/// In addition to the default [Tables], this creates the given auxiliary `tables` if necessary,
+/// which allows downstream crates to store their own data in the same environment.
+pub fn init_db_with_tables<P: AsRef<Path>>(
+    path: P,
+    log_level: Option<LogLevel>,
+    tables: &[TableDef],
+) -> eyre::Result<DatabaseEnv> {
+    init_db_inner(path.as_ref(), DbOpts::default().with_log_level(log_level), tables)
+}
+
+/// Opens up an existing database or creates a new one at the specified path with the given
+/// [DbOpts], like [init_db].
+///
+/// Options that only apply at creation time, like the page size, are ignored if the database
+/// already exists.
+///
+/// If [DbOpts::expected_genesis] is set and the database already contains a genesis header, this
+/// returns [DatabaseError::GenesisMismatch] if the hash of that header doesn't match.
+pub fn init_db_with_opts<P: AsRef<Path>>(path: P, opts: DbOpts) -> eyre::Result<DatabaseEnv> {
+    init_db_inner(path.as_ref(), opts, &[])
+}
+
+fn init_db_inner(rpath: &Path, opts: DbOpts, tables: &[TableDef]) -> eyre::Result<DatabaseEnv> {
     use crate::version::{check_db_version_file, create_db_version_file, DatabaseVersionError};
 
-    let rpath = path.as_ref();
     if is_database_empty(rpath) {
         std::fs::create_dir_all(rpath)
             .wrap_err_with(|| format!("Could not create database directory {}", rpath.display()))?;
@@ -122,8 +156,17 @@ pub fn init_db<P: AsRef<Path>>(path: P, log_level: Option<LogLevel>) -> eyre::Re
     }
     #[cfg(feature = "mdbx")]
     {
-        let db = DatabaseEnv::open(rpath, EnvKind::RW, log_level)?;
-        db.create_tables()?;
+        use crate::{database::Database, transaction::DbTx};
+
+        let expected_genesis = opts.expected_genesis;
+        let db = DatabaseEnv::open_with_opts(rpath, EnvKind::RW, opts)?;
+        db.create_tables_with(tables)?;
+        if let Some(expected) = expected_genesis {
+            let found = db.view(|tx| tx.get::<tables::CanonicalHeaders>(0))??;
+            if let Some(found) = found.filter(|found| *found != expected) {
+                return Err(DatabaseError::GenesisMismatch { expected, found }.into())
+            }
+        }
         Ok(db)
     }
     #[cfg(not(feature = "mdbx"))]
@@ -145,6 +188,16 @@ pub fn open_db_read_only(path: &Path, log_level: Option<LogLevel>) -> eyre::Resu
     }
 }
 
+/// Opens up an existing database read-only, with an in-memory layer that all writes are applied to.
+///
+/// The database is never modified, the writes are discarded once the returned [OverlayEnv] is
+/// dropped. This allows experimenting with writes against a production database without risking
+/// it.
+#[cfg(feature = "mdbx")]
+pub fn open_db_overlay(path: &Path, log_level: Option<LogLevel>) -> eyre::Result<OverlayEnv> {
+    Ok(OverlayEnv::new(open_db_read_only(path, log_level)?))
+}
+
 /// Opens up an existing database. Read/Write mode. It doesn't create it or create tables if
 /// missing.
 pub fn open_db(path: &Path, log_level: Option<LogLevel>) -> eyre::Result<DatabaseEnv> {
@@ -163,8 +216,11 @@ pub fn open_db(path: &Path, log_level: Option<LogLevel>) -> eyre::Result<Databas
 #[cfg(any(test, feature = "test-utils"))]
 pub mod test_utils {
     use super::*;
+    use crate::table::{Compress, Decode, Decompress, Encode, Table};
     use std::sync::Arc;
 
+    pub use crate::implementation::memory::{MemoryCursor, MemoryDatabase, MemoryTx};
+
     /// Error during database open
     pub const ERROR_DB_OPEN: &str = "Not able to open the database file.";
     /// Error during database creation
@@ -187,6 +243,13 @@ pub mod test_utils {
         Arc::new(init_db(path.as_ref(), None).expect(ERROR_DB_CREATION))
     }
 
+    /// Create in-memory read/write database for testing
+    ///
+    /// Unlike [create_test_rw_db], this does not open an MDBX environment.
+    pub fn create_test_memory_db() -> Arc<MemoryDatabase> {
+        Arc::new(MemoryDatabase::new())
+    }
+
     /// Create read only database for testing
     pub fn create_test_ro_db() -> Arc<DatabaseEnvRO> {
         let path = tempfile::TempDir::new().expect(ERROR_TEMPDIR).into_path();
@@ -195,17 +258,126 @@ pub mod test_utils {
         }
         Arc::new(open_db_read_only(path.as_path(), None).expect(ERROR_DB_OPEN))
     }
+
+    /// Asserts that the `key` and the `value` of table `T` survive a round-trip through their
+    /// database encoding.
+    ///
+    /// The key is encoded and decoded, and the value is compressed and decompressed. New tables
+    /// should be tested with this, and with [assert_table_key_order] if the key has a custom
+    /// encoding.
+    pub fn assert_table_roundtrip<T: Table>(key: T::Key, value: T::Value)
+    where
+        T::Value: Clone + PartialEq,
+    {
+        let decoded = T::Key::decode(key.clone().encode()).expect("failed to decode key");
+        assert_eq!(decoded, key, "key of table {} does not round-trip", T::NAME);
+
+        let decompressed =
+            T::Value::decompress(value.clone().compress()).expect("failed to decompress value");
+        assert_eq!(decompressed, value, "value of table {} does not round-trip", T::NAME);
+    }
+
+    /// Asserts that the encodings of the keys `a` and `b` of table `T` are ordered like the keys.
+    ///
+    /// The database orders entries by their encoded keys, so cursors don't walk a table in key
+    /// order if the encoding doesn't preserve [Ord].
+    pub fn assert_table_key_order<T: Table>(a: T::Key, b: T::Key) {
+        let expected = a.cmp(&b);
+        let (encoded_a, encoded_b) = (a.clone().encode(), b.clone().encode());
+        assert_eq!(
+            encoded_a.as_ref().cmp(encoded_b.as_ref()),
+            expected,
+            "encoded keys {a:?} and {b:?} of table {} are not ordered like the keys",
+            T::NAME
+        );
+    }
+
+    /// Generates property tests for the codecs of a table.
+    ///
+    /// Given a [Table], a name and a proptest strategy that generates `(key, value)` pairs of the
+    /// table, this generates:
+    /// - `<name>_roundtrip`, which checks every pair with [assert_table_roundtrip].
+    /// - `<name>_key_order`, which checks a batch of keys with [assert_table_key_order].
+    ///
+    /// ```ignore
+    /// reth_db::table_codec_suite!(
+    ///     CanonicalHeaders,
+    ///     canonical_headers,
+    ///     (any::<u64>(), any::<H256>())
+    /// );
+    /// ```
+    #[macro_export]
+    macro_rules! table_codec_suite {
+        ($table:ty, $name:ident, $strategy:expr) => {
+            $crate::test_utils::paste::paste! {
+                $crate::test_utils::proptest::proptest! {
+                    #[test]
+                    fn [<$name _roundtrip>]((key, value) in $strategy) {
+                        $crate::test_utils::assert_table_roundtrip::<$table>(key, value);
+                    }
+
+                    #[test]
+                    fn [<$name _key_order>](
+                        entries in $crate::test_utils::proptest::collection::vec($strategy, 2..64)
+                    ) {
+                        for pair in entries.windows(2) {
+                            $crate::test_utils::assert_table_key_order::<$table>(
+                                pair[0].0.clone(),
+                                pair[1].0.clone(),
+                            );
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    #[doc(hidden)]
+    pub use paste;
+    #[doc(hidden)]
+    pub use proptest;
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        init_db,
+        database::Database,
+        init_db, init_db_with_opts,
+        mdbx::DbOpts,
+        tables::CanonicalHeaders,
+        transaction::DbTxMut,
         version::{db_version_file_path, DatabaseVersionError},
+        DatabaseError,
     };
     use assert_matches::assert_matches;
+    use reth_primitives::H256;
     use tempfile::tempdir;
 
+    #[test]
+    fn db_genesis_mismatch() {
+        let path = tempdir().unwrap();
+        let (genesis, other) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+
+        // nothing to check in an empty database
+        let db = init_db_with_opts(&path, DbOpts::default().with_expected_genesis(other)).unwrap();
+        db.update(|tx| tx.put::<CanonicalHeaders>(0, genesis)).unwrap().unwrap();
+        drop(db);
+
+        let db = init_db_with_opts(&path, DbOpts::default().with_expected_genesis(other));
+        assert_matches!(
+            db.unwrap_err().downcast_ref::<DatabaseError>(),
+            Some(DatabaseError::GenesisMismatch { expected, found })
+                if *expected == other && *found == genesis
+        );
+
+        assert_matches!(
+            init_db_with_opts(&path, DbOpts::default().with_expected_genesis(genesis)),
+            Ok(_)
+        );
+        // no expected genesis preserves the default behavior
+        assert_matches!(init_db_with_opts(&path, DbOpts::default()), Ok(_));
+    }
+
     #[test]
     fn db_version() {
         let path = tempdir().unwrap();
@@ -50,6 +50,30 @@ pub enum TableType {
     DupSort,
 }
 
+/// Definition of an auxiliary table that is not part of [Tables].
+///
+/// Auxiliary tables are created next to the default tables by
+/// [init_db_with_tables](crate::init_db_with_tables).
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct TableDef {
+    /// The name of the table in database.
+    pub name: &'static str,
+    /// The type of the table.
+    pub table_type: TableType,
+}
+
+impl TableDef {
+    /// Creates the definition of a key value table.
+    pub const fn table(name: &'static str) -> Self {
+        Self { name, table_type: TableType::Table }
+    }
+
+    /// Creates the definition of a duplicate key value table.
+    pub const fn dup_sort(name: &'static str) -> Self {
+        Self { name, table_type: TableType::DupSort }
+    }
+}
+
 /// Number of tables that should be present inside database.
 pub const NUM_TABLES: usize = 26;
 
@@ -218,6 +242,8 @@ macro_rules! table {
     };
 }
 
+pub(crate) use table;
+
 #[macro_export]
 /// Macro to declare duplicate key value table.
 macro_rules! dupsort {
@@ -421,6 +447,14 @@ table!(
     ( PruneCheckpoints ) PrunePart | PruneCheckpoint
 );
 
+table!(
+    /// Stores the key `Env::recompress_table` continues at for each table that is being
+    /// recompressed.
+    ///
+    /// This is an auxiliary table that is created by the first recompression.
+    ( RecompressProgress ) String | Vec<u8>
+);
+
 /// Alias Types
 
 /// List with transaction numbers.
@@ -432,7 +466,14 @@ pub type StageId = String;
 mod tests {
     use std::str::FromStr;
 
-    use crate::*;
+    use crate::{
+        models::ShardedKey,
+        table_codec_suite,
+        test_utils::{assert_table_key_order, assert_table_roundtrip},
+        *,
+    };
+    use proptest::prelude::*;
+    use reth_primitives::{Account, Address, H256, U256};
 
     const TABLES: [(TableType, &str); NUM_TABLES] = [
         (TableType::Table, CanonicalHeaders::const_name()),
@@ -473,4 +514,44 @@ mod tests {
             assert_eq!(table.name(), table_name);
         }
     }
+
+    #[test]
+    fn table_roundtrip() {
+        let address = Address::from_low_u64_be(1);
+        let account =
+            Account { nonce: 1, balance: U256::from(2), bytecode_hash: Some(H256::random()) };
+        assert_table_roundtrip::<PlainAccountState>(address, account);
+        assert_table_roundtrip::<TxHashNumber>(H256::random(), 42);
+        assert_table_roundtrip::<AccountHistory>(
+            ShardedKey::new(address, 10),
+            BlockNumberList::new([1, 5, 10]).unwrap(),
+        );
+
+        assert_table_key_order::<CanonicalHeaders>(1, 256);
+        assert_table_key_order::<AccountHistory>(
+            ShardedKey::new(address, 10),
+            ShardedKey::new(address, 256),
+        );
+        assert_table_key_order::<AccountHistory>(
+            ShardedKey::new(address, u64::MAX),
+            ShardedKey::new(Address::from_low_u64_be(2), 0),
+        );
+    }
+
+    table_codec_suite!(CanonicalHeaders, canonical_headers, (any::<u64>(), any::<H256>()));
+    table_codec_suite!(
+        PlainAccountState,
+        plain_account_state,
+        (any::<Address>(), any::<Account>())
+    );
+    table_codec_suite!(
+        AccountHistory,
+        account_history,
+        (
+            (any::<Address>(), any::<u64>())
+                .prop_map(|(address, block)| ShardedKey::new(address, block)),
+            proptest::collection::btree_set(0..u32::MAX as usize, 1..32)
+                .prop_map(|blocks| BlockNumberList::new_pre_sorted(Vec::from_iter(blocks)))
+        )
+    );
 }
@@ -57,6 +57,19 @@ pub fn get_db_version<P: AsRef<Path>>(db_path: P) -> Result<u64, DatabaseVersion
     }
 }
 
+/// Reads the database version from the file with [DB_VERSION_FILE_NAME] name without opening the
+/// database.
+///
+/// Returns `None` if the file is missing, so callers can decide whether to migrate or warn before
+/// touching the data.
+pub fn read_db_version<P: AsRef<Path>>(db_path: P) -> Result<Option<u64>, DatabaseVersionError> {
+    match get_db_version(db_path) {
+        Ok(version) => Ok(Some(version)),
+        Err(DatabaseVersionError::MissingFile) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
 /// Creates a database version file with [DB_VERSION_FILE_NAME] name containing [DB_VERSION] string.
 ///
 /// This function will create a file if it does not exist,
@@ -72,7 +85,10 @@ pub fn db_version_file_path<P: AsRef<Path>>(db_path: P) -> PathBuf {
 
 #[cfg(test)]
 mod tests {
-    use super::{check_db_version_file, db_version_file_path, DatabaseVersionError};
+    use super::{
+        check_db_version_file, create_db_version_file, db_version_file_path, read_db_version,
+        DatabaseVersionError, DB_VERSION,
+    };
     use assert_matches::assert_matches;
     use std::fs;
     use tempfile::tempdir;
@@ -102,4 +118,19 @@ mod tests {
         let result = check_db_version_file(&dir);
         assert_matches!(result, Err(DatabaseVersionError::VersionMismatch { version: 0 }));
     }
+
+    #[test]
+    fn read_version() {
+        let dir = tempdir().unwrap();
+        assert_matches!(read_db_version(&dir), Ok(None));
+
+        fs::write(db_version_file_path(&dir), "invalid-version").unwrap();
+        assert_matches!(read_db_version(&dir), Err(DatabaseVersionError::MalformedFile));
+
+        fs::write(db_version_file_path(&dir), "0").unwrap();
+        assert_matches!(read_db_version(&dir), Ok(Some(0)));
+
+        create_db_version_file(&dir).unwrap();
+        assert_matches!(read_db_version(&dir), Ok(Some(DB_VERSION)));
+    }
 }
@@ -64,6 +64,34 @@ where
         self.cursor
     }
 
+    /// Consumes the cursor and returns the underlying MDBX cursor without closing it.
+    ///
+    /// The returned pointer must either be rebound with [Cursor::from_raw] or closed with
+    /// `mdbx_cursor_close`, otherwise it is leaked.
+    pub fn into_raw(self) -> *mut ffi::MDBX_cursor {
+        let this = mem::ManuallyDrop::new(self);
+        // SAFETY: `this` is never dropped, so the transaction handle is only released here.
+        drop(unsafe { ptr::read(&this.txn) });
+        this.cursor
+    }
+
+    /// Rebinds a cursor previously released with [Cursor::into_raw] to the given transaction.
+    ///
+    /// Renewing resets the cursor, so it is unpositioned just like a freshly opened one. On error
+    /// the caller retains ownership of `cursor`.
+    ///
+    /// # Safety
+    ///
+    /// `cursor` must be a valid MDBX cursor that is not owned by any other [Cursor].
+    pub unsafe fn from_raw<E: EnvironmentKind>(
+        txn: &'txn Transaction<K, E>,
+        cursor: *mut ffi::MDBX_cursor,
+    ) -> Result<Self> {
+        let txn = txn.txn_mutex();
+        mdbx_result(txn_execute(&txn, |txn| ffi::mdbx_cursor_renew(txn, cursor)))?;
+        Ok(Self { txn, cursor, _marker: PhantomData })
+    }
+
     /// Retrieves a key/data pair from the cursor. Depending on the cursor op,
     /// the current key may be returned.
     fn get<Key, Value>(
@@ -184,6 +212,17 @@ where
         self.get_value(None, None, MDBX_GET_MULTIPLE)
     }
 
+    /// Returns the number of duplicate data items for the current key.
+    ///
+    /// Databases without [DatabaseFlags::DUP_SORT] always report one item for a positioned cursor.
+    pub fn count(&self) -> Result<usize> {
+        let mut count = 0;
+        mdbx_result(unsafe {
+            txn_execute(&self.txn, |_| ffi::mdbx_cursor_count(self.cursor, &mut count))
+        })?;
+        Ok(count)
+    }
+
     /// Position at last key/data item.
     pub fn last<Key, Value>(&mut self) -> Result<Option<(Key, Value)>>
     where
@@ -6,15 +6,16 @@ use crate::{
     Mode, Transaction, TransactionKind,
 };
 use byteorder::{ByteOrder, NativeEndian};
+use libc::{c_char, c_int};
 use mem::size_of;
 use std::{
-    ffi::CString,
+    ffi::{CStr, CString},
     fmt,
     fmt::Debug,
     marker::PhantomData,
     mem,
     ops::{Bound, RangeBounds},
-    path::Path,
+    path::{Path, PathBuf},
     ptr, result,
     sync::mpsc::{sync_channel, SyncSender},
     thread::sleep,
@@ -103,6 +104,108 @@ where
         self.env
     }
 
+    /// Returns `true` if the environment was opened in read-write mode.
+    pub fn is_read_write(&self) -> bool {
+        self.txn_manager.is_some()
+    }
+
+    /// Returns the path of the environment, as it was passed when opening it.
+    pub fn path(&self) -> Result<PathBuf> {
+        let mut path: *const c_char = ptr::null();
+        unsafe {
+            mdbx_result(ffi::mdbx_env_get_path(self.env(), &mut path))?;
+            Ok(PathBuf::from(CStr::from_ptr(path).to_string_lossy().into_owned()))
+        }
+    }
+
+    /// Copies the environment to the file at `dest`.
+    ///
+    /// The destination file must not exist yet, but its parent directory must. If `compact` is
+    /// set, free pages are omitted and all pages are renumbered sequentially in the copy.
+    ///
+    /// Note: This uses a read-only transaction, so it can cause the environment to grow if run in
+    /// parallel with write transactions.
+    pub fn copy(&self, dest: &Path, compact: bool) -> Result<()> {
+        let dest = CString::new(path_to_bytes(dest)).map_err(|_| Error::Invalid)?;
+        let flags = if compact { ffi::MDBX_CP_COMPACT } else { ffi::MDBX_CP_DEFAULTS };
+        mdbx_result(unsafe { ffi::mdbx_env_copy(self.env(), dest.as_ptr(), flags) })?;
+        Ok(())
+    }
+
+    /// Copies the environment to the file at `dest` like [Environment::copy], reporting the number
+    /// of bytes copied so far to `progress` after every chunk.
+    ///
+    /// If `progress` returns [`ControlFlow::Break`](std::ops::ControlFlow::Break), the copy is
+    /// aborted and the partially written file is removed. Returns `false` if the copy was
+    /// aborted. The environment itself is only read, so aborting never affects it.
+    #[cfg(unix)]
+    pub fn copy_with_progress<F>(&self, dest: &Path, compact: bool, mut progress: F) -> Result<bool>
+    where
+        F: FnMut(u64) -> std::ops::ControlFlow<()>,
+    {
+        use std::{
+            fs::{File, OpenOptions},
+            io::{Read, Write},
+            os::unix::io::{AsRawFd, FromRawFd},
+        };
+
+        let io_err = |err: std::io::Error| Error::Other(err.raw_os_error().unwrap_or(libc::EIO));
+
+        let mut file =
+            OpenOptions::new().write(true).create_new(true).open(dest).map_err(io_err)?;
+
+        // the copy is written to a pipe by a separate thread and forwarded to the file here, which
+        // allows tracking its progress and aborting it by closing the read end of the pipe
+        let mut fds = [0 as c_int; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            let _ = std::fs::remove_file(dest);
+            return Err(io_err(std::io::Error::last_os_error()))
+        }
+        let mut reader = unsafe { File::from_raw_fd(fds[0]) };
+        let writer = unsafe { File::from_raw_fd(fds[1]) };
+
+        let flags = if compact { ffi::MDBX_CP_COMPACT } else { ffi::MDBX_CP_DEFAULTS };
+        let result = std::thread::scope(|scope| {
+            let copy = scope.spawn(move || {
+                // the writer is closed once the copy is done, which ends the forwarding below
+                mdbx_result(unsafe { ffi::mdbx_env_copy2fd(self.env(), writer.as_raw_fd(), flags) })
+            });
+
+            let mut forwarded = (|| -> Result<bool> {
+                let mut buf = vec![0u8; 1024 * 1024];
+                let mut copied = 0u64;
+                loop {
+                    let read = reader.read(&mut buf).map_err(io_err)?;
+                    if read == 0 {
+                        return Ok(true)
+                    }
+                    file.write_all(&buf[..read]).map_err(io_err)?;
+                    copied += read as u64;
+                    if progress(copied).is_break() {
+                        return Ok(false)
+                    }
+                }
+            })();
+            // closing the read end makes the copy fail if it was aborted
+            drop(reader);
+
+            let copied = copy.join().expect("copy thread panicked");
+            if let Ok(true) = forwarded {
+                forwarded = copied.map(|_| true);
+            }
+            forwarded
+        });
+
+        match result.and_then(|completed| file.sync_all().map(|_| completed).map_err(io_err)) {
+            Ok(true) => Ok(true),
+            result => {
+                drop(file);
+                let _ = std::fs::remove_file(dest);
+                result
+            }
+        }
+    }
+
     /// Create a read-only transaction for use with the environment.
     pub fn begin_ro_txn(&self) -> Result<Transaction<'_, RO, E>> {
         Transaction::new(self)
@@ -137,6 +240,25 @@ where
         mdbx_result(unsafe { ffi::mdbx_env_sync_ex(self.env(), force, false) })
     }
 
+    /// Flush the environment data buffers to disk without waiting for other threads.
+    ///
+    /// Returns [Error::Busy] instead of blocking if the environment is locked by a write
+    /// transaction.
+    pub fn try_sync(&self, force: bool) -> Result<bool> {
+        mdbx_result(unsafe { ffi::mdbx_env_sync_ex(self.env(), force, true) })
+    }
+
+    /// Clears the reader slots of dead processes and threads, returning the number of cleared
+    /// slots.
+    ///
+    /// Stale readers keep their snapshot's pages from being reused, which makes the environment
+    /// grow.
+    pub fn reader_check(&self) -> Result<usize> {
+        let mut dead: c_int = 0;
+        mdbx_result(unsafe { ffi::mdbx_reader_check(self.env(), &mut dead) })?;
+        Ok(dead as usize)
+    }
+
     /// Retrieves statistics about this environment.
     pub fn stat(&self) -> Result<Stat> {
         unsafe {
@@ -211,6 +333,20 @@ where
     }
 }
 
+#[cfg(unix)]
+fn path_to_bytes<P: AsRef<Path>>(path: P) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_ref().as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(windows)]
+fn path_to_bytes<P: AsRef<Path>>(path: P) -> Vec<u8> {
+    // On Windows, could use std::os::windows::ffi::OsStrExt to encode_wide(),
+    // but we end up with a Vec<u16> instead of a Vec<u8>, so that doesn't
+    // really help.
+    path.as_ref().to_string_lossy().to_string().into_bytes()
+}
+
 /// Environment statistics.
 ///
 /// Contains information about the size and layout of an MDBX environment or database.
@@ -274,6 +410,26 @@ impl GeometryInfo {
     pub fn min(&self) -> u64 {
         self.0.lower
     }
+
+    /// Upper limit for the datafile size.
+    pub fn max(&self) -> u64 {
+        self.0.upper
+    }
+
+    /// Current datafile size.
+    pub fn current(&self) -> u64 {
+        self.0.current
+    }
+
+    /// Shrink threshold for the datafile.
+    pub fn shrink(&self) -> u64 {
+        self.0.shrink
+    }
+
+    /// Growth step for the datafile.
+    pub fn grow(&self) -> u64 {
+        self.0.grow
+    }
 }
 
 /// Environment information.
@@ -466,20 +622,6 @@ where
                     ))?;
                 }
 
-                #[cfg(unix)]
-                fn path_to_bytes<P: AsRef<Path>>(path: P) -> Vec<u8> {
-                    use std::os::unix::ffi::OsStrExt;
-                    path.as_ref().as_os_str().as_bytes().to_vec()
-                }
-
-                #[cfg(windows)]
-                fn path_to_bytes<P: AsRef<Path>>(path: P) -> Vec<u8> {
-                    // On Windows, could use std::os::windows::ffi::OsStrExt to encode_wide(),
-                    // but we end up with a Vec<u16> instead of a Vec<u8>, so that doesn't
-                    // really help.
-                    path.as_ref().to_string_lossy().to_string().into_bytes()
-                }
-
                 let path = match CString::new(path_to_bytes(path)) {
                     Ok(path) => path,
                     Err(_) => return Err(Error::Invalid),
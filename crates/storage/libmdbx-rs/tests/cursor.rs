@@ -336,3 +336,23 @@ fn test_put_del() {
         (Cow::Borrowed(b"key2" as &[u8]), Cow::Borrowed(b"val2" as &[u8]))
     );
 }
+
+#[test]
+fn test_cursor_into_raw_renew() {
+    let dir = tempdir().unwrap();
+    let env = Environment::new().open(dir.path()).unwrap();
+
+    let txn = env.begin_rw_txn().unwrap();
+    let db = txn.open_db(None).unwrap();
+    txn.put(db.dbi(), b"key1", b"val1", WriteFlags::empty()).unwrap();
+    txn.put(db.dbi(), b"key2", b"val2", WriteFlags::empty()).unwrap();
+
+    let mut cursor = txn.cursor(&db).unwrap();
+    assert_eq!(cursor.last().unwrap(), Some((*b"key2", *b"val2")));
+    let raw = cursor.into_raw();
+
+    // renewing resets the position, so `next` starts from the beginning again
+    let mut cursor = unsafe { Cursor::from_raw(&txn, raw) }.unwrap();
+    assert_eq!(cursor.get_current::<(), ()>().unwrap(), None);
+    assert_eq!(cursor.next().unwrap(), Some((*b"key1", *b"val1")));
+}
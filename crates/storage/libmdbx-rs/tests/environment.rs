@@ -156,3 +156,29 @@ fn test_freelist() {
     freelist = env.freelist().unwrap();
     assert!(freelist > 0);
 }
+
+#[test]
+fn test_copy() {
+    let dir = tempdir().unwrap();
+    let env = Environment::new().open(dir.path()).unwrap();
+    assert_eq!(env.path().unwrap(), dir.path());
+
+    // Write a few small values.
+    for i in 0..64 {
+        let mut value = [0u8; 8];
+        LittleEndian::write_u64(&mut value, i);
+        let tx = env.begin_rw_txn().expect("begin_rw_txn");
+        tx.put(tx.open_db(None).unwrap().dbi(), value, value, WriteFlags::default())
+            .expect("tx.put");
+        tx.commit().expect("tx.commit");
+    }
+
+    let copy_dir = tempdir().unwrap();
+    env.copy(&copy_dir.path().join("mdbx.dat"), true).unwrap();
+
+    // copying to an existing file should fail
+    assert!(env.copy(&copy_dir.path().join("mdbx.dat"), true).is_err());
+
+    let copy = Environment::new().open(copy_dir.path()).unwrap();
+    assert_eq!(copy.stat().unwrap().entries(), 64);
+}
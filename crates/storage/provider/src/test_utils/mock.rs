@@ -14,7 +14,10 @@ use reth_primitives::{
     SealedHeader, StorageKey, StorageValue, TransactionMeta, TransactionSigned,
     TransactionSignedNoHash, TxHash, TxNumber, H256, U256,
 };
-use reth_revm_primitives::primitives::{BlockEnv, CfgEnv};
+use reth_revm_primitives::{
+    env::fill_cfg_and_block_env,
+    primitives::{BlockEnv, CfgEnv},
+};
 use std::{
     collections::{BTreeMap, HashMap},
     ops::RangeBounds,
@@ -431,43 +434,60 @@ impl StateProvider for MockEthProvider {
     }
 }
 
+impl MockEthProvider {
+    /// Returns the header at the given block and the total difficulty at that header.
+    fn header_with_td(&self, at: BlockHashOrNumber) -> Result<(Header, U256)> {
+        let hash = self.convert_number(at)?.ok_or(ProviderError::HeaderNotFound(at))?;
+        let header = self.header(&hash)?.ok_or(ProviderError::HeaderNotFound(at))?;
+        let total_difficulty = self.header_td_at(&header)?;
+        Ok((header, total_difficulty))
+    }
+
+    /// Returns the total difficulty at the given header.
+    fn header_td_at(&self, header: &Header) -> Result<U256> {
+        Ok(self
+            .header_td_by_number(header.number)?
+            .ok_or_else(|| ProviderError::HeaderNotFound(header.number.into()))?)
+    }
+}
+
 impl EvmEnvProvider for MockEthProvider {
     fn fill_env_at(
         &self,
-        _cfg: &mut CfgEnv,
-        _block_env: &mut BlockEnv,
-        _at: BlockHashOrNumber,
+        cfg: &mut CfgEnv,
+        block_env: &mut BlockEnv,
+        at: BlockHashOrNumber,
     ) -> Result<()> {
-        unimplemented!()
+        let (header, total_difficulty) = self.header_with_td(at)?;
+        fill_cfg_and_block_env(cfg, block_env, &self.chain_spec, &header, total_difficulty);
+        Ok(())
     }
 
     fn fill_env_with_header(
         &self,
-        _cfg: &mut CfgEnv,
-        _block_env: &mut BlockEnv,
-        _header: &Header,
+        cfg: &mut CfgEnv,
+        block_env: &mut BlockEnv,
+        header: &Header,
     ) -> Result<()> {
-        unimplemented!()
+        let total_difficulty = self.header_td_at(header)?;
+        fill_cfg_and_block_env(cfg, block_env, &self.chain_spec, header, total_difficulty);
+        Ok(())
     }
 
-    fn fill_block_env_at(&self, _block_env: &mut BlockEnv, _at: BlockHashOrNumber) -> Result<()> {
-        unimplemented!()
+    fn fill_block_env_at(&self, block_env: &mut BlockEnv, at: BlockHashOrNumber) -> Result<()> {
+        self.fill_env_at(&mut CfgEnv::default(), block_env, at)
     }
 
-    fn fill_block_env_with_header(
-        &self,
-        _block_env: &mut BlockEnv,
-        _header: &Header,
-    ) -> Result<()> {
-        unimplemented!()
+    fn fill_block_env_with_header(&self, block_env: &mut BlockEnv, header: &Header) -> Result<()> {
+        self.fill_env_with_header(&mut CfgEnv::default(), block_env, header)
     }
 
-    fn fill_cfg_env_at(&self, _cfg: &mut CfgEnv, _at: BlockHashOrNumber) -> Result<()> {
-        unimplemented!()
+    fn fill_cfg_env_at(&self, cfg: &mut CfgEnv, at: BlockHashOrNumber) -> Result<()> {
+        self.fill_env_at(cfg, &mut BlockEnv::default(), at)
     }
 
-    fn fill_cfg_env_with_header(&self, _cfg: &mut CfgEnv, _header: &Header) -> Result<()> {
-        unimplemented!()
+    fn fill_cfg_env_with_header(&self, cfg: &mut CfgEnv, header: &Header) -> Result<()> {
+        self.fill_env_with_header(cfg, &mut BlockEnv::default(), header)
     }
 }
 
@@ -481,11 +501,11 @@ impl StateProviderFactory for MockEthProvider {
     }
 
     fn history_by_block_hash(&self, _block: BlockHash) -> Result<StateProviderBox<'_>> {
-        todo!()
+        Ok(Box::new(self.clone()))
     }
 
     fn state_by_block_hash(&self, _block: BlockHash) -> Result<StateProviderBox<'_>> {
-        todo!()
+        Ok(Box::new(self.clone()))
     }
 
     fn pending(&self) -> Result<StateProviderBox<'_>> {